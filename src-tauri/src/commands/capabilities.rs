@@ -0,0 +1,59 @@
+use crate::domain::{is_supported_input_format, OutputFormat};
+use serde::{Deserialize, Serialize};
+
+/// What the backend can do with a given image format
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FormatCapabilities {
+    pub name: String,
+    pub extension: String,
+    pub mime_type: String,
+    pub input: bool,
+    pub output: bool,
+    pub lossless: bool,
+}
+
+impl From<OutputFormat> for FormatCapabilities {
+    fn from(format: OutputFormat) -> Self {
+        Self {
+            name: format.to_string(),
+            extension: format.extension().to_string(),
+            mime_type: format.mime_type().to_string(),
+            input: is_supported_input_format(format.extension()),
+            output: true,
+            lossless: format.supports_lossless(),
+        }
+    }
+}
+
+/// Formats supported by the backend, so the frontend doesn't hardcode the list
+#[tauri::command]
+pub async fn get_supported_formats() -> Result<Vec<FormatCapabilities>, String> {
+    Ok(supported_formats())
+}
+
+fn supported_formats() -> Vec<FormatCapabilities> {
+    OutputFormat::all()
+        .into_iter()
+        .map(FormatCapabilities::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_webp_capabilities() {
+        let formats = supported_formats();
+        let webp = formats
+            .iter()
+            .find(|format| format.extension == "webp")
+            .unwrap();
+
+        assert!(webp.input);
+        assert!(webp.output);
+        assert!(webp.lossless);
+        assert_eq!(webp.mime_type, "image/webp");
+        assert_eq!(formats.len(), OutputFormat::all().len());
+    }
+}
@@ -1,14 +1,22 @@
-use crate::domain::{validate_image_file, AppState, OutputFormat, SqliteStatsStore, StatsStore};
+use super::stats::stats_db_path;
+use crate::domain::{
+    abandoned_workers, analyze_image, check_free_space, compress_file_to_file_with_timeout,
+    estimate_with_fallback, find_duplicates, is_lossy_encoding, link_or_copy_file, list_presets,
+    mirror_output_dir, plan_batch_waves, preset_by_name, recommend_webp_mode, validate_image_file,
+    wait_for_abandoned_workers, AppState, BatchEstimation, CompressionError, CompressionPreset,
+    CompressionSettings, EstimationQuery, FileEstimation, OutputFormat, PathUtils,
+    SqliteStatsStore, StatsStore,
+};
+use base64::{engine::general_purpose, Engine as _};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Mutex;
-use tauri::{AppHandle, Emitter, State};
+use tauri::{AppHandle, Emitter, Runtime, State};
 
 // Global stats store - same pattern as stats.rs
 static STATS_STORE: std::sync::LazyLock<Mutex<SqliteStatsStore>> = std::sync::LazyLock::new(|| {
-    let db_path = std::env::temp_dir()
-        .join("plume")
-        .join("compression_stats.db");
+    let db_path = stats_db_path();
     std::fs::create_dir_all(db_path.parent().unwrap()).unwrap();
     let store = SqliteStatsStore::new(db_path.to_str().unwrap()).unwrap();
     Mutex::new(store)
@@ -20,23 +28,105 @@ pub struct CompressImageRequest {
     pub quality: Option<u8>,
     pub format: Option<String>,
     pub output_path: Option<String>,
+    #[serde(default)]
+    pub lossless: Option<bool>,
+    #[serde(default)]
+    pub on_conflict: ConflictPolicy,
+    /// Keep already-optimized lossy files instead of re-encoding them
+    #[serde(default)]
+    pub skip_if_no_gain: Option<bool>,
+    /// Keep the original file when the compressed one would be larger
+    #[serde(default)]
+    pub never_grow: Option<bool>,
+    /// Named preset filling the fields left unset
+    #[serde(default)]
+    pub preset: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl CompressImageRequest {
+    /// Fill the unset fields from the preset; explicit values always win
+    fn with_preset_defaults(mut self, preset: &CompressionSettings) -> Self {
+        self.quality = self.quality.or(Some(preset.quality));
+        self.format = self
+            .format
+            .or_else(|| Some(preset.format.extension().to_string()));
+        self.lossless = self.lossless.or(Some(preset.lossless));
+        self.skip_if_no_gain = self.skip_if_no_gain.or(Some(preset.skip_if_no_gain));
+        self.never_grow = self.never_grow.or(Some(preset.never_grow));
+        self
+    }
+}
+
+/// What to do when the output file already exists
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ConflictPolicy {
+    Overwrite,
+    Skip,
+    /// Write to a ` (1)`, ` (2)`... variant, nothing is lost
+    #[default]
+    Rename,
+}
+
+/// Apply the conflict policy to the output path, `None` meaning the file must be skipped
+fn apply_conflict_policy(
+    output_path: std::path::PathBuf,
+    policy: ConflictPolicy,
+) -> Option<std::path::PathBuf> {
+    if !output_path.exists() {
+        return Some(output_path);
+    }
+
+    match policy {
+        ConflictPolicy::Overwrite => Some(output_path),
+        ConflictPolicy::Skip => None,
+        ConflictPolicy::Rename => Some(PathUtils::make_unique_filename(&output_path)),
+    }
+}
+
+/// Response for a file left untouched because its output already exists
+fn skipped_response(image_id: String, output_path: &Path) -> CompressImageResponse {
+    CompressImageResponse {
+        success: true,
+        image_id,
+        output_path: Some(output_path.to_string_lossy().to_string()),
+        result: None,
+        error: None,
+        skipped: true,
+    }
+}
+
+/// Response for a file whose compression failed
+fn failed_response(image_id: String, error: &CompressionError) -> CompressImageResponse {
+    log::error!("Compression of {} failed: {}", image_id, error);
+    CompressImageResponse {
+        success: false,
+        image_id,
+        output_path: None,
+        result: None,
+        error: Some(format!("Compression failed: {}", error)),
+        skipped: false,
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompressionResult {
     pub original_size: u64,
     pub compressed_size: u64,
     pub savings_percent: f64,
     pub output_path: String,
+    #[serde(default)]
+    pub processing_time_ms: u64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompressImageResponse {
     pub success: bool,
     pub image_id: String,
     pub output_path: Option<String>,
     pub result: Option<CompressionResult>,
     pub error: Option<String>,
+    #[serde(default)]
+    pub skipped: bool, // Output already existed and the conflict policy was Skip
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -48,6 +138,72 @@ pub struct CompressionProgressEvent {
     pub estimated_time_remaining: Option<u64>,
 }
 
+/// Progress bar share between the file-read (25%) and encode stages covered by decoding
+const DECODE_PROGRESS_RANGE: (f64, f64) = (25.0, 75.0);
+
+/// Processing event for a decode that has got through `fraction` of the source image
+fn decode_progress_event(
+    image_id: &str,
+    image_name: &str,
+    fraction: f32,
+) -> CompressionProgressEvent {
+    let (start, end) = DECODE_PROGRESS_RANGE;
+    CompressionProgressEvent {
+        image_id: image_id.to_string(),
+        image_name: image_name.to_string(),
+        stage: CompressionStage::Processing,
+        progress: start + (end - start) * fraction.clamp(0.0, 1.0) as f64,
+        estimated_time_remaining: None,
+    }
+}
+
+/// Aggregate progress of a batch, emitted before each file is compressed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchProgressEvent {
+    pub current: usize,
+    pub total: usize,
+    pub file_name: String,
+    pub bytes_processed: u64,
+    pub bytes_saved: u64,
+    pub estimated_time_remaining: Option<u64>, // ms, None until a file has completed
+}
+
+impl BatchProgressEvent {
+    /// Progress for file `index`, given the results of the files already handled
+    fn new(
+        index: usize,
+        total: usize,
+        file_name: &str,
+        completed: &[CompressImageResponse],
+        elapsed_ms: u64,
+    ) -> Self {
+        let (bytes_processed, bytes_saved) = completed
+            .iter()
+            .filter_map(|response| response.result.as_ref())
+            .fold((0, 0), |(processed, saved), result| {
+                (
+                    processed + result.original_size,
+                    saved + result.original_size.saturating_sub(result.compressed_size),
+                )
+            });
+
+        // Temps moyen par fichier terminé × fichiers restants
+        let estimated_time_remaining = (!completed.is_empty()).then(|| {
+            let remaining = total.saturating_sub(completed.len()) as u64;
+            elapsed_ms / completed.len() as u64 * remaining
+        });
+
+        Self {
+            current: index + 1,
+            total,
+            file_name: file_name.to_string(),
+            bytes_processed,
+            bytes_saved,
+            estimated_time_remaining,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum CompressionStage {
     Started,
@@ -56,14 +212,168 @@ pub enum CompressionStage {
     Error,
 }
 
+/// Resolve the requested output format for a given input extension
+fn resolve_output_format(format: Option<&str>, input_extension: Option<&str>) -> OutputFormat {
+    let input_extension = input_extension.unwrap_or("webp");
+
+    match format {
+        Some("webp") => OutputFormat::WebP,
+        Some("png") => OutputFormat::Png,
+        Some("jpg") | Some("jpeg") => OutputFormat::Jpeg,
+        // Mode 'auto' : préserver le format original
+        Some("auto") => CompressionSettings::preserve_input_format(input_extension),
+        // Aucun format spécifié ou format inconnu : utiliser WebP optimal
+        _ => CompressionSettings::optimal_format_for_input(input_extension),
+    }
+}
+
+/// Run the image analysis to decide whether an automatic WebP output should be lossless
+fn auto_selects_lossless(file_path: &Path, input_extension: Option<&str>) -> bool {
+    let data = match std::fs::read(file_path) {
+        Ok(data) => data,
+        Err(_) => return false,
+    };
+
+    // Analyse impossible (format non géré par l'extracteur) : on reste en lossy
+    match analyze_image(&data, input_extension.unwrap_or("")) {
+        Ok((metadata, quality, colors)) => recommend_webp_mode(&metadata, &colors, &quality),
+        Err(_) => false,
+    }
+}
+
+/// Output size predicted from the stats, or the source size when the store is unavailable
+fn predicted_output_size(
+    input_format: &str,
+    output_format: &str,
+    original_size: u64,
+    quality: u8,
+    lossy_mode: bool,
+) -> u64 {
+    let store = match STATS_STORE.lock() {
+        Ok(store) => store,
+        Err(_) => return original_size,
+    };
+
+    let query = EstimationQuery {
+        input_format: input_format.to_string(),
+        output_format: output_format.to_string(),
+        original_size,
+        quality_setting: quality,
+        lossy_mode,
+    };
+    FileEstimation::new(
+        String::new(),
+        output_format.to_string(),
+        original_size,
+        estimate_with_fallback(&*store, &query),
+    )
+    .estimated_size
+}
+
+/// Compute the output file path: a directory gets `<stem>.<ext>`, a file path is used as is,
+/// and no path means `<stem>_compressed.<ext>` next to the input
+fn resolve_output_path(
+    file_path: &Path,
+    output_path: Option<&str>,
+    output_extension: &str,
+) -> std::path::PathBuf {
+    let filename = file_path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("compressed");
+
+    match output_path {
+        Some(custom_path) => {
+            let path = Path::new(custom_path);
+            if path.is_dir() {
+                // If output_path is a directory, generate filename
+                path.join(format!("{}.{}", filename, output_extension))
+            } else {
+                // Use the provided path directly
+                path.to_path_buf()
+            }
+        }
+        // Generate output path next to input file
+        None => file_path.with_file_name(format!("{}_compressed.{}", filename, output_extension)),
+    }
+}
+
+/// Give a duplicate the representative's compressed output (hard link, or copy)
+fn link_duplicate_output(
+    file_path: &str,
+    output_path: Option<&str>,
+    representative: &CompressImageResponse,
+    on_conflict: ConflictPolicy,
+) -> Option<CompressImageResponse> {
+    let representative_output = Path::new(representative.output_path.as_ref()?);
+    let output_extension = representative_output.extension()?.to_str()?;
+    let target = resolve_output_path(Path::new(file_path), output_path, output_extension);
+    let target = match apply_conflict_policy(target.clone(), on_conflict) {
+        Some(target) => target,
+        None => return Some(skipped_response(representative.image_id.clone(), &target)),
+    };
+
+    link_or_copy_file(representative_output, &target).ok()?;
+
+    let target = target.to_string_lossy().to_string();
+    Some(CompressImageResponse {
+        success: true,
+        image_id: representative.image_id.clone(),
+        output_path: Some(target.clone()),
+        result: representative
+            .result
+            .as_ref()
+            .map(|result| CompressionResult {
+                original_size: result.original_size,
+                compressed_size: result.compressed_size,
+                savings_percent: result.savings_percent,
+                output_path: target,
+                processing_time_ms: result.processing_time_ms,
+            }),
+        error: None,
+        skipped: false,
+    })
+}
+
+/// Fail fast if a lossless batch would produce any JPEG output
+fn validate_lossless_batch(file_paths: &[String], format: Option<&str>) -> Result<(), String> {
+    for file_path in file_paths {
+        let extension = Path::new(file_path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_lowercase());
+
+        if resolve_output_format(format, extension.as_deref()) == OutputFormat::Jpeg {
+            return Err(format!(
+                "Lossless mode cannot produce JPEG output (file: {})",
+                file_path
+            ));
+        }
+    }
+    Ok(())
+}
+
 #[tauri::command]
-pub async fn compress_image(
+pub async fn compress_image<R: Runtime>(
     request: CompressImageRequest,
     image_id: Option<String>,
-    app_handle: AppHandle,
-    _state: State<'_, AppState>,
+    app_handle: AppHandle<R>,
+    state: State<'_, AppState>,
 ) -> Result<CompressImageResponse, String> {
     let start_time = std::time::Instant::now();
+
+    // Le preset complète les champs laissés vides
+    let preset = match request.preset.as_deref() {
+        Some(name) => {
+            Some(preset_by_name(name).ok_or_else(|| format!("Unknown preset: {}", name))?)
+        }
+        None => None,
+    };
+    let request = match &preset {
+        Some(preset) => request.with_preset_defaults(&preset.settings),
+        None => request,
+    };
+
     let file_path = Path::new(&request.file_path);
     let file_name = file_path
         .file_name()
@@ -73,11 +383,11 @@ pub async fn compress_image(
 
     let image_id = image_id.unwrap_or_else(|| {
         let generated_id = format!("img_{}", start_time.elapsed().as_nanos());
-        println!("🔧 No image_id provided, generated: {}", generated_id);
+        log::debug!("No image_id provided, generated: {}", generated_id);
         generated_id
     });
 
-    println!("🎯 compress_image called, using image_id: {}", image_id);
+    log::debug!("compress_image called, using image_id: {}", image_id);
 
     // Emit start event
     let _ = app_handle.emit(
@@ -112,6 +422,7 @@ pub async fn compress_image(
                 output_path: None,
                 result: None,
                 error: Some(error_msg),
+                skipped: false,
             });
         }
     };
@@ -129,32 +440,61 @@ pub async fn compress_image(
     );
 
     // Determine compression settings
-    let output_format = match request.format.as_deref() {
-        Some("webp") => OutputFormat::WebP,
-        Some("png") => OutputFormat::Png,
-        Some("jpg") | Some("jpeg") => OutputFormat::Jpeg,
-        Some("auto") => {
-            // Mode 'auto' : préserver le format original
-            let input_extension = metadata
-                .extension
-                .clone()
-                .unwrap_or_else(|| "webp".to_string());
-
-            crate::domain::CompressionSettings::preserve_input_format(&input_extension)
+    let output_format =
+        resolve_output_format(request.format.as_deref(), metadata.extension.as_deref());
+
+    let settings = {
+        let config = state.get_config();
+        let quality = config
+            .compression
+            .resolve_quality(request.quality, output_format);
+        let (max_width, max_height) = config.compression.max_dimensions;
+        let mut settings = crate::domain::CompressionSettings::new(quality, output_format)
+            .with_memory_limit(config.performance.memory_limit_mb)
+            .with_lossless(request.lossless.unwrap_or(false))
+            .with_skip_if_no_gain(request.skip_if_no_gain.unwrap_or(false))
+            .with_never_grow(request.never_grow.unwrap_or(false));
+        if let Ok(max_dimensions) = crate::domain::Dimensions::new(max_width, max_height) {
+            settings = settings.with_max_dimensions(max_dimensions, config.compression.on_oversize);
         }
-        _ => {
-            // Aucun format spécifié ou format inconnu : utiliser WebP optimal
-            let input_extension = metadata
-                .extension
-                .clone()
-                .unwrap_or_else(|| "webp".to_string());
-
-            crate::domain::CompressionSettings::optimal_format_for_input(&input_extension)
+        // Le preset impose ses métadonnées et sa taille maximale
+        if let Some(preset) = &preset {
+            settings = settings.with_metadata_preservation(preset.settings.preserve_metadata);
+            if let Some(max_dimensions) = preset.settings.max_dimensions.clone() {
+                settings =
+                    settings.with_max_dimensions(max_dimensions, preset.settings.on_oversize);
+            }
         }
+        settings
+    };
+
+    // Format choisi automatiquement : l'analyse décide entre WebP lossless et lossy
+    let format_is_explicit = matches!(
+        request.format.as_deref(),
+        Some("webp") | Some("png") | Some("jpg") | Some("jpeg")
+    );
+    let settings = if !format_is_explicit
+        && output_format == OutputFormat::WebP
+        && !settings.lossless
+        && auto_selects_lossless(file_path, metadata.extension.as_deref())
+    {
+        settings.with_lossless(true)
+    } else {
+        settings
     };
 
-    let quality = request.quality.unwrap_or(80);
-    let settings = crate::domain::CompressionSettings::new(quality, output_format);
+    // Seul un JPEG source peut être réoptimisé sans perte en JPEG
+    let jpeg_input = matches!(metadata.extension.as_deref(), Some("jpg") | Some("jpeg"));
+    if settings.lossless && output_format == OutputFormat::Jpeg && !jpeg_input {
+        return Ok(CompressImageResponse {
+            success: false,
+            image_id,
+            output_path: None,
+            result: None,
+            error: Some("Lossless mode cannot produce JPEG output".to_string()),
+            skipped: false,
+        });
+    }
 
     // Determine output path
     let output_extension = match output_format {
@@ -163,35 +503,72 @@ pub async fn compress_image(
         OutputFormat::Jpeg => "jpg",
     };
 
-    let output_path = match request.output_path.as_ref() {
-        Some(custom_path) => {
-            let path = Path::new(custom_path);
-            if path.is_dir() {
-                // If output_path is a directory, generate filename
-                let filename = file_path
-                    .file_stem()
-                    .and_then(|stem| stem.to_str())
-                    .unwrap_or("compressed");
-                path.join(format!("{}.{}", filename, output_extension))
-            } else {
-                // Use the provided path directly
-                path.to_path_buf()
-            }
+    let output_path =
+        resolve_output_path(file_path, request.output_path.as_deref(), output_extension);
+    let output_path = match apply_conflict_policy(output_path.clone(), request.on_conflict) {
+        Some(output_path) => output_path,
+        None => return Ok(skipped_response(image_id, &output_path)),
+    };
+
+    // Vérifie l'espace disque à partir de la taille prédite, avant de compresser
+    let predicted_size = predicted_output_size(
+        metadata.extension.as_deref().unwrap_or("unknown"),
+        output_extension,
+        metadata.size,
+        settings.quality,
+        is_lossy_encoding(
+            metadata.extension.as_deref().unwrap_or("unknown"),
+            &settings,
+        ),
+    );
+    if let Some(output_dir) = output_path.parent() {
+        if let Err(e) = check_free_space(output_dir, predicted_size) {
+            return Ok(CompressImageResponse {
+                success: false,
+                image_id,
+                output_path: None,
+                result: None,
+                error: Some(e.to_string()),
+                skipped: false,
+            });
         }
-        None => {
-            // Generate output path next to input file
-            let mut output_path = file_path.to_path_buf();
-            let filename = file_path
-                .file_stem()
-                .and_then(|stem| stem.to_str())
-                .unwrap_or("compressed");
-            output_path.set_file_name(format!("{}_compressed.{}", filename, output_extension));
-            output_path
+    }
+
+    // Le décodage des très grandes images fait avancer la barre entre lecture et encodage
+    let on_decode_progress = {
+        let (app_handle, image_id, image_name) =
+            (app_handle.clone(), image_id.clone(), file_name.clone());
+        move |fraction: f32| {
+            let _ = app_handle.emit(
+                "compression-progress",
+                decode_progress_event(&image_id, &image_name, fraction),
+            );
         }
     };
 
-    // Perform file-to-file compression
-    match crate::domain::compression::compress_file_to_file(file_path, &output_path, &settings) {
+    // Perform file-to-file compression; a stuck encoder fails this file, not the batch
+    let timeout_ms = state.get_config().performance.per_image_timeout_ms;
+    let timeout = (timeout_ms > 0).then(|| std::time::Duration::from_millis(timeout_ms));
+    let (input_path, target_path) = (file_path.to_path_buf(), output_path.clone());
+    let compress_settings = settings.clone();
+    // Hors du runtime async : les fichiers d'un lot se compressent en parallèle
+    let compression = tauri::async_runtime::spawn_blocking(move || {
+        compress_file_to_file_with_timeout(
+            &input_path,
+            &target_path,
+            &compress_settings,
+            timeout,
+            on_decode_progress,
+        )
+    })
+    .await
+    .unwrap_or_else(|e| {
+        Err(CompressionError::ProcessingFailed(format!(
+            "Compression task failed: {}",
+            e
+        )))
+    });
+    match compression {
         Ok(compression_output) => {
             let processing_time = start_time.elapsed().as_millis() as u64;
 
@@ -213,7 +590,7 @@ pub async fn compress_image(
                     .extension
                     .clone()
                     .unwrap_or_else(|| "unknown".to_string());
-                let stat = crate::domain::compression::stats::create_stat_with_time(
+                let mut stat = crate::domain::compression::stats::create_stat_with_time(
                     input_format.clone(),
                     output_extension.to_string(),
                     compression_output.original_size,
@@ -222,16 +599,29 @@ pub async fn compress_image(
                     &settings,
                     "plume-v0.1.0".to_string(),
                 );
+                stat.image_type = compression_output.image_type.clone();
+                stat.lossy_mode = compression_output.lossy_mode;
+
+                // Garde la prédiction pour mesurer sa précision plus tard
+                let query = EstimationQuery {
+                    input_format: input_format.clone(),
+                    output_format: output_extension.to_string(),
+                    original_size: compression_output.original_size,
+                    quality_setting: settings.quality,
+                    lossy_mode: stat.lossy_mode,
+                };
+                stat.predicted_percent = Some(estimate_with_fallback(&*store, &query).percent);
 
                 match store.save_stat(stat) {
                     Ok(id) => {
-                        println!(
-                            "📊 Saved compression stat with timing (id: {}, time: {}ms)",
-                            id, processing_time
+                        log::debug!(
+                            "Saved compression stat with timing (id: {}, time: {}ms)",
+                            id,
+                            processing_time
                         );
                     }
                     Err(e) => {
-                        println!("⚠️ Failed to save compression stat: {}", e);
+                        log::warn!("Failed to save compression stat: {}", e);
                     }
                 }
             }
@@ -258,12 +648,13 @@ pub async fn compress_image(
                     compressed_size: compression_output.compressed_size,
                     savings_percent: compression_output.savings_percent,
                     output_path: compression_output.output_path.to_string_lossy().to_string(),
+                    processing_time_ms: processing_time,
                 }),
                 error: None,
+                skipped: false,
             })
         }
         Err(e) => {
-            let error_msg = format!("Compression failed: {}", e);
             let _ = app_handle.emit(
                 "compression-progress",
                 CompressionProgressEvent {
@@ -274,89 +665,1272 @@ pub async fn compress_image(
                     estimated_time_remaining: None,
                 },
             );
-            Ok(CompressImageResponse {
-                success: false,
-                image_id,
-                output_path: None,
-                result: None,
-                error: Some(error_msg),
-            })
+            Ok(failed_response(image_id, &e))
         }
     }
 }
 
+/// List the built-in compression presets
+#[tauri::command]
+pub async fn get_compression_presets(
+    _state: State<'_, AppState>,
+) -> Result<Vec<CompressionPreset>, String> {
+    Ok(list_presets())
+}
+
+/// Commande pour projeter la taille de sortie à plusieurs qualités (slider avec aperçu)
+#[tauri::command]
+pub async fn quality_size_curve(
+    file_path: String,
+    format: String,
+    qualities: Vec<u8>,
+    _state: State<'_, AppState>,
+) -> Result<Vec<(u8, u64)>, String> {
+    let path = Path::new(&file_path);
+    let metadata =
+        validate_image_file(path).map_err(|e| format!("File validation failed: {}", e))?;
+    let output_format = OutputFormat::from_string(&format)
+        .ok_or_else(|| format!("Unsupported output format: {}", format))?;
+    let input_data = std::fs::read(path).map_err(|e| format!("Failed to read image: {}", e))?;
+
+    crate::domain::quality_size_curve(
+        &input_data,
+        metadata.extension.as_deref().unwrap_or("unknown"),
+        output_format,
+        &qualities,
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Compressed image returned as a data URI, with the sizes before and after
 #[derive(Debug, Serialize, Deserialize)]
+pub struct CompressDataUriResponse {
+    pub data_uri: String,
+    pub original_size: u64,
+    pub compressed_size: u64,
+    pub savings_percent: f64,
+}
+
+/// Split a `data:image/...;base64,` URI into its input format and decoded bytes
+fn parse_data_uri(data_uri: &str) -> Result<(String, Vec<u8>), String> {
+    let rest = data_uri
+        .trim()
+        .strip_prefix("data:")
+        .ok_or_else(|| "Malformed data URI: missing 'data:' prefix".to_string())?;
+    let (header, payload) = rest
+        .split_once(',')
+        .ok_or_else(|| "Malformed data URI: missing ',' before the data".to_string())?;
+    let mime = header
+        .strip_suffix(";base64")
+        .ok_or_else(|| "Malformed data URI: only base64 data is supported".to_string())?;
+
+    let input_format = match mime.to_lowercase().as_str() {
+        "image/png" => "png",
+        "image/jpeg" | "image/jpg" => "jpg",
+        "image/webp" => "webp",
+        other => return Err(format!("Unsupported data URI type: {}", other)),
+    };
+
+    let data = general_purpose::STANDARD
+        .decode(payload.trim())
+        .map_err(|e| format!("Malformed data URI: invalid base64 ({})", e))?;
+    if data.is_empty() {
+        return Err("Malformed data URI: no image data".to_string());
+    }
+
+    Ok((input_format.to_string(), data))
+}
+
+/// Compress the image held in a data URI and re-encode the output as a data URI
+fn compress_data_uri_with(
+    data_uri: &str,
+    settings: &CompressionSettings,
+) -> Result<CompressDataUriResponse, String> {
+    let (input_format, input_data) = parse_data_uri(data_uri)?;
+    let output = crate::domain::compress_bytes(&input_data, &input_format, settings)
+        .map_err(|e| format!("Compression failed: {}", e))?;
+
+    let original_size = input_data.len() as u64;
+    let compressed_size = output.len() as u64;
+    Ok(CompressDataUriResponse {
+        data_uri: format!(
+            "data:{};base64,{}",
+            settings.format.mime_type(),
+            general_purpose::STANDARD.encode(&output)
+        ),
+        original_size,
+        compressed_size,
+        savings_percent: (1.0 - compressed_size as f64 / original_size as f64) * 100.0,
+    })
+}
+
+/// Commande pour compresser une image glissée-déposée, sans passer par un fichier temporaire
+#[tauri::command]
+pub async fn compress_data_uri(
+    data_uri: String,
+    format: String,
+    quality: Option<u8>,
+    state: State<'_, AppState>,
+) -> Result<CompressDataUriResponse, String> {
+    let output_format = OutputFormat::from_string(&format)
+        .ok_or_else(|| format!("Unsupported output format: {}", format))?;
+
+    let settings = {
+        let config = state.get_config();
+        let quality = config.compression.resolve_quality(quality, output_format);
+        CompressionSettings::new(quality, output_format)
+            .with_memory_limit(config.performance.memory_limit_mb)
+    };
+
+    compress_data_uri_with(&data_uri, &settings)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompressBatchRequest {
     pub file_paths: Vec<String>,
     pub quality: Option<u8>,
     pub format: Option<String>,
     pub output_dir: Option<String>,
+    #[serde(default)]
+    pub force_lossless: Option<bool>, // Refuse JPEG output, encode WebP/PNG losslessly
+    // Mirror the input tree: outputs are re-rooted from input_base_dir to output_base_dir
+    #[serde(default)]
+    pub input_base_dir: Option<String>,
+    #[serde(default)]
+    pub output_base_dir: Option<String>,
+    #[serde(default)]
+    pub skip_duplicates: bool, // Compress identical files once, link the others
+    #[serde(default)]
+    pub overrides: Vec<BatchOverride>, // Per-file settings, take precedence over the batch ones
+    #[serde(default)]
+    pub on_conflict: ConflictPolicy,
+    #[serde(default)]
+    pub skip_if_no_gain: Option<bool>, // Keep already-optimized lossy files as they are
+    #[serde(default)]
+    pub never_grow: Option<bool>, // Keep originals that would grow when compressed
+    #[serde(default)]
+    pub preset: Option<String>, // Applied to every file, explicit settings win
+}
+
+/// Quality/format for a single file of a batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchOverride {
+    pub file_path: String,
+    pub quality: Option<u8>,
+    pub format: Option<String>,
+}
+
+impl CompressBatchRequest {
+    /// Quality and format for a file, applying its override when present
+    fn settings_for(&self, file_path: &str) -> (Option<u8>, Option<String>) {
+        match self
+            .overrides
+            .iter()
+            .find(|item| item.file_path == file_path)
+        {
+            Some(item) => (
+                item.quality.or(self.quality),
+                item.format.clone().or_else(|| self.format.clone()),
+            ),
+            None => (self.quality, self.format.clone()),
+        }
+    }
+
+    /// Every override must target a file of the batch
+    fn validate_overrides(&self) -> Result<(), String> {
+        match self
+            .overrides
+            .iter()
+            .find(|item| !self.file_paths.contains(&item.file_path))
+        {
+            Some(item) => Err(format!(
+                "Override targets a file outside the batch: {}",
+                item.file_path
+            )),
+            None => Ok(()),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CompressBatchResponse {
+    pub batch_id: String, // Pass to retry_failed to re-run the failed files
     pub total_files: usize,
     pub successful: usize,
     pub failed: usize,
+    // Aggregates over successful results only
+    pub total_original_bytes: u64,
+    pub total_compressed_bytes: u64,
+    pub total_savings_percent: f64,
+    pub average_processing_time_ms: f64,
     pub results: Vec<CompressImageResponse>,
 }
 
+impl CompressBatchResponse {
+    /// Build the batch summary from the individual results
+    fn from_results(batch_id: String, results: Vec<CompressImageResponse>) -> Self {
+        let total_files = results.len();
+        let successful = results.iter().filter(|response| response.success).count();
+
+        let (total_original_bytes, total_compressed_bytes) = results
+            .iter()
+            .filter(|response| response.success)
+            .filter_map(|response| response.result.as_ref())
+            .fold((0u64, 0u64), |(original, compressed), result| {
+                (
+                    original + result.original_size,
+                    compressed + result.compressed_size,
+                )
+            });
+
+        let total_savings_percent = if total_original_bytes > 0 {
+            (total_original_bytes as f64 - total_compressed_bytes as f64)
+                / total_original_bytes as f64
+                * 100.0
+        } else {
+            0.0
+        };
+
+        // Moyenne des temps par fichier : le temps mural d'un lot parallèle la fausserait
+        let processing_times: Vec<u64> = results
+            .iter()
+            .filter_map(|response| response.result.as_ref())
+            .map(|result| result.processing_time_ms)
+            .collect();
+        let average_processing_time_ms = if processing_times.is_empty() {
+            0.0
+        } else {
+            processing_times.iter().sum::<u64>() as f64 / processing_times.len() as f64
+        };
+
+        Self {
+            batch_id,
+            total_files,
+            successful,
+            failed: total_files - successful,
+            total_original_bytes,
+            total_compressed_bytes,
+            total_savings_percent,
+            average_processing_time_ms,
+            results,
+        }
+    }
+}
+
+/// How long a finished batch can still be retried
+const BATCH_RETENTION: std::time::Duration = std::time::Duration::from_secs(30 * 60);
+const MAX_RECENT_BATCHES: usize = 16;
+
+/// Request and latest per-file results of a finished batch
+struct RecordedBatch {
+    request: CompressBatchRequest,
+    results: Vec<CompressImageResponse>,
+    recorded_at: std::time::Instant,
+}
+
+/// Short-lived registry of recent batches, used by retry_failed
+#[derive(Default)]
+struct BatchRegistry {
+    batches: HashMap<String, RecordedBatch>,
+}
+
+impl BatchRegistry {
+    fn record(
+        &mut self,
+        batch_id: String,
+        request: CompressBatchRequest,
+        results: Vec<CompressImageResponse>,
+    ) {
+        self.batches
+            .retain(|_, batch| batch.recorded_at.elapsed() < BATCH_RETENTION);
+
+        // Plein : on oublie le lot le plus ancien
+        while self.batches.len() >= MAX_RECENT_BATCHES {
+            let oldest = self
+                .batches
+                .iter()
+                .min_by_key(|(_, batch)| batch.recorded_at)
+                .map(|(batch_id, _)| batch_id.clone());
+            match oldest {
+                Some(oldest) => self.batches.remove(&oldest),
+                None => break,
+            };
+        }
+
+        self.batches.insert(
+            batch_id,
+            RecordedBatch {
+                request,
+                results,
+                recorded_at: std::time::Instant::now(),
+            },
+        );
+    }
+
+    /// Batch request and indices of the files whose last attempt failed
+    fn failed_files(&self, batch_id: &str) -> Result<(CompressBatchRequest, Vec<usize>), String> {
+        let batch = self
+            .batches
+            .get(batch_id)
+            .filter(|batch| batch.recorded_at.elapsed() < BATCH_RETENTION)
+            .ok_or_else(|| format!("Unknown or expired batch: {}", batch_id))?;
+
+        let failed = batch
+            .results
+            .iter()
+            .enumerate()
+            .filter(|(_, response)| !response.success)
+            .map(|(index, _)| index)
+            .collect();
+        Ok((batch.request.clone(), failed))
+    }
+
+    /// Replace the retried results and return the merged list
+    fn update(
+        &mut self,
+        batch_id: &str,
+        retried: Vec<(usize, CompressImageResponse)>,
+    ) -> Result<Vec<CompressImageResponse>, String> {
+        let batch = self
+            .batches
+            .get_mut(batch_id)
+            .ok_or_else(|| format!("Unknown or expired batch: {}", batch_id))?;
+
+        for (index, response) in retried {
+            if let Some(slot) = batch.results.get_mut(index) {
+                *slot = response;
+            }
+        }
+        batch.recorded_at = std::time::Instant::now();
+        Ok(batch.results.clone())
+    }
+}
+
+static RECENT_BATCHES: std::sync::LazyLock<Mutex<BatchRegistry>> =
+    std::sync::LazyLock::new(|| Mutex::new(BatchRegistry::default()));
+
+/// Stable id of a file within a batch, kept across retries
+fn batch_image_id(batch_id: &str, index: usize) -> String {
+    format!("{}_{}", batch_id, index)
+}
+
+fn batch_failure(image_id: String, error: String) -> CompressImageResponse {
+    CompressImageResponse {
+        success: false,
+        image_id,
+        output_path: None,
+        result: None,
+        error: Some(error),
+        skipped: false,
+    }
+}
+
+/// Output directory of a batch file, mirroring the input tree when requested
+fn batch_output_dir(
+    request: &CompressBatchRequest,
+    file_path: &str,
+) -> Result<Option<String>, String> {
+    match (&request.input_base_dir, &request.output_base_dir) {
+        (Some(input_base_dir), Some(output_base_dir)) => {
+            mirror_output_dir(file_path, input_base_dir, output_base_dir)
+                .map(|output_dir| Some(output_dir.to_string_lossy().to_string()))
+                .map_err(|e| format!("Output directory error: {}", e))
+        }
+        _ => Ok(request.output_dir.clone()),
+    }
+}
+
+/// Compress file `index` of a batch with its own settings and output directory
+async fn compress_batch_file<R: Runtime>(
+    request: &CompressBatchRequest,
+    batch_id: &str,
+    index: usize,
+    app_handle: AppHandle<R>,
+    state: State<'_, AppState>,
+) -> CompressImageResponse {
+    let file_path = &request.file_paths[index];
+    let image_id = batch_image_id(batch_id, index);
+    let output_path = match batch_output_dir(request, file_path) {
+        Ok(output_path) => output_path,
+        Err(e) => return batch_failure(image_id, e),
+    };
+
+    let (quality, format) = request.settings_for(file_path);
+    let compress_request = CompressImageRequest {
+        file_path: file_path.to_string(),
+        quality,
+        format,
+        output_path,
+        lossless: request.force_lossless,
+        on_conflict: request.on_conflict,
+        skip_if_no_gain: request.skip_if_no_gain,
+        never_grow: request.never_grow,
+        preset: request.preset.clone(),
+    };
+
+    match compress_image(compress_request, Some(image_id.clone()), app_handle, state).await {
+        Ok(response) => response,
+        Err(e) => batch_failure(image_id, e),
+    }
+}
+
+/// Run `job` on the given file indices, wave by wave so each wave's estimated decoded
+/// size stays under the memory budget; results follow `indices`
+async fn run_in_waves<T, F, Fut>(
+    file_paths: &[String],
+    indices: &[usize],
+    max_concurrency: usize,
+    memory_limit_mb: u64,
+    mut job: F,
+) -> Vec<T>
+where
+    F: FnMut(usize) -> Fut,
+    Fut: std::future::Future<Output = T>,
+{
+    let input_paths: Vec<&str> = indices
+        .iter()
+        .map(|&index| file_paths[index].as_str())
+        .collect();
+
+    let mut results = Vec::with_capacity(indices.len());
+    // Vagues contiguës et join_all ordonné : les résultats gardent l'ordre d'entrée
+    for wave in plan_batch_waves(&input_paths, max_concurrency, Some(memory_limit_mb)) {
+        // Un fichier abandonné sur timeout décode encore : sa mémoire n'est pas libérée
+        if abandoned_workers() > 0 {
+            let _ = tauri::async_runtime::spawn_blocking(wait_for_abandoned_workers).await;
+        }
+        let tasks: Vec<_> = wave
+            .into_iter()
+            .map(|position| job(indices[position]))
+            .collect();
+        results.extend(futures::future::join_all(tasks).await);
+    }
+    results
+}
+
 #[tauri::command]
-pub async fn compress_batch(
+pub async fn compress_batch<R: Runtime>(
     request: CompressBatchRequest,
-    app_handle: AppHandle,
-    _state: State<'_, AppState>,
+    app_handle: AppHandle<R>,
+    state: State<'_, AppState>,
 ) -> Result<CompressBatchResponse, String> {
+    request.validate_overrides()?;
+    if request.force_lossless == Some(true) {
+        // Chaque fichier peut avoir son propre format via les overrides
+        for file_path in &request.file_paths {
+            let (_, format) = request.settings_for(file_path);
+            validate_lossless_batch(std::slice::from_ref(file_path), format.as_deref())?;
+        }
+    }
+
+    let batch_id = format!(
+        "batch_{}_{}",
+        chrono::Utc::now().timestamp_millis(),
+        crate::domain::unique_suffix()
+    );
     let total_files = request.file_paths.len();
-    let mut results = Vec::new();
-    let mut successful = 0;
-    let mut failed = 0;
-
-    for (index, file_path) in request.file_paths.iter().enumerate() {
-        let compress_request = CompressImageRequest {
-            file_path: file_path.clone(),
-            quality: request.quality,
-            format: request.format.clone(),
-            output_path: request.output_dir.clone(),
-        };
+    let start_time = std::time::Instant::now();
+    let (max_concurrency, memory_limit_mb) = {
+        let performance = &state.get_config().performance;
+        (
+            performance.max_concurrent_operations,
+            performance.memory_limit_mb,
+        )
+    };
 
-        // Emit batch progress
-        let _ = app_handle.emit(
-            "batch-progress",
-            serde_json::json!({
-                "current": index + 1,
-                "total": total_files,
-                "file_name": Path::new(file_path).file_name()
-                    .and_then(|n| n.to_str()).unwrap_or("unknown")
-            }),
+    // Doublon -> représentant du groupe (premier fichier identique rencontré)
+    let mut representatives: HashMap<String, String> = HashMap::new();
+    if request.skip_duplicates {
+        for cluster in find_duplicates(&request.file_paths) {
+            // Un doublon ne réutilise la sortie que si ses réglages sont identiques
+            let representative_settings = request.settings_for(&cluster[0]);
+            for duplicate in &cluster[1..] {
+                if request.settings_for(duplicate) == representative_settings {
+                    representatives.insert(duplicate.clone(), cluster[0].clone());
+                }
+            }
+        }
+    }
+    // Réponses terminées, dans l'ordre de fin, pour la progression du lot
+    let completed = Mutex::new(Vec::with_capacity(total_files));
+    let emit_progress = |index: usize| {
+        let file_name = Path::new(&request.file_paths[index])
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("unknown");
+        let Ok(completed) = completed.lock() else {
+            return;
+        };
+        let event = BatchProgressEvent::new(
+            index,
+            total_files,
+            file_name,
+            &completed,
+            start_time.elapsed().as_millis() as u64,
+        );
+        drop(completed);
+        let _ = app_handle.emit("batch-progress", event);
+    };
+    let finish = |response: CompressImageResponse| {
+        if let Ok(mut completed) = completed.lock() {
+            completed.push(response.clone());
+        }
+        response
+    };
+    let compress_one = |index: usize| {
+        emit_progress(index);
+        let task = compress_batch_file(
+            &request,
+            &batch_id,
+            index,
+            app_handle.clone(),
+            state.clone(),
         );
+        async { finish(task.await) }
+    };
 
-        match compress_image(compress_request, None, app_handle.clone(), _state.clone()).await {
-            Ok(response) => {
-                if response.success {
-                    successful += 1;
-                } else {
-                    failed += 1;
-                }
-                results.push(response);
+    // Les doublons attendent leur représentant pour réutiliser sa sortie
+    let (duplicates, originals): (Vec<usize>, Vec<usize>) = (0..total_files)
+        .partition(|&index| representatives.contains_key(&request.file_paths[index]));
+    let mut results: Vec<Option<CompressImageResponse>> = vec![None; total_files];
+
+    let responses = run_in_waves(
+        &request.file_paths,
+        &originals,
+        max_concurrency,
+        memory_limit_mb,
+        &compress_one,
+    )
+    .await;
+    for (index, response) in originals.into_iter().zip(responses) {
+        results[index] = Some(response);
+    }
+
+    let mut unlinked = Vec::new();
+    for index in duplicates {
+        let file_path = &request.file_paths[index];
+        let representative = representatives
+            .get(file_path)
+            .and_then(|representative| {
+                request
+                    .file_paths
+                    .iter()
+                    .position(|path| path == representative)
+            })
+            .and_then(|representative_index| results[representative_index].as_ref())
+            .filter(|response| response.success);
+        let linked = match (representative, batch_output_dir(&request, file_path)) {
+            (Some(representative), Ok(output_path)) => link_duplicate_output(
+                file_path,
+                output_path.as_deref(),
+                representative,
+                request.on_conflict,
+            ),
+            _ => None,
+        };
+        match linked {
+            Some(response) => {
+                emit_progress(index);
+                results[index] = Some(finish(response));
             }
-            Err(e) => {
-                failed += 1;
-                results.push(CompressImageResponse {
-                    success: false,
+            None => unlinked.push(index),
+        }
+    }
+
+    let responses = run_in_waves(
+        &request.file_paths,
+        &unlinked,
+        max_concurrency,
+        memory_limit_mb,
+        &compress_one,
+    )
+    .await;
+    for (index, response) in unlinked.into_iter().zip(responses) {
+        results[index] = Some(response);
+    }
+
+    let results: Vec<CompressImageResponse> = results
+        .into_iter()
+        .enumerate()
+        .map(|(index, response)| {
+            response.unwrap_or_else(|| {
+                batch_failure(
+                    batch_image_id(&batch_id, index),
+                    "File was not processed".to_string(),
+                )
+            })
+        })
+        .collect();
+
+    if let Ok(mut registry) = RECENT_BATCHES.lock() {
+        registry.record(batch_id.clone(), request, results.clone());
+    }
+
+    Ok(CompressBatchResponse::from_results(batch_id, results))
+}
+
+/// Re-run only the files of a recent batch whose last attempt failed, with the same settings
+#[tauri::command]
+pub async fn retry_failed<R: Runtime>(
+    batch_id: String,
+    app_handle: AppHandle<R>,
+    state: State<'_, AppState>,
+) -> Result<CompressBatchResponse, String> {
+    let (request, failed) = RECENT_BATCHES
+        .lock()
+        .map_err(|_| "Failed to acquire batch registry lock".to_string())?
+        .failed_files(&batch_id)?;
+
+    let (max_concurrency, memory_limit_mb) = {
+        let performance = &state.get_config().performance;
+        (
+            performance.max_concurrent_operations,
+            performance.memory_limit_mb,
+        )
+    };
+    let responses = run_in_waves(
+        &request.file_paths,
+        &failed,
+        max_concurrency,
+        memory_limit_mb,
+        |index| {
+            compress_batch_file(
+                &request,
+                &batch_id,
+                index,
+                app_handle.clone(),
+                state.clone(),
+            )
+        },
+    )
+    .await;
+    let retried = failed.into_iter().zip(responses).collect();
+
+    let results = RECENT_BATCHES
+        .lock()
+        .map_err(|_| "Failed to acquire batch registry lock".to_string())?
+        .update(&batch_id, retried)?;
+
+    Ok(CompressBatchResponse::from_results(batch_id, results))
+}
+
+/// Estimate output sizes for a batch without writing any file
+#[tauri::command]
+pub async fn estimate_batch(
+    file_paths: Vec<String>,
+    format: Option<String>,
+    quality: Option<u8>,
+    state: State<'_, AppState>,
+) -> Result<BatchEstimation, String> {
+    let compression_config = state.get_config().compression.clone();
+
+    // Validation (I/O disque) avant de prendre le verrou des stats
+    let mut queries = Vec::with_capacity(file_paths.len());
+    for file_path in file_paths {
+        let metadata = validate_image_file(Path::new(&file_path))
+            .map_err(|e| format!("File validation failed for {}: {}", file_path, e))?;
+
+        let input_format = metadata
+            .extension
+            .clone()
+            .unwrap_or_else(|| "unknown".to_string());
+        let resolved_format =
+            resolve_output_format(format.as_deref(), metadata.extension.as_deref());
+        let quality = compression_config.resolve_quality(quality, resolved_format);
+        let output_format = resolved_format.extension().to_string();
+        // Même tranche lossy que celle remplie par compress_image
+        let lossy_mode = is_lossy_encoding(
+            &input_format,
+            &CompressionSettings::new(quality, resolved_format),
+        );
+
+        let query = EstimationQuery {
+            input_format,
+            output_format,
+            original_size: metadata.size,
+            quality_setting: quality,
+            lossy_mode,
+        };
+        queries.push((file_path, query));
+    }
+
+    let store = STATS_STORE
+        .lock()
+        .map_err(|_| "Failed to acquire stats store lock".to_string())?;
+
+    let files = queries
+        .into_iter()
+        .map(|(file_path, query)| {
+            let estimation = estimate_with_fallback(&*store, &query);
+            FileEstimation::new(
+                file_path,
+                query.output_format,
+                query.original_size,
+                estimation,
+            )
+        })
+        .collect();
+
+    Ok(BatchEstimation::from_files(files))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_progress_event() {
+        use tauri::{Listener, Manager};
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file_paths: Vec<String> = (0..3)
+            .map(|index| {
+                let path = temp_dir.path().join(format!("cat_{}.png", index));
+                image::RgbImage::from_fn(48, 48, |x, y| image::Rgb([x as u8, y as u8, 90]))
+                    .save(&path)
+                    .unwrap();
+                path.to_string_lossy().to_string()
+            })
+            .collect();
+
+        // Un fichier à la fois : chaque événement voit les fichiers précédents terminés
+        let mut config = crate::domain::AppConfig::default();
+        config.performance.max_concurrent_operations = 1;
+        let app = tauri::test::mock_app();
+        app.manage(AppState::with_config(config));
+
+        let events = std::sync::Arc::new(Mutex::new(Vec::new()));
+        let captured = events.clone();
+        app.listen("batch-progress", move |event| {
+            let progress: BatchProgressEvent = serde_json::from_str(event.payload()).unwrap();
+            captured.lock().unwrap().push(progress);
+        });
+
+        let request = CompressBatchRequest {
+            file_paths,
+            quality: Some(80),
+            format: Some("webp".to_string()),
+            output_dir: Some(temp_dir.path().to_string_lossy().to_string()),
+            force_lossless: None,
+            input_base_dir: None,
+            output_base_dir: None,
+            skip_duplicates: false,
+            overrides: Vec::new(),
+            on_conflict: ConflictPolicy::Overwrite,
+            skip_if_no_gain: None,
+            never_grow: None,
+            preset: None,
+        };
+        let response = tauri::async_runtime::block_on(compress_batch(
+            request,
+            app.handle().clone(),
+            app.state::<AppState>(),
+        ))
+        .unwrap();
+        assert_eq!(response.successful, 3);
+
+        let events = events.lock().unwrap();
+        let currents: Vec<usize> = events.iter().map(|event| event.current).collect();
+        assert_eq!(currents, vec![1, 2, 3]);
+        assert!(events.iter().all(|event| event.total == 3));
+        assert_eq!(events[0].estimated_time_remaining, None);
+        assert_eq!(events[0].bytes_processed, 0);
+        // Dès le premier fichier terminé, l'ETA et les octets traités sont renseignés
+        for (previous, event) in events.iter().zip(&events[1..]) {
+            assert!(event.estimated_time_remaining.is_some());
+            assert!(event.bytes_processed > previous.bytes_processed);
+        }
+    }
+
+    #[test]
+    fn test_resolve_output_path() {
+        let input = Path::new("photos/cat.png");
+        assert_eq!(
+            resolve_output_path(input, None, "webp"),
+            Path::new("photos/cat_compressed.webp")
+        );
+        assert_eq!(
+            resolve_output_path(input, Some("out/custom.webp"), "webp"),
+            Path::new("out/custom.webp")
+        );
+    }
+
+    #[test]
+    fn test_batch_summary_aggregates_bytes() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let settings = CompressionSettings::new(80, OutputFormat::WebP);
+
+        let mut results: Vec<CompressImageResponse> = [64u32, 128]
+            .iter()
+            .enumerate()
+            .map(|(index, &size)| {
+                let input_path = temp_dir.path().join(format!("image_{}.png", index));
+                let output_path = temp_dir.path().join(format!("image_{}.webp", index));
+                image::RgbImage::from_fn(size, size, |x, y| image::Rgb([x as u8, y as u8, 90]))
+                    .save(&input_path)
+                    .unwrap();
+
+                let output =
+                    crate::domain::compress_file_to_file(&input_path, &output_path, &settings)
+                        .unwrap();
+                CompressImageResponse {
+                    success: true,
                     image_id: format!("batch_{}", index),
-                    output_path: None,
-                    result: None,
-                    error: Some(e),
-                });
+                    output_path: Some(output_path.to_string_lossy().to_string()),
+                    result: Some(CompressionResult {
+                        original_size: output.original_size,
+                        compressed_size: output.compressed_size,
+                        savings_percent: output.savings_percent,
+                        output_path: output_path.to_string_lossy().to_string(),
+                        processing_time_ms: 100 * (index as u64 + 1),
+                    }),
+                    error: None,
+                    skipped: false,
+                }
+            })
+            .collect();
+        results.push(CompressImageResponse {
+            success: false,
+            image_id: "batch_2".to_string(),
+            output_path: None,
+            result: None,
+            error: Some("Compression failed".to_string()),
+            skipped: false,
+        });
+
+        let expected_original: u64 = results
+            .iter()
+            .filter_map(|response| response.result.as_ref())
+            .map(|result| result.original_size)
+            .sum();
+        let expected_compressed: u64 = results
+            .iter()
+            .filter_map(|response| response.result.as_ref())
+            .map(|result| result.compressed_size)
+            .sum();
+
+        let summary = CompressBatchResponse::from_results("batch_test".to_string(), results);
+        assert_eq!((summary.successful, summary.failed), (2, 1));
+        assert_eq!(summary.total_original_bytes, expected_original);
+        assert_eq!(summary.total_compressed_bytes, expected_compressed);
+        assert!(summary.total_savings_percent > 0.0);
+        // Seuls les fichiers traités comptent dans la moyenne
+        assert_eq!(summary.average_processing_time_ms, 150.0);
+    }
+
+    #[test]
+    fn test_batch_overrides() {
+        let mut request = CompressBatchRequest {
+            file_paths: vec![
+                "assets/logo.png".to_string(),
+                "assets/photo.png".to_string(),
+            ],
+            quality: Some(80),
+            format: Some("webp".to_string()),
+            output_dir: None,
+            force_lossless: None,
+            input_base_dir: None,
+            output_base_dir: None,
+            skip_duplicates: false,
+            on_conflict: ConflictPolicy::default(),
+            skip_if_no_gain: None,
+            never_grow: None,
+            preset: None,
+            overrides: vec![BatchOverride {
+                file_path: "assets/logo.png".to_string(),
+                quality: None,
+                format: Some("png".to_string()),
+            }],
+        };
+        assert!(request.validate_overrides().is_ok());
+
+        let extensions: Vec<_> = request
+            .file_paths
+            .iter()
+            .map(|file_path| {
+                let (quality, format) = request.settings_for(file_path);
+                assert_eq!(quality, Some(80));
+                resolve_output_format(format.as_deref(), Some("png")).extension()
+            })
+            .collect();
+        assert_eq!(extensions, vec!["png", "webp"]);
+
+        request.overrides[0].file_path = "elsewhere/logo.png".to_string();
+        assert!(request.validate_overrides().is_err());
+    }
+
+    #[test]
+    fn test_conflict_policies() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let target = temp_dir.path().join("photo_compressed.webp");
+        std::fs::write(&target, b"original").unwrap();
+
+        let overwrite = apply_conflict_policy(target.clone(), ConflictPolicy::Overwrite).unwrap();
+        assert_eq!(overwrite, target);
+        crate::domain::atomic_write(&overwrite, b"replaced").unwrap();
+        assert_eq!(std::fs::read(&target).unwrap(), b"replaced");
+
+        assert_eq!(
+            apply_conflict_policy(target.clone(), ConflictPolicy::Skip),
+            None
+        );
+        let response = skipped_response("img_1".to_string(), &target);
+        assert!(response.skipped && response.result.is_none());
+        assert_eq!(std::fs::read(&target).unwrap(), b"replaced");
+
+        let renamed = apply_conflict_policy(target.clone(), ConflictPolicy::Rename).unwrap();
+        assert_eq!(renamed, temp_dir.path().join("photo_compressed (1).webp"));
+
+        // Pas de conflit : le chemin est gardé quelle que soit la politique
+        let fresh = temp_dir.path().join("fresh.webp");
+        assert_eq!(
+            apply_conflict_policy(fresh.clone(), ConflictPolicy::Skip),
+            Some(fresh)
+        );
+    }
+
+    #[test]
+    fn test_decode_failure_in_response() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let input_path = temp_dir.path().join("corrupt.png");
+        std::fs::write(&input_path, b"not an image at all, only garbage bytes").unwrap();
+
+        let settings = CompressionSettings::new(80, OutputFormat::WebP);
+        let error = crate::domain::compression::compress_file_to_file(
+            input_path.clone(),
+            temp_dir.path().join("corrupt.webp"),
+            &settings,
+        )
+        .unwrap_err();
+        assert!(matches!(error, CompressionError::DecodeFailed(_)));
+
+        let response = failed_response("img_1".to_string(), &error);
+        assert!(!response.success);
+        assert!(response
+            .error
+            .unwrap()
+            .starts_with("Compression failed: Failed to decode image"));
+    }
+
+    #[test]
+    fn test_lossless_batch_validation() {
+        let files = vec!["a.png".to_string(), "b.jpg".to_string()];
+
+        assert!(validate_lossless_batch(&files, Some("jpeg")).is_err());
+        // 'auto' keeps b.jpg as JPEG
+        assert!(validate_lossless_batch(&files, Some("auto")).is_err());
+        assert!(validate_lossless_batch(&files, Some("webp")).is_ok());
+        assert!(validate_lossless_batch(&files, None).is_ok());
+    }
+
+    /// Logger de test qui garde les enregistrements en mémoire
+    struct CapturingLogger {
+        records: std::sync::Mutex<Vec<(log::Level, String)>>,
+    }
+
+    impl log::Log for CapturingLogger {
+        fn enabled(&self, _metadata: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            if let Ok(mut records) = self.records.lock() {
+                records.push((record.level(), record.args().to_string()));
             }
         }
+
+        fn flush(&self) {}
     }
 
-    Ok(CompressBatchResponse {
-        total_files,
-        successful,
-        failed,
-        results,
-    })
+    static CAPTURING_LOGGER: CapturingLogger = CapturingLogger {
+        records: std::sync::Mutex::new(Vec::new()),
+    };
+
+    #[test]
+    fn test_compression_failure_logs_error() {
+        // Un seul logger par processus : on ignore l'erreur s'il est déjà installé
+        let _ = log::set_logger(&CAPTURING_LOGGER);
+        log::set_max_level(log::LevelFilter::Trace);
+
+        let response = failed_response(
+            "log_capture_image".to_string(),
+            &CompressionError::DecodeFailed("truncated file".to_string()),
+        );
+        assert!(!response.success);
+
+        let records = CAPTURING_LOGGER.records.lock().unwrap();
+        assert!(records.iter().any(|(level, message)| {
+            *level == log::Level::Error && message.contains("log_capture_image")
+        }));
+    }
+
+    #[test]
+    fn test_compress_data_uri_png_to_webp() {
+        let img = image::RgbaImage::from_fn(32, 24, |x, y| {
+            image::Rgba([(x * 8) as u8, (y * 10) as u8, 120, 255])
+        });
+        let mut png_data = Vec::new();
+        image::DynamicImage::ImageRgba8(img)
+            .write_to(
+                &mut std::io::Cursor::new(&mut png_data),
+                image::ImageFormat::Png,
+            )
+            .unwrap();
+        let data_uri = format!(
+            "data:image/png;base64,{}",
+            general_purpose::STANDARD.encode(&png_data)
+        );
+
+        let settings = CompressionSettings::new(75, OutputFormat::WebP);
+        let response = compress_data_uri_with(&data_uri, &settings).unwrap();
+
+        assert_eq!(response.original_size, png_data.len() as u64);
+        let payload = response
+            .data_uri
+            .strip_prefix("data:image/webp;base64,")
+            .unwrap();
+        let output = general_purpose::STANDARD.decode(payload).unwrap();
+        assert_eq!(response.compressed_size, output.len() as u64);
+        let decoded =
+            image::load_from_memory_with_format(&output, image::ImageFormat::WebP).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (32, 24));
+    }
+
+    #[test]
+    fn test_malformed_data_uri() {
+        let settings = CompressionSettings::new(75, OutputFormat::WebP);
+        for (data_uri, expected) in [
+            ("image/png;base64,AAAA", "missing 'data:'"),
+            ("data:image/png;base64", "missing ','"),
+            ("data:image/png,rawdata", "only base64"),
+            ("data:image/gif;base64,R0lGOD", "Unsupported data URI type"),
+            ("data:image/png;base64,@@@", "invalid base64"),
+            ("data:image/png;base64,", "no image data"),
+        ] {
+            let error = compress_data_uri_with(data_uri, &settings).unwrap_err();
+            assert!(error.contains(expected), "{}: {}", data_uri, error);
+        }
+    }
+
+    #[test]
+    fn test_retry_failed_reruns_only_failures() {
+        use tauri::Manager;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let output_dir = temp_dir.path().join("out");
+        std::fs::create_dir(&output_dir).unwrap();
+        let good = temp_dir.path().join("good.png");
+        let broken = temp_dir.path().join("broken.png");
+        image::RgbImage::from_pixel(16, 16, image::Rgb([200, 80, 30]))
+            .save(&good)
+            .unwrap();
+        std::fs::write(&broken, b"not a png").unwrap();
+
+        let app = tauri::test::mock_app();
+        app.manage(AppState::new());
+        let request = CompressBatchRequest {
+            file_paths: vec![
+                good.to_string_lossy().to_string(),
+                broken.to_string_lossy().to_string(),
+            ],
+            quality: Some(80),
+            format: Some("webp".to_string()),
+            output_dir: Some(output_dir.to_string_lossy().to_string()),
+            force_lossless: None,
+            input_base_dir: None,
+            output_base_dir: None,
+            skip_duplicates: false,
+            overrides: Vec::new(),
+            on_conflict: ConflictPolicy::Overwrite,
+            skip_if_no_gain: None,
+            never_grow: None,
+            preset: None,
+        };
+
+        let first = tauri::async_runtime::block_on(compress_batch(
+            request,
+            app.handle().clone(),
+            app.state::<AppState>(),
+        ))
+        .unwrap();
+        assert_eq!((first.successful, first.failed), (1, 1));
+        assert!(first.results[0].success && !first.results[1].success);
+
+        // Le fichier cassé est réparé ; la sortie du premier est supprimée pour
+        // vérifier qu'il n'est pas recompressé
+        let good_output = first.results[0].output_path.clone().unwrap();
+        std::fs::remove_file(&good_output).unwrap();
+        image::RgbImage::from_pixel(32, 32, image::Rgb([10, 120, 240]))
+            .save(&broken)
+            .unwrap();
+
+        let retried = tauri::async_runtime::block_on(retry_failed(
+            first.batch_id.clone(),
+            app.handle().clone(),
+            app.state::<AppState>(),
+        ))
+        .unwrap();
+        assert!(!Path::new(&good_output).exists());
+        assert!(Path::new(retried.results[1].output_path.as_ref().unwrap()).exists());
+        assert_eq!(retried.results[0].output_path, first.results[0].output_path);
+        assert_eq!(retried.results[1].image_id, format!("{}_1", first.batch_id));
+
+        // Les agrégats couvrent maintenant les deux fichiers
+        assert_eq!(
+            (retried.total_files, retried.successful, retried.failed),
+            (2, 2, 0)
+        );
+        let retried_original = retried.results[1].result.as_ref().unwrap().original_size;
+        assert_eq!(
+            retried.total_original_bytes,
+            first.total_original_bytes + retried_original
+        );
+        assert!(retried.total_compressed_bytes > first.total_compressed_bytes);
+
+        // Plus rien à relancer
+        let again = tauri::async_runtime::block_on(retry_failed(
+            first.batch_id.clone(),
+            app.handle().clone(),
+            app.state::<AppState>(),
+        ))
+        .unwrap();
+        assert_eq!((again.successful, again.failed), (2, 0));
+        assert!(tauri::async_runtime::block_on(retry_failed(
+            "batch_unknown".to_string(),
+            app.handle().clone(),
+            app.state::<AppState>(),
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn test_preset_fills_unset_fields() {
+        let request = CompressImageRequest {
+            file_path: "photo.png".to_string(),
+            quality: None,
+            format: None,
+            output_path: None,
+            lossless: None,
+            on_conflict: ConflictPolicy::Rename,
+            skip_if_no_gain: None,
+            never_grow: None,
+            preset: Some("email".to_string()),
+        };
+        let email = preset_by_name("email").unwrap().settings;
+
+        let filled = request.with_preset_defaults(&email);
+        assert_eq!(filled.quality, Some(70));
+        assert_eq!(filled.format.as_deref(), Some("jpg"));
+        assert_eq!(filled.never_grow, Some(true));
+
+        // Les valeurs explicites ne sont pas écrasées
+        let explicit = CompressImageRequest {
+            quality: Some(92),
+            format: Some("webp".to_string()),
+            ..filled
+        }
+        .with_preset_defaults(&email);
+        assert_eq!(explicit.quality, Some(92));
+        assert_eq!(explicit.format.as_deref(), Some("webp"));
+
+        // Un `false` explicite l'emporte aussi sur un preset sans perte
+        let lossless_preset = CompressionSettings::new(90, OutputFormat::WebP)
+            .with_lossless(true)
+            .with_never_grow(true);
+        let request = CompressImageRequest {
+            lossless: Some(false),
+            never_grow: Some(false),
+            ..explicit
+        }
+        .with_preset_defaults(&lossless_preset);
+        assert_eq!(request.lossless, Some(false));
+        assert_eq!(request.never_grow, Some(false));
+        assert_eq!(
+            CompressImageRequest {
+                lossless: None,
+                ..request
+            }
+            .with_preset_defaults(&lossless_preset)
+            .lossless,
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn test_batch_waves_keep_input_order() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::task::Poll;
+
+        // Rend la main `times` fois avant de terminer
+        async fn yield_times(times: usize) {
+            for _ in 0..times {
+                let mut yielded = false;
+                std::future::poll_fn(|cx| {
+                    if yielded {
+                        Poll::Ready(())
+                    } else {
+                        yielded = true;
+                        cx.waker().wake_by_ref();
+                        Poll::Pending
+                    }
+                })
+                .await;
+            }
+        }
+
+        let run = |file_paths: &[String], indices: &[usize], memory_limit_mb: u64| {
+            let in_flight = AtomicUsize::new(0);
+            let max_in_flight = AtomicUsize::new(0);
+            let finish_order = Mutex::new(Vec::new());
+
+            let results = futures::executor::block_on(run_in_waves(
+                file_paths,
+                indices,
+                3,
+                memory_limit_mb,
+                |index| {
+                    let (in_flight, max_in_flight, finish_order) =
+                        (&in_flight, &max_in_flight, &finish_order);
+                    async move {
+                        let running = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                        max_in_flight.fetch_max(running, Ordering::SeqCst);
+
+                        // Les premiers fichiers sont les plus lents
+                        yield_times(10 - index).await;
+
+                        in_flight.fetch_sub(1, Ordering::SeqCst);
+                        finish_order.lock().unwrap().push(index);
+                        index
+                    }
+                },
+            ));
+            (
+                results,
+                max_in_flight.into_inner(),
+                finish_order.into_inner().unwrap(),
+            )
+        };
+
+        // Fichiers illisibles : taille estimée nulle, seule la concurrence limite
+        let missing: Vec<String> = (0..7).map(|i| format!("missing_{}.png", i)).collect();
+        let indices = [0, 1, 2, 4, 5, 6];
+        let (results, max_in_flight, finish_order) = run(&missing, &indices, 1024);
+        assert_eq!(results, indices);
+        assert_eq!(max_in_flight, 3);
+        assert_eq!(finish_order.len(), indices.len());
+        assert_ne!(finish_order, indices);
+
+        // 1000×1000 RGB ≈ 3 Mo décodés : un budget de 4 Mo n'en laisse passer qu'un à la fois
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let large: Vec<String> = (0..3)
+            .map(|i| {
+                let path = temp_dir.path().join(format!("large_{}.png", i));
+                image::RgbImage::new(1000, 1000).save(&path).unwrap();
+                path.to_string_lossy().to_string()
+            })
+            .collect();
+        let (results, max_in_flight, _) = run(&large, &[0, 1, 2], 4);
+        assert_eq!(results, vec![0, 1, 2]);
+        assert_eq!(max_in_flight, 1);
+    }
 }
@@ -0,0 +1,117 @@
+use super::stats::{stats_count, stats_db_path};
+use crate::domain::{get_version_info, AppConfig, AppState, ConfigFieldError, VersionInfo};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+/// Rejected configuration update, with the invalid settings for the form
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConfigUpdateError {
+    pub message: String,
+    pub fields: Vec<ConfigFieldError>,
+}
+
+/// Version and effective configuration, for the about/diagnostics panel
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AppInfo {
+    pub version: VersionInfo,
+    pub config: AppConfig,
+    pub config_path: String,
+    pub database_path: String,
+    pub stats_count: u32,
+    pub event_count: usize,
+}
+
+/// Commande pour récupérer l'état complet de l'application en un seul appel
+#[tauri::command]
+pub async fn get_app_info(state: State<'_, AppState>) -> Result<AppInfo, String> {
+    Ok(app_info(&state, stats_count()?))
+}
+
+fn app_info(state: &AppState, stats_count: u32) -> AppInfo {
+    AppInfo {
+        version: get_version_info(),
+        config: state.get_config().clone(),
+        config_path: state.config_path.to_string_lossy().to_string(),
+        database_path: stats_db_path().to_string_lossy().to_string(),
+        stats_count,
+        event_count: state.event_count(),
+    }
+}
+
+/// Commande pour lire la configuration courante
+#[tauri::command]
+pub async fn get_app_config(state: State<'_, AppState>) -> Result<AppConfig, String> {
+    Ok(state.get_config().clone())
+}
+
+/// Commande pour remplacer la configuration depuis l'écran des réglages
+#[tauri::command]
+pub async fn update_app_config(
+    config: AppConfig,
+    state: State<'_, AppState>,
+) -> Result<AppConfig, ConfigUpdateError> {
+    apply_app_config(&state, config)
+}
+
+/// Commande pour restaurer la configuration par défaut
+#[tauri::command]
+pub async fn reset_config_to_defaults(state: State<'_, AppState>) -> Result<AppConfig, String> {
+    state
+        .reset_config_to_defaults()
+        .map_err(|e| format!("Failed to reset configuration: {}", e))
+}
+
+fn apply_app_config(state: &AppState, config: AppConfig) -> Result<AppConfig, ConfigUpdateError> {
+    let fields = config.validation_errors();
+    if !fields.is_empty() {
+        return Err(ConfigUpdateError {
+            message: "Invalid configuration".to_string(),
+            fields,
+        });
+    }
+
+    state
+        .replace_config(config.clone())
+        .map_err(|e| ConfigUpdateError {
+            message: format!("Failed to update configuration: {}", e),
+            fields: Vec::new(),
+        })?;
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_app_info() {
+        let state = AppState::new();
+        let info = app_info(&state, 3);
+
+        assert_eq!(info.version.version, env!("CARGO_PKG_VERSION"));
+        assert!(info.config.validate().is_ok());
+        assert_eq!(info.stats_count, 3);
+        assert!(info.database_path.ends_with("compression_stats.db"));
+    }
+
+    #[test]
+    fn test_update_app_config() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let state = AppState::new().with_config_path(temp_dir.path().join("config.json"));
+
+        let mut config = state.get_config().clone();
+        config.max_file_size = 4096;
+        apply_app_config(&state, config).unwrap();
+        assert_eq!(state.get_config().max_file_size, 4096);
+
+        let mut invalid = state.get_config().clone();
+        invalid.max_file_size = 0;
+        invalid.compression.default_quality = 0;
+        let error = apply_app_config(&state, invalid).unwrap_err();
+        let fields: Vec<_> = error.fields.iter().map(|f| f.field.as_str()).collect();
+        assert_eq!(fields, vec!["max_file_size", "compression.default_quality"]);
+
+        // La configuration valide précédente est conservée
+        assert_eq!(state.get_config().max_file_size, 4096);
+    }
+}
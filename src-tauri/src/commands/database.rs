@@ -4,7 +4,7 @@ use tauri::AppHandle;
 /// Test database connection
 #[tauri::command]
 pub async fn test_database_connection(app: AppHandle) -> Result<String, String> {
-    println!("Testing database connection...");
+    log::info!("Testing database connection...");
     let db_manager = DatabaseManager::new(&app)?;
     db_manager.connect()?;
     Ok("Database connection successful".to_string())
@@ -13,7 +13,7 @@ pub async fn test_database_connection(app: AppHandle) -> Result<String, String>
 /// Initialise la base de données au démarrage de l'application
 #[tauri::command]
 pub async fn init_database(app: AppHandle) -> Result<String, String> {
-    println!("Initializing database...");
+    log::info!("Initializing database...");
 
     // Crée le gestionnaire de base de données
     let db_manager = DatabaseManager::new(&app)?;
@@ -27,7 +27,7 @@ pub async fn init_database(app: AppHandle) -> Result<String, String> {
     let count = db_manager.count_records()?;
     let message = format!("Database initialized successfully with {} records", count);
 
-    println!("{}", message);
+    log::info!("{}", message);
     Ok(message)
 }
 
@@ -188,7 +188,7 @@ pub async fn seed_compression_database(app: AppHandle) -> Result<String, String>
 
         match db_manager.insert_compression_record(&record) {
             Ok(_) => inserted_count += 1,
-            Err(e) => println!("Failed to insert seed record: {}", e),
+            Err(e) => log::warn!("Failed to insert seed record: {}", e),
         }
     }
 
@@ -1,7 +1,12 @@
-use crate::domain::{copy_file, get_file_info, read_image_file, validate_image_file, AppState};
+use crate::domain::{
+    batch_copy_files_unique, classify_image_type, comprehensive_analysis, compute_blurhash,
+    copy_file_unique_into, delete_file, extract_metadata, get_file_info, is_supported_image_file,
+    read_image_file, validate_image_file, validate_path_depth, AppState, ColorAnalysis,
+    CompressionPotential, ImageMetadata, PathUtils, QualityAssessment,
+};
 use base64::{engine::general_purpose, Engine as _};
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use tauri::{AppHandle, State};
 
 /// Commande pour ouvrir le dialog de sélection de fichiers
@@ -38,6 +43,79 @@ pub struct FileInfo {
     pub is_image: bool,
 }
 
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ScannedImage {
+    pub path: String,
+    pub size: u64,
+}
+
+/// Commande pour lister les images d'un dossier (optionnellement récursif)
+#[tauri::command]
+pub async fn scan_directory_for_images(
+    dir: String,
+    recursive: bool,
+    max_depth: Option<u32>,
+    state: State<'_, AppState>,
+) -> Result<Vec<ScannedImage>, String> {
+    let max_path_depth = state.get_config().security.max_path_depth;
+
+    // Le dossier racine doit être lisible, les sous-dossiers sont ignorés en cas d'erreur
+    let entries =
+        std::fs::read_dir(&dir).map_err(|e| format!("Failed to read directory: {}", e))?;
+
+    let max_depth = if recursive {
+        max_depth.unwrap_or(u32::MAX)
+    } else {
+        0
+    };
+    let mut images = Vec::new();
+    scan_entries(entries, 0, max_depth, max_path_depth, &mut images);
+
+    images.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(images)
+}
+
+fn scan_entries(
+    entries: std::fs::ReadDir,
+    depth: u32,
+    max_depth: u32,
+    max_path_depth: u32,
+    images: &mut Vec<ScannedImage>,
+) {
+    let mut subdirectories: Vec<PathBuf> = Vec::new();
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if validate_path_depth(&path, max_path_depth).is_err() {
+            continue;
+        }
+
+        match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => subdirectories.push(path),
+            Ok(file_type) if file_type.is_file() && is_supported_image_file(&path) => {
+                if let Ok(metadata) = entry.metadata() {
+                    images.push(ScannedImage {
+                        path: path.to_string_lossy().to_string(),
+                        size: metadata.len(),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if depth >= max_depth {
+        return;
+    }
+
+    for subdirectory in subdirectories {
+        match std::fs::read_dir(&subdirectory) {
+            Ok(entries) => scan_entries(entries, depth + 1, max_depth, max_path_depth, images),
+            Err(e) => log::warn!("Skipping unreadable directory {:?}: {}", subdirectory, e),
+        }
+    }
+}
+
 /// Commande pour générer un preview base64 à partir d'un chemin de fichier
 #[tauri::command]
 pub async fn generate_preview(
@@ -78,37 +156,34 @@ pub async fn save_to_downloads(
     let downloads_dir =
         dirs::download_dir().ok_or_else(|| "Could not find Downloads directory".to_string())?;
 
-    // Get file name
-    let file_name = source_path
-        .file_name()
-        .ok_or_else(|| "Invalid file path".to_string())?;
-
-    let target_path = downloads_dir.join(file_name);
-
-    // Make unique if file already exists
-    let unique_target = crate::domain::PathUtils::make_unique_filename(&target_path);
-
-    // Copy file
-    copy_file(source_path, &unique_target)
+    // Copy under a unique name if the file already exists
+    let operation = copy_file_unique_into(source_path, &downloads_dir)
         .map_err(|e| format!("Failed to copy file to Downloads: {}", e))?;
 
-    Ok(unique_target.to_string_lossy().to_string())
+    Ok(operation.target_path)
 }
 
 /// Commande pour sauvegarder tous les fichiers dans le dossier Downloads
 #[tauri::command]
 pub async fn save_all_to_downloads(
     file_paths: Vec<String>,
-    _state: State<'_, AppState>,
+    max_concurrency: Option<usize>,
+    state: State<'_, AppState>,
 ) -> Result<Vec<String>, String> {
-    let mut saved_paths = Vec::new();
+    let downloads_dir =
+        dirs::download_dir().ok_or_else(|| "Could not find Downloads directory".to_string())?;
+    let max_concurrency =
+        max_concurrency.unwrap_or(state.get_config().performance.max_concurrent_operations);
+
+    let results = batch_copy_files_unique(&file_paths, &downloads_dir, max_concurrency);
 
-    for file_path in file_paths {
-        match save_to_downloads(file_path, _state.clone()).await {
-            Ok(saved_path) => saved_paths.push(saved_path),
+    let mut saved_paths = Vec::new();
+    for (file_path, result) in file_paths.iter().zip(results) {
+        match result {
+            Ok(operation) => saved_paths.push(operation.target_path),
             Err(e) => {
                 // Log error but continue with other files
-                eprintln!("Failed to save file: {}", e);
+                log::error!("Failed to save file {}: {}", file_path, e);
             }
         }
     }
@@ -120,6 +195,46 @@ pub async fn save_all_to_downloads(
     }
 }
 
+/// Commande pour supprimer un fichier produit par l'application (temporaire ou Downloads)
+#[tauri::command]
+pub async fn delete_output_file(path: String, state: State<'_, AppState>) -> Result<(), String> {
+    let mut output_dirs = vec![
+        std::env::temp_dir(),
+        PathBuf::from(&state.get_config().temp_dir),
+    ];
+    output_dirs.extend(dirs::download_dir());
+
+    delete_output(Path::new(&path), &output_dirs)
+}
+
+fn delete_output(path: &Path, output_dirs: &[PathBuf]) -> Result<(), String> {
+    PathUtils::validate_safe_path(path)
+        .map_err(|e| format!("Refusing to delete {}: {}", path.display(), e))?;
+
+    // Liens symboliques résolus avant de comparer aux dossiers de sortie
+    let resolved = path
+        .canonicalize()
+        .map_err(|e| format!("Cannot delete {}: {}", path.display(), e))?;
+    if !resolved.is_file() {
+        return Err(format!("Cannot delete {}: not a file", path.display()));
+    }
+
+    let is_output = output_dirs
+        .iter()
+        .filter_map(|dir| dir.canonicalize().ok())
+        .any(|dir| resolved.starts_with(dir));
+    if !is_output {
+        return Err(format!(
+            "Refusing to delete {}: not in a Plume output directory",
+            path.display()
+        ));
+    }
+
+    delete_file(&resolved)
+        .map(|_| ())
+        .map_err(|e| format!("Failed to delete {}: {}", path.display(), e))
+}
+
 /// Commande pour nettoyer les fichiers temporaires de l'application
 #[tauri::command]
 pub async fn clear_app_temporary_files(_state: State<'_, AppState>) -> Result<(), String> {
@@ -130,15 +245,14 @@ pub async fn clear_app_temporary_files(_state: State<'_, AppState>) -> Result<()
         return Ok(());
     }
 
-    // Clean up files with "dropped" or "plume" prefix
-    let cleaned_files = crate::domain::cleanup_temp_files(&temp_dir, "dropped")
-        .map_err(|e| format!("Failed to cleanup temp files: {}", e))?;
-
-    let more_cleaned = crate::domain::cleanup_temp_files(&temp_dir, "plume")
-        .map_err(|e| format!("Failed to cleanup temp files: {}", e))?;
-
-    let total_cleaned = cleaned_files.len() + more_cleaned.len();
-    println!("Cleaned {} temporary files", total_cleaned);
+    // Only files we generated: "dropped_…" / "plume_…" followed by a timestamp
+    let mut total_cleaned = 0;
+    for prefix in crate::domain::TEMP_FILE_PREFIXES {
+        total_cleaned += crate::domain::cleanup_temp_files(&temp_dir, prefix, None)
+            .map_err(|e| format!("Failed to cleanup temp files: {}", e))?
+            .len();
+    }
+    log::info!("Cleaned {} temporary files", total_cleaned);
 
     Ok(())
 }
@@ -161,3 +275,221 @@ pub async fn get_file_information(
         is_image: metadata.is_image,
     })
 }
+
+/// Image size read from the file header
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ImageDimensions {
+    pub width: u32,
+    pub height: u32,
+    pub format: String,
+}
+
+/// Commande pour lire les dimensions d'une image sans la décoder
+#[tauri::command]
+pub async fn get_image_dimensions(
+    file_path: String,
+    _state: State<'_, AppState>,
+) -> Result<ImageDimensions, String> {
+    read_image_dimensions(Path::new(&file_path))
+}
+
+fn read_image_dimensions(path: &Path) -> Result<ImageDimensions, String> {
+    // Seul l'en-tête est lu : ni les pixels ni le reste du fichier
+    let reader = image::ImageReader::open(path)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(|e| format!("Failed to open image: {}", e))?;
+
+    let format = reader
+        .format()
+        .and_then(|format| format.extensions_str().first().copied())
+        .ok_or_else(|| format!("Unrecognized image format: {}", path.display()))?
+        .to_string();
+    let (width, height) = reader
+        .into_dimensions()
+        .map_err(|e| format!("Failed to read image header: {}", e))?;
+
+    Ok(ImageDimensions {
+        width,
+        height,
+        format,
+    })
+}
+
+/// Commande pour générer le placeholder BlurHash d'une image (4x3 composantes par défaut)
+#[tauri::command]
+pub async fn get_blurhash(
+    file_path: String,
+    x_components: Option<u32>,
+    y_components: Option<u32>,
+    _state: State<'_, AppState>,
+) -> Result<String, String> {
+    let path = Path::new(&file_path);
+    let data = read_image_file(path).map_err(|e| format!("Failed to read image: {}", e))?;
+    let format = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+        .unwrap_or_default();
+
+    let metadata =
+        extract_metadata(&data, &format).map_err(|e| format!("Analysis failed: {}", e))?;
+    compute_blurhash(
+        &data,
+        &metadata,
+        x_components.unwrap_or(4),
+        y_components.unwrap_or(3),
+    )
+    .map_err(|e| format!("BlurHash failed: {}", e))
+}
+
+/// Full analysis of an image for the details panel
+#[derive(Debug, Serialize)]
+pub struct FileAnalysis {
+    pub metadata: ImageMetadata,
+    pub quality: QualityAssessment,
+    pub colors: ColorAnalysis,
+    pub compression: CompressionPotential,
+}
+
+/// Commande pour analyser une image sans la compresser
+#[tauri::command]
+pub async fn analyze_file(
+    file_path: String,
+    _state: State<'_, AppState>,
+) -> Result<FileAnalysis, String> {
+    analyze_path(Path::new(&file_path))
+}
+
+fn analyze_path(path: &Path) -> Result<FileAnalysis, String> {
+    let data = read_image_file(path).map_err(|e| format!("Failed to read image: {}", e))?;
+    let format = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+        .unwrap_or_default();
+
+    let mut metadata =
+        extract_metadata(&data, &format).map_err(|e| format!("Analysis failed: {}", e))?;
+    metadata.image_type = classify_image_type(&metadata);
+
+    let (quality, colors, compression) =
+        comprehensive_analysis(&metadata).map_err(|e| format!("Analysis failed: {}", e))?;
+
+    Ok(FileAnalysis {
+        metadata,
+        quality,
+        colors,
+        compression,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_image_dimensions() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("banner.png");
+        image::RgbImage::new(40, 30).save(&path).unwrap();
+
+        assert_eq!(
+            read_image_dimensions(&path).unwrap(),
+            ImageDimensions {
+                width: 40,
+                height: 30,
+                format: "png".to_string(),
+            }
+        );
+
+        let not_an_image = temp_dir.path().join("notes.png");
+        std::fs::write(&not_an_image, b"just some text").unwrap();
+        assert!(read_image_dimensions(&not_an_image).is_err());
+    }
+
+    #[test]
+    fn test_delete_output() {
+        let output_dir = tempfile::TempDir::new().unwrap();
+        let output = output_dir.path().join("photo_compressed.webp");
+        std::fs::write(&output, b"compressed").unwrap();
+
+        let output_dirs = vec![output_dir.path().to_path_buf()];
+        delete_output(&output, &output_dirs).unwrap();
+        assert!(!output.exists());
+
+        // Hors des dossiers de sortie : refusé, le fichier reste en place
+        let elsewhere = tempfile::TempDir::new().unwrap();
+        let original = elsewhere.path().join("original.png");
+        std::fs::write(&original, b"original").unwrap();
+        assert!(delete_output(&original, &output_dirs).is_err());
+        assert!(original.exists());
+
+        let traversal = output_dir.path().join("../original.png");
+        assert!(delete_output(&traversal, &output_dirs).is_err());
+        assert!(delete_output(output_dir.path(), &output_dirs).is_err());
+    }
+
+    #[test]
+    fn test_analyze_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("logo.png");
+        image::RgbaImage::from_pixel(64, 64, image::Rgba([20, 40, 200, 255]))
+            .save(&path)
+            .unwrap();
+
+        let analysis = analyze_path(&path).unwrap();
+        assert!((1..=100).contains(&analysis.compression.recommended_quality));
+
+        let json = serde_json::to_value(&analysis).unwrap();
+        assert!(json["compression"]["riskLevel"].is_string());
+        assert_eq!(json["metadata"]["dimensions"]["width"], 64);
+    }
+
+    fn scan(dir: &Path, max_depth: u32) -> Vec<ScannedImage> {
+        let mut images = Vec::new();
+        scan_entries(
+            std::fs::read_dir(dir).unwrap(),
+            0,
+            max_depth,
+            64,
+            &mut images,
+        );
+        images.sort_by(|a, b| a.path.cmp(&b.path));
+        images
+    }
+
+    #[test]
+    fn test_scan_directory_for_images() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("nested/deeper")).unwrap();
+        std::fs::write(root.join("photo.jpg"), b"jpg").unwrap();
+        std::fs::write(root.join("notes.txt"), b"text").unwrap();
+        std::fs::write(root.join("nested/logo.PNG"), b"png!").unwrap();
+        std::fs::write(root.join("nested/archive.zip"), b"zip").unwrap();
+        std::fs::write(root.join("nested/deeper/icon.webp"), b"webp!").unwrap();
+
+        let names = |images: &[ScannedImage]| -> Vec<String> {
+            images
+                .iter()
+                .map(|image| {
+                    Path::new(&image.path)
+                        .strip_prefix(root)
+                        .unwrap()
+                        .to_string_lossy()
+                        .replace('\\', "/")
+                })
+                .collect()
+        };
+
+        let all = scan(root, u32::MAX);
+        assert_eq!(
+            names(&all),
+            vec!["nested/deeper/icon.webp", "nested/logo.PNG", "photo.jpg"]
+        );
+        assert_eq!(all[1].size, 4);
+
+        assert_eq!(names(&scan(root, 0)), vec!["photo.jpg"]);
+        assert_eq!(names(&scan(root, 1)), vec!["nested/logo.PNG", "photo.jpg"]);
+    }
+}
@@ -0,0 +1,367 @@
+use crate::domain::{atomic_write, read_image_file, PathUtils};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// EXIF block of an image, as shown in the details panel
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ExifData {
+    /// Tag name (`Make`, `ExposureTime`...) to human-readable value
+    pub fields: BTreeMap<String, String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub orientation: Option<u32>, // 1-8, as defined by the EXIF Orientation tag
+}
+
+/// Commande pour lire les métadonnées EXIF (appareil, objectif, exposition, date)
+#[tauri::command]
+pub async fn read_exif(file_path: String) -> Result<ExifData, String> {
+    let data = read_image_file(Path::new(&file_path))
+        .map_err(|e| format!("Failed to read image: {}", e))?;
+    parse_exif(&data)
+}
+
+/// Parse the EXIF block of an image, an image without one giving empty data
+fn parse_exif(data: &[u8]) -> Result<ExifData, String> {
+    let exif = match exif::Reader::new().read_from_container(&mut std::io::Cursor::new(data)) {
+        Ok(exif) => exif,
+        Err(exif::Error::NotFound(_)) | Err(exif::Error::InvalidFormat(_)) => {
+            return Ok(ExifData::default())
+        }
+        Err(e) => return Err(format!("Failed to parse EXIF: {}", e)),
+    };
+
+    // Seule l'image principale compte, pas la vignette (IFD 1)
+    let fields = exif
+        .fields()
+        .filter(|field| field.ifd_num == exif::In::PRIMARY)
+        .filter(|field| !matches!(field.value, exif::Value::Undefined(..)))
+        .map(|field| {
+            let value = match &field.value {
+                // Texte brut, sans les guillemets de display_value
+                exif::Value::Ascii(strings) => strings
+                    .iter()
+                    .map(|bytes| String::from_utf8_lossy(bytes).trim().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                _ => field.display_value().with_unit(&exif).to_string(),
+            };
+            (field.tag.to_string(), value)
+        })
+        .collect();
+
+    let uint_field = |tags: &[exif::Tag]| {
+        tags.iter().find_map(|&tag| {
+            exif.get_field(tag, exif::In::PRIMARY)
+                .and_then(|field| field.value.get_uint(0))
+        })
+    };
+
+    Ok(ExifData {
+        fields,
+        width: uint_field(&[exif::Tag::PixelXDimension, exif::Tag::ImageWidth]),
+        height: uint_field(&[exif::Tag::PixelYDimension, exif::Tag::ImageLength]),
+        orientation: uint_field(&[exif::Tag::Orientation]),
+    })
+}
+
+/// EXIF tag pointing to the GPS IFD
+const GPS_IFD_POINTER_TAG: u16 = 0x8825;
+
+/// Commande pour retirer la localisation GPS d'un JPEG sans réencoder les pixels
+/// (renvoie le nombre de champs GPS retirés)
+#[tauri::command]
+pub async fn strip_location(file_path: String, output_path: String) -> Result<u32, String> {
+    let output = Path::new(&output_path);
+    PathUtils::validate_safe_path(output)
+        .map_err(|e| format!("Refusing to write {}: {}", output.display(), e))?;
+
+    let data = read_image_file(Path::new(&file_path))
+        .map_err(|e| format!("Failed to read image: {}", e))?;
+    let (stripped, removed) = strip_location_from_jpeg(&data)?;
+
+    atomic_write(output, &stripped).map_err(|e| format!("Failed to write image: {}", e))?;
+    Ok(removed)
+}
+
+/// Remove the GPS IFD from a JPEG's EXIF block, leaving every other byte in place
+fn strip_location_from_jpeg(data: &[u8]) -> Result<(Vec<u8>, u32), String> {
+    if data.get(0..2) != Some(&[0xFF, 0xD8][..]) {
+        return Err("Location stripping only supports JPEG images".to_string());
+    }
+
+    let mut stripped = data.to_vec();
+    let removed = match find_exif_tiff(data) {
+        Some(tiff_range) => remove_gps_ifd(&mut stripped[tiff_range])?,
+        None => 0,
+    };
+
+    // Vérifie en relisant qu'il ne reste aucun champ GPS
+    if let Ok(exif) = exif::Reader::new().read_from_container(&mut std::io::Cursor::new(&stripped))
+    {
+        if exif
+            .fields()
+            .any(|field| field.tag.context() == exif::Context::Gps)
+        {
+            return Err("GPS fields still present after stripping".to_string());
+        }
+    }
+
+    Ok((stripped, removed))
+}
+
+/// Byte range of the TIFF data inside the APP1 "Exif" segment, if any
+fn find_exif_tiff(data: &[u8]) -> Option<std::ops::Range<usize>> {
+    let mut pos = 2;
+    while let Some(&[0xFF, marker, high, low]) = data.get(pos..pos + 4) {
+        // Début des données compressées ou fin d'image : plus de segment APPn
+        if marker == 0xDA || marker == 0xD9 {
+            return None;
+        }
+        let length = u16::from_be_bytes([high, low]) as usize;
+        let end = pos + 2 + length;
+        if length < 2 || end > data.len() {
+            return None;
+        }
+
+        let payload = pos + 4..end;
+        if marker == 0xE1 && data[payload.clone()].starts_with(b"Exif\0\0") {
+            return Some(payload.start + 6..payload.end);
+        }
+        pos = end;
+    }
+    None
+}
+
+/// Bounds-checked reader/writer for TIFF data in either byte order
+struct Tiff<'a> {
+    data: &'a mut [u8],
+    little_endian: bool,
+}
+
+impl Tiff<'_> {
+    fn bytes<const N: usize>(&self, offset: usize) -> Result<[u8; N], String> {
+        self.data
+            .get(offset..offset + N)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| "Truncated EXIF data".to_string())
+    }
+
+    fn u16_at(&self, offset: usize) -> Result<u16, String> {
+        let bytes = self.bytes::<2>(offset)?;
+        Ok(if self.little_endian {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        })
+    }
+
+    fn u32_at(&self, offset: usize) -> Result<u32, String> {
+        let bytes = self.bytes::<4>(offset)?;
+        Ok(if self.little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    }
+
+    fn zero(&mut self, range: std::ops::Range<usize>) -> Result<(), String> {
+        self.data
+            .get_mut(range)
+            .ok_or_else(|| "Truncated EXIF data".to_string())?
+            .fill(0);
+        Ok(())
+    }
+}
+
+/// Wipe the GPS IFD and drop its pointer from IFD0; returns the number of GPS fields removed
+fn remove_gps_ifd(data: &mut [u8]) -> Result<u32, String> {
+    let little_endian = match data.get(0..2) {
+        Some(b"II") => true,
+        Some(b"MM") => false,
+        _ => return Err("Invalid EXIF byte order".to_string()),
+    };
+    let mut tiff = Tiff {
+        data,
+        little_endian,
+    };
+
+    let ifd0 = tiff.u32_at(4)? as usize;
+    let count = tiff.u16_at(ifd0)? as usize;
+    let entry_offset = |index: usize| ifd0 + 2 + index * 12;
+    let gps_index = match (0..count).find(|&index| {
+        tiff.u16_at(entry_offset(index))
+            .is_ok_and(|tag| tag == GPS_IFD_POINTER_TAG)
+    }) {
+        Some(index) => index,
+        None => return Ok(0),
+    };
+
+    // Efface les valeurs hors-ligne de chaque champ GPS, puis l'IFD lui-même
+    let gps_ifd = tiff.u32_at(entry_offset(gps_index) + 8)? as usize;
+    let gps_count = tiff.u16_at(gps_ifd)? as usize;
+    for index in 0..gps_count {
+        let entry = gps_ifd + 2 + index * 12;
+        let value_size = match tiff.u16_at(entry + 2)? {
+            1 | 2 | 6 | 7 => 1,
+            3 | 8 => 2,
+            4 | 9 | 11 => 4,
+            5 | 10 | 12 => 8,
+            _ => 0,
+        } * tiff.u32_at(entry + 4)? as usize;
+        if value_size > 4 {
+            let value_offset = tiff.u32_at(entry + 8)? as usize;
+            tiff.zero(value_offset..value_offset + value_size)?;
+        }
+    }
+    tiff.zero(gps_ifd..gps_ifd + 2 + gps_count * 12 + 4)?;
+
+    // Retire l'entrée GPSInfo d'IFD0 : les entrées suivantes et l'offset du prochain IFD remontent
+    let ifd0_end = entry_offset(count) + 4;
+    tiff.data
+        .get_mut(entry_offset(gps_index)..ifd0_end)
+        .ok_or_else(|| "Truncated EXIF data".to_string())?
+        .rotate_left(12);
+    tiff.zero(ifd0_end - 12..ifd0_end)?;
+    let new_count = (count - 1) as u16;
+    let count_bytes = if little_endian {
+        new_count.to_le_bytes()
+    } else {
+        new_count.to_be_bytes()
+    };
+    tiff.data[ifd0..ifd0 + 2].copy_from_slice(&count_bytes);
+
+    Ok(gps_count as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// JPEG with an APP1 EXIF segment holding the given fields
+    fn jpeg_with_exif(fields: &[exif::Field]) -> Vec<u8> {
+        let mut writer = exif::experimental::Writer::new();
+        for field in fields {
+            writer.push_field(field);
+        }
+        let mut tiff = std::io::Cursor::new(Vec::new());
+        writer.write(&mut tiff, false).unwrap();
+        let tiff = tiff.into_inner();
+
+        let img = image::RgbImage::from_pixel(16, 8, image::Rgb([200, 120, 40]));
+        let mut jpeg = Vec::new();
+        image::codecs::jpeg::JpegEncoder::new(&mut jpeg)
+            .encode_image(&img)
+            .unwrap();
+
+        // APP1 juste après SOI : "Exif\0\0" puis les données TIFF
+        let mut segment = vec![0xFF, 0xE1];
+        segment.extend(((tiff.len() + 8) as u16).to_be_bytes());
+        segment.extend(b"Exif\0\0");
+        segment.extend(tiff);
+        jpeg.splice(2..2, segment);
+        jpeg
+    }
+
+    #[test]
+    fn test_parse_exif_fields() {
+        let ascii = |tag, text: &str| exif::Field {
+            tag,
+            ifd_num: exif::In::PRIMARY,
+            value: exif::Value::Ascii(vec![text.as_bytes().to_vec()]),
+        };
+        let fields = [
+            ascii(exif::Tag::Make, "Fujifilm"),
+            ascii(exif::Tag::Model, "X-T4"),
+            ascii(exif::Tag::DateTimeOriginal, "2024:06:01 18:30:00"),
+            exif::Field {
+                tag: exif::Tag::Orientation,
+                ifd_num: exif::In::PRIMARY,
+                value: exif::Value::Short(vec![6]),
+            },
+            exif::Field {
+                tag: exif::Tag::PixelXDimension,
+                ifd_num: exif::In::PRIMARY,
+                value: exif::Value::Long(vec![6240]),
+            },
+            exif::Field {
+                tag: exif::Tag::PixelYDimension,
+                ifd_num: exif::In::PRIMARY,
+                value: exif::Value::Long(vec![4160]),
+            },
+        ];
+
+        let exif_data = parse_exif(&jpeg_with_exif(&fields)).unwrap();
+        assert_eq!(exif_data.fields["Make"], "Fujifilm");
+        assert_eq!(exif_data.fields["Model"], "X-T4");
+        assert_eq!(exif_data.fields["DateTimeOriginal"], "2024:06:01 18:30:00");
+        assert_eq!(exif_data.orientation, Some(6));
+        assert_eq!(
+            (exif_data.width, exif_data.height),
+            (Some(6240), Some(4160))
+        );
+    }
+
+    #[test]
+    fn test_image_without_exif() {
+        let img = image::RgbImage::from_pixel(4, 4, image::Rgb([0, 0, 0]));
+        let mut png = Vec::new();
+        image::DynamicImage::ImageRgb8(img)
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+
+        let exif_data = parse_exif(&png).unwrap();
+        assert!(exif_data.fields.is_empty());
+        assert_eq!(exif_data.orientation, None);
+    }
+
+    #[test]
+    fn test_strip_location() {
+        let ascii = |tag, text: &str| exif::Field {
+            tag,
+            ifd_num: exif::In::PRIMARY,
+            value: exif::Value::Ascii(vec![text.as_bytes().to_vec()]),
+        };
+        let rational = |tag, values: &[(u32, u32)]| exif::Field {
+            tag,
+            ifd_num: exif::In::PRIMARY,
+            value: exif::Value::Rational(
+                values
+                    .iter()
+                    .map(|&(num, denom)| exif::Rational { num, denom })
+                    .collect(),
+            ),
+        };
+        let fields = [
+            ascii(exif::Tag::Make, "Fujifilm"),
+            ascii(exif::Tag::Model, "X-T4"),
+            ascii(exif::Tag::GPSLatitudeRef, "N"),
+            rational(exif::Tag::GPSLatitude, &[(48, 1), (51, 1), (2400, 100)]),
+            ascii(exif::Tag::GPSLongitudeRef, "E"),
+            rational(exif::Tag::GPSLongitude, &[(2, 1), (21, 1), (700, 100)]),
+        ];
+        let jpeg = jpeg_with_exif(&fields);
+        assert!(parse_exif(&jpeg)
+            .unwrap()
+            .fields
+            .contains_key("GPSLatitude"));
+
+        let (stripped, removed) = strip_location_from_jpeg(&jpeg).unwrap();
+        assert_eq!(removed, 4);
+        assert_eq!(stripped.len(), jpeg.len());
+
+        let exif_data = parse_exif(&stripped).unwrap();
+        assert!(!exif_data.fields.keys().any(|key| key.starts_with("GPS")));
+        assert_eq!(exif_data.fields["Model"], "X-T4");
+        assert_eq!(exif_data.fields["Make"], "Fujifilm");
+
+        // Pixels intacts : même image décodée
+        let original = image::load_from_memory(&jpeg).unwrap().to_rgb8();
+        let decoded = image::load_from_memory(&stripped).unwrap().to_rgb8();
+        assert_eq!(original, decoded);
+
+        // Sans GPS : rien à retirer, fichier inchangé
+        let (unchanged, removed) = strip_location_from_jpeg(&stripped).unwrap();
+        assert_eq!((unchanged, removed), (stripped, 0));
+    }
+}
@@ -1,20 +1,31 @@
+pub mod capabilities;
 pub mod compression;
+pub mod config;
 pub mod database;
 pub mod file;
+pub mod metadata;
 pub mod progress;
 pub mod stats;
 
-pub use compression::{compress_batch, compress_image};
+pub use capabilities::get_supported_formats;
+pub use compression::{
+    compress_batch, compress_data_uri, compress_image, estimate_batch, get_compression_presets,
+    quality_size_curve, retry_failed,
+};
+pub use config::{get_app_config, get_app_info, reset_config_to_defaults, update_app_config};
 pub use database::{
     get_compression_prediction, init_database, record_compression_result,
     seed_compression_database, test_compression_prediction, test_database_connection,
 };
 pub use file::{
-    clear_app_temporary_files, generate_preview, get_file_information, save_all_to_downloads,
-    save_to_downloads, select_image_files,
+    analyze_file, clear_app_temporary_files, delete_output_file, generate_preview, get_blurhash,
+    get_file_information, get_image_dimensions, save_all_to_downloads, save_to_downloads,
+    scan_directory_for_images, select_image_files,
 };
+pub use metadata::{read_exif, strip_location};
 // Progress-related functions are now handled by the AdaptiveProgressManager in TypeScript
 pub use stats::{
-    get_compression_estimation, get_stats_count, get_stats_summary, record_compression_stat,
-    reset_compression_stats,
+    calibrate_predictions, get_compression_by_image_type, get_compression_estimation,
+    get_compression_prediction_accuracy, get_stats_count, get_stats_summary,
+    prune_compression_stats, record_compression_stat, reset_compression_stats,
 };
@@ -1,13 +1,22 @@
-use crate::domain::{AppState, EstimationQuery, EstimationResult, SqliteStatsStore, StatsStore};
+use crate::domain::{
+    AppState, BatchEstimation, CompressionSettings, EstimationQuery, EstimationResult,
+    ImageTypeCompression, OutputFormat, PredictionAccuracy, SqliteStatsStore, StatsStore,
+};
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 use std::sync::Mutex;
 use tauri::State;
 
+/// Location of the SQLite database backing the stats store
+pub(crate) fn stats_db_path() -> PathBuf {
+    std::env::temp_dir()
+        .join("plume")
+        .join("compression_stats.db")
+}
+
 // Global stats store - in a real app, this would be managed by AppState
 static STATS_STORE: std::sync::LazyLock<Mutex<SqliteStatsStore>> = std::sync::LazyLock::new(|| {
-    let db_path = std::env::temp_dir()
-        .join("plume")
-        .join("compression_stats.db");
+    let db_path = stats_db_path();
     std::fs::create_dir_all(db_path.parent().unwrap()).unwrap();
     let store = SqliteStatsStore::new(db_path.to_str().unwrap()).unwrap();
     Mutex::new(store)
@@ -30,6 +39,8 @@ pub struct RecordStatRequest {
     pub compressed_size: u64,
     pub quality_setting: u8,
     pub lossy_mode: bool,
+    #[serde(default)]
+    pub predicted_percent: Option<f64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -78,13 +89,15 @@ pub async fn record_compression_stat(
         _ => crate::domain::OutputFormat::WebP,
     };
 
-    let stat = crate::domain::create_stat(
+    let mut stat = crate::domain::create_stat(
         request.input_format,
         request.output_format,
         request.original_size,
         request.compressed_size,
         &crate::domain::CompressionSettings::new(request.quality_setting, output_format_enum),
     );
+    stat.lossy_mode = request.lossy_mode;
+    stat.predicted_percent = request.predicted_percent;
 
     let mut store = STATS_STORE
         .lock()
@@ -95,8 +108,73 @@ pub async fn record_compression_stat(
         .map_err(|e| format!("Failed to save stat: {}", e))
 }
 
+/// Compress sample files in memory and feed their real ratios into the stats store
+#[tauri::command]
+pub async fn calibrate_predictions(
+    sample_paths: Vec<String>,
+    format: String,
+    quality: u8,
+    _state: State<'_, AppState>,
+) -> Result<BatchEstimation, String> {
+    let output_format = OutputFormat::from_string(&format)
+        .ok_or_else(|| format!("Unsupported output format: {}", format))?;
+    let settings = CompressionSettings::new(quality, output_format);
+
+    // Compression hors du runtime async et sans le verrou : les autres commandes
+    // de stats restent disponibles pendant la calibration
+    let sample_settings = settings.clone();
+    let stats = tauri::async_runtime::spawn_blocking(move || {
+        crate::domain::calibration_stats(&sample_paths, &sample_settings)
+    })
+    .await
+    .map_err(|e| format!("Calibration task failed: {}", e))?
+    .map_err(|e| format!("Calibration failed: {}", e))?;
+
+    let mut store = STATS_STORE
+        .lock()
+        .map_err(|_| "Failed to acquire stats store lock".to_string())?;
+
+    crate::domain::record_calibration(&mut *store, stats, &settings)
+        .map_err(|e| format!("Calibration failed: {}", e))
+}
+
 // record_compression_result_with_time function removed - was unused
 
+/// Compare past predictions with the savings actually achieved
+#[tauri::command]
+pub async fn get_compression_prediction_accuracy(
+    input_format: String,
+    output_format: String,
+    _state: State<'_, AppState>,
+) -> Result<PredictionAccuracy, String> {
+    let store = STATS_STORE
+        .lock()
+        .map_err(|_| "Failed to acquire stats store lock".to_string())?;
+
+    store
+        .get_prediction_accuracy(&input_format.to_lowercase(), &output_format.to_lowercase())
+        .map_err(|e| format!("Failed to compute prediction accuracy: {}", e))
+}
+
+/// Average savings per image type (photo, logo...) for a format pair
+#[tauri::command]
+pub async fn get_compression_by_image_type(
+    input_format: String,
+    output_format: String,
+    _state: State<'_, AppState>,
+) -> Result<Vec<ImageTypeCompression>, String> {
+    let store = STATS_STORE
+        .lock()
+        .map_err(|_| "Failed to acquire stats store lock".to_string())?;
+
+    store
+        .average_compression_by_image_type(
+            &input_format.to_lowercase(),
+            &output_format.to_lowercase(),
+        )
+        .map_err(|e| format!("Failed to compute compression by image type: {}", e))
+}
+
 /// Reset all compression statistics
 #[tauri::command]
 pub async fn reset_compression_stats(_state: State<'_, AppState>) -> Result<(), String> {
@@ -109,9 +187,31 @@ pub async fn reset_compression_stats(_state: State<'_, AppState>) -> Result<(),
         .map_err(|e| format!("Failed to clear stats: {}", e))
 }
 
+/// Drop stats older than `older_than` (RFC 3339) and keep at most `max_count`;
+/// returns the number of deleted stats
+#[tauri::command]
+pub async fn prune_compression_stats(
+    max_count: Option<u32>,
+    older_than: Option<chrono::DateTime<chrono::Utc>>,
+    _state: State<'_, AppState>,
+) -> Result<u32, String> {
+    let mut store = STATS_STORE
+        .lock()
+        .map_err(|_| "Failed to acquire stats store lock".to_string())?;
+
+    store
+        .prune_stats(max_count, older_than)
+        .map_err(|e| format!("Failed to prune stats: {}", e))
+}
+
 /// Get total number of compression statistics
 #[tauri::command]
 pub async fn get_stats_count(_state: State<'_, AppState>) -> Result<u32, String> {
+    stats_count()
+}
+
+/// Number of recorded compression stats
+pub(crate) fn stats_count() -> Result<u32, String> {
     let store = STATS_STORE
         .lock()
         .map_err(|_| "Failed to acquire stats store lock".to_string())?;
@@ -146,6 +246,8 @@ pub async fn get_stats_summary(_state: State<'_, AppState>) -> Result<StatsSumma
             ratio: 1.0,
             confidence: 0.0,
             sample_count: 0,
+            p25_percent: 0.0,
+            p75_percent: 0.0,
         });
 
     Ok(StatsSummary {
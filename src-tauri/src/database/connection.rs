@@ -26,7 +26,7 @@ impl DatabaseManager {
         // Chemin complet vers la base de données
         let db_path = app_data.join("compression_stats.db");
 
-        println!("Database will be created at: {:?}", db_path);
+        log::info!("Database will be created at: {:?}", db_path);
 
         Ok(Self {
             db_path,
@@ -34,6 +34,14 @@ impl DatabaseManager {
         })
     }
 
+    /// Crée un gestionnaire pointant vers un fichier de base donné
+    pub fn from_path(db_path: PathBuf) -> Self {
+        Self {
+            db_path,
+            connection: Mutex::new(None),
+        }
+    }
+
     /// Établit la connexion à la base de données
     pub fn connect(&self) -> Result<(), String> {
         let conn = Connection::open(&self.db_path)
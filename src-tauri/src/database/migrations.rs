@@ -57,7 +57,7 @@ pub fn create_tables(conn: &Connection) -> SqlResult<()> {
         [],
     )?;
 
-    println!("Database tables and indexes created successfully");
+    log::debug!("Database tables and indexes created successfully");
     Ok(())
 }
 
@@ -70,10 +70,10 @@ pub fn initialize_database(conn: &Connection) -> SqlResult<()> {
     let count: i64 = stmt.query_row([], |row| row.get(0))?;
 
     if count == 0 {
-        println!("Database is empty, will be seeded with initial data");
+        log::info!("Database is empty, will be seeded with initial data");
         // La logique de seeding sera appelée depuis le gestionnaire principal
     } else {
-        println!(
+        log::info!(
             "Database already contains {} compression stats records",
             count
         );
@@ -0,0 +1,236 @@
+use crate::domain::compression::settings::CompressionSettings;
+use crate::domain::shared::{content_sha256, PerformanceConfig};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{LazyLock, Mutex};
+
+/// Cache key: input content hash + settings hash
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    pub content_hash: String,
+    pub settings_hash: String,
+}
+
+impl CacheKey {
+    /// Build the key for an input and its compression settings
+    pub fn new(input_data: &[u8], settings: &CompressionSettings) -> Self {
+        let settings_json = serde_json::to_vec(settings).unwrap_or_default();
+
+        Self {
+            content_hash: content_sha256(input_data),
+            settings_hash: content_sha256(&settings_json),
+        }
+    }
+}
+
+/// Bounded LRU cache of compressed outputs
+#[derive(Debug)]
+pub struct CompressionCache {
+    entries: HashMap<CacheKey, Vec<u8>>,
+    order: VecDeque<CacheKey>, // Least recently used first
+    capacity_bytes: usize,
+    current_bytes: usize,
+    hits: u64,
+    misses: u64,
+}
+
+impl CompressionCache {
+    /// Create a cache holding at most `capacity_bytes` of output data
+    pub fn new(capacity_bytes: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            capacity_bytes,
+            current_bytes: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Create a cache sized from the performance configuration
+    pub fn from_config(config: &PerformanceConfig) -> Self {
+        Self::new(capacity_from_config(config))
+    }
+
+    /// Change the byte capacity, evicting least recently used entries that no longer fit
+    pub fn set_capacity(&mut self, capacity_bytes: usize) {
+        self.capacity_bytes = capacity_bytes;
+        while self.current_bytes > self.capacity_bytes {
+            match self.order.pop_front() {
+                Some(oldest) => {
+                    if let Some(evicted) = self.entries.remove(&oldest) {
+                        self.current_bytes -= evicted.len();
+                    }
+                }
+                None => break,
+            }
+        }
+    }
+
+    /// Maximum size of cached outputs in bytes
+    pub fn capacity_bytes(&self) -> usize {
+        self.capacity_bytes
+    }
+
+    /// Look up a cached output, marking it as recently used
+    pub fn get(&mut self, key: &CacheKey) -> Option<Vec<u8>> {
+        match self.entries.get(key) {
+            Some(data) => {
+                let data = data.clone();
+                self.touch(key);
+                self.hits += 1;
+                Some(data)
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Store an output, evicting least recently used entries if needed
+    pub fn insert(&mut self, key: CacheKey, data: Vec<u8>) {
+        if data.len() > self.capacity_bytes {
+            return;
+        }
+
+        if let Some(previous) = self.entries.remove(&key) {
+            self.current_bytes -= previous.len();
+            self.order.retain(|k| k != &key);
+        }
+
+        while self.current_bytes + data.len() > self.capacity_bytes {
+            match self.order.pop_front() {
+                Some(oldest) => {
+                    if let Some(evicted) = self.entries.remove(&oldest) {
+                        self.current_bytes -= evicted.len();
+                    }
+                }
+                None => break,
+            }
+        }
+
+        self.current_bytes += data.len();
+        self.order.push_back(key.clone());
+        self.entries.insert(key, data);
+    }
+
+    /// Remove all entries and reset counters
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+        self.current_bytes = 0;
+        self.hits = 0;
+        self.misses = 0;
+    }
+
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Total size of cached outputs in bytes
+    pub fn size_bytes(&self) -> usize {
+        self.current_bytes
+    }
+
+    fn touch(&mut self, key: &CacheKey) {
+        if let Some(position) = self.order.iter().position(|k| k == key) {
+            if let Some(key) = self.order.remove(position) {
+                self.order.push_back(key);
+            }
+        }
+    }
+}
+
+// Global cache used by compress_bytes
+static COMPRESSION_CACHE: LazyLock<Mutex<CompressionCache>> =
+    LazyLock::new(|| Mutex::new(CompressionCache::from_config(&PerformanceConfig::default())));
+
+/// Get the process-wide compression cache
+pub fn global_cache() -> &'static Mutex<CompressionCache> {
+    &COMPRESSION_CACHE
+}
+
+/// Resize the process-wide cache to the configured memory budget
+pub fn configure_global_cache(config: &PerformanceConfig) {
+    if let Ok(mut cache) = COMPRESSION_CACHE.lock() {
+        cache.set_capacity(capacity_from_config(config));
+    }
+}
+
+fn capacity_from_config(config: &PerformanceConfig) -> usize {
+    (config.memory_cache_size_mb * 1024 * 1024) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::compression::formats::OutputFormat;
+
+    fn key(data: &[u8]) -> CacheKey {
+        CacheKey::new(data, &CompressionSettings::new(80, OutputFormat::WebP))
+    }
+
+    #[test]
+    fn test_cache_key_depends_on_settings() {
+        let data = b"image bytes";
+        let webp_80 = CacheKey::new(data, &CompressionSettings::new(80, OutputFormat::WebP));
+        let webp_60 = CacheKey::new(data, &CompressionSettings::new(60, OutputFormat::WebP));
+
+        assert_eq!(webp_80, key(data));
+        assert_eq!(webp_80.content_hash, webp_60.content_hash);
+        assert_ne!(webp_80, webp_60);
+    }
+
+    #[test]
+    fn test_set_capacity_evicts_oldest() {
+        let mut cache = CompressionCache::new(30);
+        cache.insert(key(b"a"), vec![0; 10]);
+        cache.insert(key(b"b"), vec![0; 10]);
+        cache.insert(key(b"c"), vec![0; 10]);
+
+        cache.set_capacity(15);
+        assert_eq!(cache.len(), 1);
+        assert!(cache.get(&key(b"c")).is_some());
+
+        let config = PerformanceConfig {
+            memory_cache_size_mb: 2,
+            ..PerformanceConfig::default()
+        };
+        assert_eq!(
+            CompressionCache::from_config(&config).capacity_bytes(),
+            2 * 1024 * 1024
+        );
+    }
+
+    #[test]
+    fn test_lru_eviction() {
+        let mut cache = CompressionCache::new(10);
+        cache.insert(key(b"a"), vec![0; 4]);
+        cache.insert(key(b"b"), vec![0; 4]);
+
+        // Touch "a" so "b" becomes the least recently used
+        assert!(cache.get(&key(b"a")).is_some());
+        cache.insert(key(b"c"), vec![0; 4]);
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&key(b"b")).is_none());
+        assert!(cache.get(&key(b"a")).is_some());
+        assert!(cache.size_bytes() <= 10);
+
+        // Outputs larger than the capacity are never stored
+        cache.insert(key(b"d"), vec![0; 11]);
+        assert!(cache.get(&key(b"d")).is_none());
+    }
+}
@@ -0,0 +1,139 @@
+use crate::domain::compression::error::{CompressionError, CompressionResult};
+use std::io::Cursor;
+
+const VP8X_ICC_FLAG: u8 = 0x20;
+const VP8X_ALPHA_FLAG: u8 = 0x10;
+
+/// Extract the embedded ICC profile (JPEG APP2, WebP ICCP, PNG iCCP)
+pub fn extract_icc_profile(data: &[u8], format: &str) -> Option<Vec<u8>> {
+    use image::codecs::{jpeg::JpegDecoder, png::PngDecoder, webp::WebPDecoder};
+    use image::ImageDecoder;
+
+    let profile = match format.to_lowercase().as_str() {
+        "jpg" | "jpeg" => JpegDecoder::new(Cursor::new(data)).ok()?.icc_profile(),
+        "webp" => WebPDecoder::new(Cursor::new(data)).ok()?.icc_profile(),
+        "png" => PngDecoder::new(Cursor::new(data)).ok()?.icc_profile(),
+        _ => return None,
+    };
+
+    profile.ok().flatten().filter(|profile| !profile.is_empty())
+}
+
+/// Embed an ICC profile into WebP data, converting to the extended (VP8X) layout if needed
+pub fn embed_webp_icc_profile(
+    webp_data: &[u8],
+    icc_profile: &[u8],
+    width: u32,
+    height: u32,
+) -> CompressionResult<Vec<u8>> {
+    if webp_data.len() < 12 || &webp_data[0..4] != b"RIFF" || &webp_data[8..12] != b"WEBP" {
+        return Err(CompressionError::ProcessingError(
+            "Invalid WebP container".to_string(),
+        ));
+    }
+
+    let mut chunks = parse_riff_chunks(&webp_data[12..])?;
+    // Un seul profil : on remplace l'éventuel ICCP existant
+    chunks.retain(|(fourcc, _)| fourcc != b"ICCP");
+
+    match chunks.first_mut() {
+        Some((fourcc, payload)) if fourcc == b"VP8X" && !payload.is_empty() => {
+            payload[0] |= VP8X_ICC_FLAG;
+        }
+        Some((fourcc, payload)) => {
+            let mut flags = VP8X_ICC_FLAG;
+            if fourcc == b"VP8L" && vp8l_has_alpha(payload) {
+                flags |= VP8X_ALPHA_FLAG;
+            }
+            chunks.insert(0, (*b"VP8X", vp8x_payload(flags, width, height)));
+        }
+        None => {
+            return Err(CompressionError::ProcessingError(
+                "Empty WebP container".to_string(),
+            ))
+        }
+    }
+
+    // L'ordre imposé par la spec : VP8X puis ICCP
+    chunks.insert(1, (*b"ICCP", icc_profile.to_vec()));
+
+    let mut body = Vec::new();
+    body.extend_from_slice(b"WEBP");
+    for (fourcc, payload) in &chunks {
+        body.extend_from_slice(fourcc);
+        body.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        body.extend_from_slice(payload);
+        if payload.len() % 2 == 1 {
+            body.push(0);
+        }
+    }
+
+    let mut output = Vec::with_capacity(body.len() + 8);
+    output.extend_from_slice(b"RIFF");
+    output.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    output.extend_from_slice(&body);
+    Ok(output)
+}
+
+fn parse_riff_chunks(mut data: &[u8]) -> CompressionResult<Vec<([u8; 4], Vec<u8>)>> {
+    let mut chunks = Vec::new();
+
+    while data.len() >= 8 {
+        let fourcc = [data[0], data[1], data[2], data[3]];
+        let size = u32::from_le_bytes([data[4], data[5], data[6], data[7]]) as usize;
+        let padded_size = size + size % 2;
+
+        if data.len() < 8 + size {
+            return Err(CompressionError::ProcessingError(
+                "Truncated WebP chunk".to_string(),
+            ));
+        }
+
+        chunks.push((fourcc, data[8..8 + size].to_vec()));
+        data = &data[(8 + padded_size).min(data.len())..];
+    }
+
+    Ok(chunks)
+}
+
+fn vp8x_payload(flags: u8, width: u32, height: u32) -> Vec<u8> {
+    let mut payload = vec![flags, 0, 0, 0];
+    payload.extend_from_slice(&(width.saturating_sub(1)).to_le_bytes()[..3]);
+    payload.extend_from_slice(&(height.saturating_sub(1)).to_le_bytes()[..3]);
+    payload
+}
+
+/// Read the alpha_is_used bit from a VP8L header
+fn vp8l_has_alpha(payload: &[u8]) -> bool {
+    if payload.len() < 5 {
+        return false;
+    }
+    let bits = u32::from_le_bytes([payload[1], payload[2], payload[3], payload[4]]);
+    (bits >> 28) & 1 == 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embed_webp_icc_profile() {
+        let rgb = vec![120u8; 8 * 8 * 3];
+        let webp_data = webp::Encoder::from_rgb(&rgb, 8, 8).encode(80.0).to_vec();
+        let profile = b"fake icc profile".to_vec();
+
+        let with_profile = embed_webp_icc_profile(&webp_data, &profile, 8, 8).unwrap();
+        assert_eq!(extract_icc_profile(&with_profile, "webp"), Some(profile));
+
+        let decoded = image::load_from_memory(&with_profile).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (8, 8));
+    }
+
+    #[test]
+    fn test_extract_without_profile() {
+        let rgb = vec![120u8; 8 * 8 * 3];
+        let webp_data = webp::Encoder::from_rgb(&rgb, 8, 8).encode(80.0).to_vec();
+        assert_eq!(extract_icc_profile(&webp_data, "webp"), None);
+        assert!(embed_webp_icc_profile(b"not a webp", b"icc", 8, 8).is_err());
+    }
+}
@@ -1,11 +1,38 @@
 use crate::domain::compression::{
+    cache::{global_cache, CacheKey, CompressionCache},
+    color_profile::{embed_webp_icc_profile, extract_icc_profile},
     error::{CompressionError, CompressionResult},
     formats::OutputFormat,
-    settings::CompressionSettings,
-    stats::{create_stat, CompressionStat},
+    settings::{webp_encodes_lossless, CompressionSettings, JpegEncoder, OversizePolicy},
+    stats::{
+        create_stat, BatchEstimation, CompressionStat, EstimationQuery, FileEstimation,
+        CALIBRATION_SOURCE,
+    },
+    store::StatsStore,
 };
+use crate::domain::file::{
+    atomic_write, check_free_space, read_file, validate_image_file, PathUtils,
+};
+use crate::domain::image::{
+    classify_image_type, flatten_alpha, is_likely_optimized, ColorSpace, Dimensions, ImageMetadata,
+    ImageType,
+};
+use crate::domain::shared::{validate_dimensions, DomainError};
+use std::cell::RefCell;
+use std::io::{BufRead, Read, Seek};
 use std::path::Path;
 
+/// Smallest decode progress step worth reporting
+const DECODE_PROGRESS_STEP: f32 = 0.02;
+
+/// Receives the decode progress, from 0.0 to 1.0
+type DecodeProgressCallback = Box<dyn Fn(f32)>;
+
+thread_local! {
+    // Callback de progression du décodage, propre au thread qui compresse
+    static DECODE_PROGRESS: RefCell<Option<DecodeProgressCallback>> = const { RefCell::new(None) };
+}
+
 /// Result of a compression operation
 #[derive(Debug, Clone)]
 pub struct CompressionOutput {
@@ -14,6 +41,10 @@ pub struct CompressionOutput {
     pub compressed_size: u64,
     pub format: OutputFormat,
     pub savings_percent: f64,
+    pub image_type: Option<String>, // Classification of the source image
+    pub skipped_already_optimized: bool, // Original bytes kept, re-encoding would not help
+    pub kept_original: bool, // Output would have been larger, original bytes and extension kept
+    pub lossy_mode: bool,    // The encoder actually discarded data (lossy WebP/JPEG)
 }
 
 impl CompressionOutput {
@@ -24,7 +55,7 @@ impl CompressionOutput {
         format: OutputFormat,
     ) -> Self {
         let savings_percent = if original_size > 0 {
-            ((original_size - compressed_size) as f64 / original_size as f64) * 100.0
+            ((original_size as f64 - compressed_size as f64) / original_size as f64) * 100.0
         } else {
             0.0
         };
@@ -35,56 +66,537 @@ impl CompressionOutput {
             compressed_size,
             format,
             savings_percent,
+            image_type: None,
+            skipped_already_optimized: false,
+            kept_original: false,
+            lossy_mode: false,
         }
     }
 }
 
+/// Source bytes and their compressed form, not written anywhere yet
+struct EncodedFile {
+    input_format: String,
+    input_data: Vec<u8>,
+    compressed_data: Vec<u8>,
+    skipped_already_optimized: bool,
+    lossy: bool,
+}
+
+/// Result of `compress_or_skip`
+struct CompressedBytes {
+    data: Vec<u8>,
+    skipped_already_optimized: bool, // Original bytes returned as is
+    lossy: bool,                     // Decided from the effective settings, after max_dimensions
+}
+
 /// Compress image file-to-file using the specified settings
 pub fn compress_file_to_file<P: AsRef<Path>>(
     input_path: P,
     output_path: P,
     settings: &CompressionSettings,
 ) -> CompressionResult<CompressionOutput> {
-    validate_settings(settings)?;
+    let encoded = encode_file(input_path.as_ref(), settings)?;
+    write_encoded_file(input_path.as_ref(), output_path.as_ref(), settings, encoded)
+}
 
-    let input_path = input_path.as_ref();
-    let output_path = output_path.as_ref();
+/// `compress_file_to_file` giving up after `timeout`: the worker only encodes, and the
+/// output is written here once it finished in time, never by a late worker
+pub fn compress_file_to_file_with_timeout<P, F>(
+    input_path: P,
+    output_path: P,
+    settings: &CompressionSettings,
+    timeout: Option<std::time::Duration>,
+    on_decode_progress: F,
+) -> CompressionResult<CompressionOutput>
+where
+    P: AsRef<Path>,
+    F: Fn(f32) + Send + 'static,
+{
+    let encode = {
+        let (input_path, settings) = (input_path.as_ref().to_path_buf(), settings.clone());
+        move || with_decode_progress(on_decode_progress, || encode_file(&input_path, &settings))
+    };
+    let encoded = match timeout {
+        Some(timeout) => run_with_timeout(timeout, encode)?,
+        None => encode()?,
+    };
+    write_encoded_file(input_path.as_ref(), output_path.as_ref(), settings, encoded)
+}
 
-    // Get original file size
-    let original_size = std::fs::metadata(input_path)
-        .map_err(|e| CompressionError::IoError(format!("Failed to get file metadata: {}", e)))?
-        .len();
+/// Read and compress a file in memory
+fn encode_file(
+    input_path: &Path,
+    settings: &CompressionSettings,
+) -> CompressionResult<EncodedFile> {
+    validate_settings(settings)?;
 
     // Determine input format from extension
     let input_format = input_path
         .extension()
         .and_then(|ext| ext.to_str())
-        .ok_or_else(|| CompressionError::UnsupportedFormat("No file extension".to_string()))?;
+        .ok_or_else(|| CompressionError::UnsupportedFormat("No file extension".to_string()))?
+        .to_string();
 
-    // Route to appropriate compression function based on target format
+    // Refuse avant de charger le fichier si l'image décodée dépasse la limite
+    if let Ok(reader) = image::ImageReader::open(input_path) {
+        check_memory_limit(reader, settings)?;
+    }
+
+    // Read image file data
+    let input_data = std::fs::read(input_path)
+        .map_err(|e| CompressionError::IoError(format!("Failed to read input file: {}", e)))?;
+
+    let compressed = compress_or_skip(&input_data, &input_format, settings, global_cache())?;
+
+    Ok(EncodedFile {
+        input_format,
+        input_data,
+        compressed_data: compressed.data,
+        skipped_already_optimized: compressed.skipped_already_optimized,
+        lossy: compressed.lossy,
+    })
+}
+
+/// Write an encoded file to `output_path`, or the original bytes if the output would grow
+fn write_encoded_file(
+    input_path: &Path,
+    output_path: &Path,
+    settings: &CompressionSettings,
+    encoded: EncodedFile,
+) -> CompressionResult<CompressionOutput> {
+    let EncodedFile {
+        input_format,
+        input_data,
+        compressed_data,
+        skipped_already_optimized,
+        lossy,
+    } = encoded;
+    let input_format = input_format.as_str();
+
+    // Sortie plus lourde que la source : on garde l'original, avec son extension
+    let kept_original = settings.never_grow && compressed_data.len() > input_data.len();
+    let (output_path, output_data, output_format) = if kept_original {
+        (
+            kept_original_path(input_path, output_path, input_format)?,
+            input_data.as_slice(),
+            OutputFormat::from_string(input_format).unwrap_or(settings.format),
+        )
+    } else {
+        (
+            output_path.to_path_buf(),
+            compressed_data.as_slice(),
+            settings.format,
+        )
+    };
+
+    // Refuse d'écrire un fichier tronqué faute de place
+    if let Some(parent) = output_path.parent() {
+        check_free_space(parent, output_data.len() as u64)
+            .map_err(|e| CompressionError::IoError(e.to_string()))?;
+    }
+
+    // Écriture atomique : une interruption ne laisse jamais de fichier tronqué
+    atomic_write(&output_path, output_data)
+        .map_err(|e| CompressionError::IoError(format!("Failed to write output file: {}", e)))?;
+
+    let mut output = CompressionOutput::new(
+        output_path,
+        input_data.len() as u64,
+        output_data.len() as u64,
+        output_format,
+    );
+    output.image_type = classify_input(&input_data, input_format);
+    output.skipped_already_optimized = skipped_already_optimized;
+    output.kept_original = kept_original;
+    // Octets d'origine conservés : rien n'a été perdu
+    output.lossy_mode = !kept_original && lossy;
+    Ok(output)
+}
+
+/// Where the original bytes go when the output would grow: the output path with the
+/// source extension, renamed rather than overwriting a file the caller never checked
+fn kept_original_path(
+    input_path: &Path,
+    output_path: &Path,
+    input_format: &str,
+) -> CompressionResult<std::path::PathBuf> {
+    let kept_path = output_path.with_extension(input_format);
+
+    let same_file = match (kept_path.canonicalize(), input_path.canonicalize()) {
+        (Ok(kept), Ok(input)) => kept == input,
+        _ => false,
+    };
+    if same_file {
+        return Err(CompressionError::IoError(format!(
+            "Output would be larger than the source, and keeping the original would overwrite {}",
+            input_path.display()
+        )));
+    }
+
+    // La politique de conflit n'a vu que le chemin demandé
+    if kept_path == output_path {
+        Ok(kept_path)
+    } else {
+        Ok(PathUtils::make_unique_filename(&kept_path))
+    }
+}
+
+/// Whether compressing `input_format` with these settings actually discards data
+pub fn is_lossy_encoding(input_format: &str, settings: &CompressionSettings) -> bool {
     match settings.format {
-        OutputFormat::WebP => {
-            compress_to_webp_file(input_path, output_path, input_format, settings)?
+        OutputFormat::Jpeg => !uses_lossless_jpeg_transcode(input_format, settings),
+        _ => settings.is_lossy(),
+    }
+}
+
+/// JPEG -> JPEG without resize can be optimized losslessly at the coefficient level
+fn uses_lossless_jpeg_transcode(input_format: &str, settings: &CompressionSettings) -> bool {
+    settings.lossless
+        && settings.target_dimensions.is_none()
+        && matches!(input_format.to_lowercase().as_str(), "jpg" | "jpeg")
+}
+
+/// Classify the source image (photo, logo...) from its header dimensions
+pub fn classify_input(input_data: &[u8], input_format: &str) -> Option<String> {
+    let metadata = header_metadata(input_data, input_format)?;
+
+    match classify_image_type(&metadata) {
+        ImageType::Unknown => None,
+        image_type => Some(image_type.as_str().to_string()),
+    }
+}
+
+/// Metadata built from the header dimensions only, without decoding the pixels
+fn header_metadata(input_data: &[u8], input_format: &str) -> Option<ImageMetadata> {
+    let (width, height) = image::ImageReader::new(std::io::Cursor::new(input_data))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()?;
+
+    Some(ImageMetadata::new(
+        input_format.to_lowercase(),
+        Dimensions::new(width, height).ok()?,
+        ColorSpace::RGB,
+        input_data.len() as u64,
+    ))
+}
+
+/// Whether the input should be kept as is: same lossy format, no resize, already tight
+fn is_already_optimized(
+    input_data: &[u8],
+    input_format: &str,
+    settings: &CompressionSettings,
+) -> bool {
+    settings.skip_if_no_gain
+        && settings.target_dimensions.is_none()
+        && OutputFormat::from_string(input_format) == Some(settings.format)
+        && header_metadata(input_data, input_format)
+            .is_some_and(|metadata| is_likely_optimized(&metadata))
+}
+
+/// Compress in-memory image data using the specified settings
+pub fn compress_bytes(
+    input_data: &[u8],
+    input_format: &str,
+    settings: &CompressionSettings,
+) -> CompressionResult<Vec<u8>> {
+    compress_bytes_with_cache(input_data, input_format, settings, global_cache())
+}
+
+/// Compress in-memory image data, giving up after `timeout`
+pub fn compress_bytes_with_timeout(
+    input_data: &[u8],
+    input_format: &str,
+    settings: &CompressionSettings,
+    timeout: std::time::Duration,
+) -> CompressionResult<Vec<u8>> {
+    let (input_data, input_format, settings) = (
+        input_data.to_vec(),
+        input_format.to_string(),
+        settings.clone(),
+    );
+    run_with_timeout(timeout, move || {
+        compress_bytes(&input_data, &input_format, &settings)
+    })
+}
+
+/// Workers given up on by `run_with_timeout` that are still running in the background
+static ABANDONED_WORKERS: std::sync::Mutex<usize> = std::sync::Mutex::new(0);
+static ABANDONED_WORKERS_DONE: std::sync::Condvar = std::sync::Condvar::new();
+
+/// Run a compression on a worker thread and stop waiting for it after `timeout`.
+///
+/// The worker is not cancelled: it keeps decoding and encoding in the background, holding
+/// its memory, and its result is dropped. Until it finishes it is counted by
+/// `abandoned_workers`, so schedulers can wait for it with `wait_for_abandoned_workers`.
+pub fn run_with_timeout<T, F>(timeout: std::time::Duration, operation: F) -> CompressionResult<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> CompressionResult<T> + Send + 'static,
+{
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let abandoned = std::sync::Arc::new(std::sync::Mutex::new(false));
+    let worker_abandoned = abandoned.clone();
+    std::thread::spawn(move || {
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(operation));
+        // Verrou tenu jusqu'à l'envoi : l'appelant voit soit le résultat, soit un worker abandonné
+        let abandoned = worker_abandoned
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if *abandoned {
+            release_abandoned_worker();
         }
-        OutputFormat::Png => compress_to_png_file(input_path, output_path, input_format, settings)?,
-        OutputFormat::Jpeg => {
-            compress_to_jpeg_file(input_path, output_path, input_format, settings)?
+        // Panique : l'expéditeur est abandonné, l'appelant reçoit Disconnected
+        if let Ok(result) = result {
+            let _ = sender.send(result);
+        }
+    });
+
+    match receiver.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
+            let mut abandoned = abandoned
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            match receiver.try_recv() {
+                // Terminé juste après le délai : rien ne tourne plus en arrière-plan
+                Ok(result) => result,
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => Err(
+                    CompressionError::ProcessingFailed("Compression worker panicked".to_string()),
+                ),
+                Err(std::sync::mpsc::TryRecvError::Empty) => {
+                    *abandoned = true;
+                    *ABANDONED_WORKERS
+                        .lock()
+                        .unwrap_or_else(|poisoned| poisoned.into_inner()) += 1;
+                    Err(CompressionError::ProcessingError(format!(
+                        "timed out after {} ms",
+                        timeout.as_millis()
+                    )))
+                }
+            }
+        }
+        Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => Err(
+            CompressionError::ProcessingFailed("Compression worker panicked".to_string()),
+        ),
+    }
+}
+
+fn release_abandoned_worker() {
+    let mut count = ABANDONED_WORKERS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    *count = count.saturating_sub(1);
+    ABANDONED_WORKERS_DONE.notify_all();
+}
+
+/// Number of timed-out workers still running in the background
+pub fn abandoned_workers() -> usize {
+    *ABANDONED_WORKERS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Block until every timed-out worker has finished and released its memory
+pub fn wait_for_abandoned_workers() {
+    let count = ABANDONED_WORKERS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    drop(
+        ABANDONED_WORKERS_DONE
+            .wait_while(count, |count| *count > 0)
+            .unwrap_or_else(|poisoned| poisoned.into_inner()),
+    );
+}
+
+/// Run `operation`, reporting to `on_progress` how far (0.0 to 1.0) the source image decode has got.
+/// Only decoders that stream their input (PNG, WebP) report intermediate values.
+pub fn with_decode_progress<T>(
+    on_progress: impl Fn(f32) + 'static,
+    operation: impl FnOnce() -> T,
+) -> T {
+    // Restaure le callback précédent même si l'opération panique
+    struct Restore(Option<DecodeProgressCallback>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            DECODE_PROGRESS.with(|progress| *progress.borrow_mut() = previous);
+        }
+    }
+
+    let _restore =
+        Restore(DECODE_PROGRESS.with(|progress| progress.replace(Some(Box::new(on_progress)))));
+    operation()
+}
+
+/// Reader reporting the share of the input consumed by the decoder,
+/// which tracks decode cost for formats decoded as they are read
+struct ProgressReader<'a> {
+    inner: std::io::Cursor<&'a [u8]>,
+    on_progress: &'a dyn Fn(f32),
+    reported: f32,
+}
+
+impl<'a> ProgressReader<'a> {
+    fn new(data: &'a [u8], on_progress: &'a dyn Fn(f32)) -> Self {
+        Self {
+            inner: std::io::Cursor::new(data),
+            on_progress,
+            reported: 0.0,
+        }
+    }
+
+    fn report(&mut self) {
+        let total = self.inner.get_ref().len().max(1) as f32;
+        let fraction = (self.inner.position() as f32 / total).min(1.0);
+        if fraction - self.reported >= DECODE_PROGRESS_STEP
+            || (fraction >= 1.0 && self.reported < 1.0)
+        {
+            self.reported = fraction;
+            (self.on_progress)(fraction);
+        }
+    }
+}
+
+impl Read for ProgressReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.report();
+        Ok(read)
+    }
+}
+
+impl BufRead for ProgressReader<'_> {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.inner.consume(amount);
+        self.report();
+    }
+}
+
+impl Seek for ProgressReader<'_> {
+    fn seek(&mut self, position: std::io::SeekFrom) -> std::io::Result<u64> {
+        self.inner.seek(position)
+    }
+}
+
+/// Compress in-memory image data, serving identical requests from the given cache
+pub fn compress_bytes_with_cache(
+    input_data: &[u8],
+    input_format: &str,
+    settings: &CompressionSettings,
+    cache: &std::sync::Mutex<CompressionCache>,
+) -> CompressionResult<Vec<u8>> {
+    compress_or_skip(input_data, input_format, settings, cache).map(|compressed| compressed.data)
+}
+
+/// Compress, or return the original bytes when they are already optimized
+fn compress_or_skip(
+    input_data: &[u8],
+    input_format: &str,
+    settings: &CompressionSettings,
+    cache: &std::sync::Mutex<CompressionCache>,
+) -> CompressionResult<CompressedBytes> {
+    validate_settings(settings)?;
+    check_memory_limit(
+        image::ImageReader::new(std::io::Cursor::new(input_data)),
+        settings,
+    )?;
+
+    let settings = &apply_max_dimensions(input_data, settings)?;
+    check_declared_dimensions(input_data, settings)?;
+
+    if is_already_optimized(input_data, input_format, settings) {
+        return Ok(CompressedBytes {
+            data: input_data.to_vec(),
+            skipped_already_optimized: true,
+            lossy: false,
+        });
+    }
+
+    // Un JPEG réduit par max_dimensions est réencodé, même demandé sans perte
+    let lossy = is_lossy_encoding(input_format, settings);
+    let key = CacheKey::new(input_data, settings);
+    if let Some(cached) = cache.lock().ok().and_then(|mut cache| cache.get(&key)) {
+        return Ok(CompressedBytes {
+            data: cached,
+            skipped_already_optimized: false,
+            lossy,
+        });
+    }
+
+    // Le verrou n'est pas tenu pendant la compression
+    let compressed = encode_for_settings(input_data, input_format, settings)?;
+    if settings.verify_output {
+        verify_encoded_output(&compressed, input_data, settings)?;
+    }
+
+    if let Ok(mut cache) = cache.lock() {
+        cache.insert(key, compressed.clone());
+    }
+
+    Ok(CompressedBytes {
+        data: compressed,
+        skipped_already_optimized: false,
+        lossy,
+    })
+}
+
+/// Decode the encoded output and compare its dimensions to what the settings should produce
+fn verify_encoded_output(
+    output_data: &[u8],
+    input_data: &[u8],
+    settings: &CompressionSettings,
+) -> CompressionResult<()> {
+    let decoded = decode_image(output_data, settings.format.extension()).map_err(|e| {
+        CompressionError::EncodeFailed(format!("Output failed verification: {}", e))
+    })?;
+    let (width, height) = (decoded.width(), decoded.height());
+
+    let source = match image::ImageReader::new(std::io::Cursor::new(input_data))
+        .with_guessed_format()
+        .ok()
+        .and_then(|reader| reader.into_dimensions().ok())
+    {
+        Some(source) => source,
+        None => return Ok(()),
+    };
+
+    // Sans redimensionnement les dimensions sont identiques, sinon bornées par la cible
+    let dimensions_ok = match &settings.target_dimensions {
+        None => (width, height) == source,
+        Some(target) => {
+            width > 0
+                && height > 0
+                && width <= target.width.min(source.0)
+                && height <= target.height.min(source.1)
         }
     };
+    if !dimensions_ok {
+        return Err(CompressionError::EncodeFailed(format!(
+            "Output failed verification: decoded {}x{} from a {}x{} source",
+            width, height, source.0, source.1
+        )));
+    }
+    Ok(())
+}
 
-    // Get compressed file size
-    let compressed_size = std::fs::metadata(output_path)
-        .map_err(|e| {
-            CompressionError::IoError(format!("Failed to get output file metadata: {}", e))
-        })?
-        .len();
-
-    Ok(CompressionOutput::new(
-        output_path.to_path_buf(),
-        original_size,
-        compressed_size,
-        settings.format,
-    ))
+fn encode_for_settings(
+    input_data: &[u8],
+    input_format: &str,
+    settings: &CompressionSettings,
+) -> CompressionResult<Vec<u8>> {
+    // Route to appropriate compression function based on target format
+    match settings.format {
+        OutputFormat::WebP => compress_to_webp(input_data, input_format, settings),
+        OutputFormat::Png => compress_to_png(input_data, input_format, settings),
+        OutputFormat::Jpeg => compress_to_jpeg(input_data, input_format, settings),
+    }
 }
 
 /// Compress multiple images in batch (file-to-file)
@@ -98,6 +610,100 @@ pub fn compress_batch_files(
         .collect()
 }
 
+/// Compress a batch on up to `max_concurrency` threads, lowering the concurrency
+/// so the estimated decoded working set stays under `settings.memory_limit_mb`
+pub fn compress_batch_files_parallel(
+    files: Vec<(std::path::PathBuf, std::path::PathBuf)>, // (input_path, output_path) pairs
+    settings: &CompressionSettings,
+    max_concurrency: usize,
+) -> Vec<CompressionResult<CompressionOutput>> {
+    let input_paths: Vec<_> = files.iter().map(|(input_path, _)| input_path).collect();
+    let waves = plan_batch_waves(&input_paths, max_concurrency, settings.memory_limit_mb);
+
+    let mut results: Vec<Option<CompressionResult<CompressionOutput>>> =
+        files.iter().map(|_| None).collect();
+    for wave in waves {
+        let wave_results: Vec<_> = std::thread::scope(|scope| {
+            let handles: Vec<_> = wave
+                .iter()
+                .map(|&index| {
+                    let (input_path, output_path) = &files[index];
+                    let handle = scope
+                        .spawn(move || compress_file_to_file(input_path, output_path, settings));
+                    (index, handle)
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|(index, handle)| {
+                    let result = handle.join().unwrap_or_else(|_| {
+                        Err(CompressionError::ProcessingError(
+                            "Compression thread panicked".to_string(),
+                        ))
+                    });
+                    (index, result)
+                })
+                .collect()
+        });
+
+        for (index, result) in wave_results {
+            results[index] = Some(result);
+        }
+    }
+
+    // Chaque index appartient à exactement une vague
+    results.into_iter().flatten().collect()
+}
+
+/// Waves of input indices whose estimated decoded size fits `memory_limit_mb`,
+/// see `schedule_waves`
+pub fn plan_batch_waves<P: AsRef<Path>>(
+    input_paths: &[P],
+    max_concurrency: usize,
+    memory_limit_mb: Option<u64>,
+) -> Vec<Vec<usize>> {
+    let estimated_bytes: Vec<u64> = input_paths
+        .iter()
+        .map(|input_path| {
+            image::ImageReader::open(input_path)
+                .ok()
+                .and_then(estimated_decoded_bytes)
+                .unwrap_or(0)
+        })
+        .collect();
+    let limit_bytes = memory_limit_mb.map(|limit_mb| limit_mb * 1024 * 1024);
+
+    schedule_waves(&estimated_bytes, max_concurrency, limit_bytes)
+}
+
+/// Group batch indices into waves run concurrently: at most `max_concurrency` files each,
+/// and a summed estimated size under `limit_bytes` (a single oversized file runs alone)
+fn schedule_waves(
+    estimated_bytes: &[u64],
+    max_concurrency: usize,
+    limit_bytes: Option<u64>,
+) -> Vec<Vec<usize>> {
+    let mut waves = Vec::new();
+    let mut current: Vec<usize> = Vec::new();
+    let mut current_bytes = 0u64;
+
+    for (index, &bytes) in estimated_bytes.iter().enumerate() {
+        let over_limit = limit_bytes.is_some_and(|limit| current_bytes + bytes > limit);
+        if !current.is_empty() && (current.len() >= max_concurrency.max(1) || over_limit) {
+            waves.push(std::mem::take(&mut current));
+            current_bytes = 0;
+        }
+        current.push(index);
+        current_bytes += bytes;
+    }
+
+    if !current.is_empty() {
+        waves.push(current);
+    }
+    waves
+}
+
 /// Legacy function - use compress_file_to_file instead
 #[deprecated(note = "Use compress_file_to_file for better memory efficiency")]
 pub fn compress_file<P: AsRef<Path>>(
@@ -124,193 +730,838 @@ pub fn create_compression_stat(
     output: &CompressionOutput,
     settings: &CompressionSettings,
 ) -> CompressionStat {
-    create_stat(
+    let mut stat = create_stat(
         input_format.to_string(),
         output.format.to_string().to_lowercase(),
         output.original_size,
         output.compressed_size,
         settings,
-    )
+    );
+    stat.image_type = output.image_type.clone();
+    stat.lossy_mode = output.lossy_mode;
+    stat
 }
 
-// Private compression functions for each format (file-to-file)
+/// Compress sample files in memory, record their real ratios as calibration stats
+/// and return the updated estimation for each sample
+pub fn calibrate_predictions<S, P>(
+    store: &mut S,
+    sample_paths: &[P],
+    settings: &CompressionSettings,
+) -> CompressionResult<BatchEstimation>
+where
+    S: StatsStore + ?Sized,
+    P: AsRef<Path>,
+{
+    let stats = calibration_stats(sample_paths, settings)?;
+    record_calibration(store, stats, settings)
+}
 
-fn compress_to_webp_file(
-    input_path: &Path,
-    output_path: &Path,
-    input_format: &str,
+/// Compress sample files in memory and build their calibration stats, without
+/// touching the store; samples go through the same path checks as any input
+pub fn calibration_stats<P: AsRef<Path>>(
+    sample_paths: &[P],
     settings: &CompressionSettings,
-) -> CompressionResult<()> {
-    // Read image file data
-    let input_data = std::fs::read(input_path)
-        .map_err(|e| CompressionError::IoError(format!("Failed to read input file: {}", e)))?;
-    use image::ImageFormat;
+) -> CompressionResult<Vec<(String, CompressionStat)>> {
+    validate_settings(settings)?;
+    let output_format = settings.format.extension().to_string();
 
-    // Décode l'image selon le format d'entrée
-    let img = match input_format.to_lowercase().as_str() {
-        "png" => image::load_from_memory_with_format(&input_data, ImageFormat::Png),
-        "jpg" | "jpeg" => image::load_from_memory_with_format(&input_data, ImageFormat::Jpeg),
-        "webp" => image::load_from_memory_with_format(&input_data, ImageFormat::WebP),
-        _ => {
-            return Err(CompressionError::UnsupportedFormat(format!(
-                "Format {} non supporté",
-                input_format
-            )))
-        }
-    }
-    .map_err(|e| CompressionError::ProcessingError(format!("Erreur décodage image: {}", e)))?;
+    // Tout compresser avant d'enregistrer : un échantillon illisible ne laisse
+    // pas une calibration à moitié faite
+    let mut stats = Vec::with_capacity(sample_paths.len());
+    for path in sample_paths {
+        let path = path.as_ref();
+        let metadata = validate_image_file(path).map_err(|e| {
+            CompressionError::IoError(format!("Invalid sample {}: {}", path.display(), e))
+        })?;
+        let input_format = metadata
+            .extension
+            .map(|ext| ext.to_lowercase())
+            .ok_or_else(|| CompressionError::UnsupportedFormat("No file extension".to_string()))?;
+        let input_data = read_file(path).map_err(|e| {
+            CompressionError::IoError(format!("Failed to read {}: {}", path.display(), e))
+        })?;
+        let compressed = compress_or_skip(&input_data, &input_format, settings, global_cache())?;
 
-    // Encode en WebP avec webp crate
-    let rgba_img = img.to_rgba8();
-    let (width, height) = rgba_img.dimensions();
+        let mut stat = create_stat(
+            input_format.clone(),
+            output_format.clone(),
+            input_data.len() as u64,
+            compressed.data.len() as u64,
+            settings,
+        );
+        stat.lossy_mode = compressed.lossy;
+        stat.image_type = classify_input(&input_data, &input_format);
+        stat.source_type = Some(CALIBRATION_SOURCE.to_string());
+        stats.push((path.display().to_string(), stat));
+    }
+    Ok(stats)
+}
 
-    let encoder = webp::Encoder::from_rgba(rgba_img.as_raw(), width, height);
+/// Save calibration stats and return the updated estimation for each sample
+pub fn record_calibration<S: StatsStore + ?Sized>(
+    store: &mut S,
+    stats: Vec<(String, CompressionStat)>,
+    settings: &CompressionSettings,
+) -> CompressionResult<BatchEstimation> {
+    let output_format = settings.format.extension().to_string();
 
-    let encoded = if settings.quality >= 90 {
-        // Mode lossless pour qualité élevée
-        encoder.encode_lossless()
-    } else {
-        // Mode lossy avec qualité spécifiée
-        encoder.encode(settings.quality as f32)
-    };
+    for (_, stat) in &stats {
+        store
+            .save_stat(stat.clone())
+            .map_err(|e| CompressionError::IoError(format!("Failed to save stat: {}", e)))?;
+    }
 
-    // Write directly to output file
-    std::fs::write(output_path, &*encoded)
-        .map_err(|e| CompressionError::IoError(format!("Failed to write output file: {}", e)))?;
+    let mut files = Vec::with_capacity(stats.len());
+    for (file_path, stat) in stats {
+        let query = EstimationQuery {
+            input_format: stat.input_format,
+            output_format: output_format.clone(),
+            original_size: stat.original_size,
+            quality_setting: stat.quality_setting,
+            lossy_mode: stat.lossy_mode,
+        };
+        let estimation = store
+            .get_estimation(&query)
+            .map_err(|e| CompressionError::IoError(format!("Failed to get estimation: {}", e)))?;
+        files.push(FileEstimation::new(
+            file_path,
+            output_format.clone(),
+            stat.original_size,
+            estimation,
+        ));
+    }
 
-    Ok(())
+    Ok(BatchEstimation::from_files(files))
 }
 
-fn compress_to_png_file(
-    input_path: &Path,
-    output_path: &Path,
+// Private compression functions for each format (in-memory)
+
+fn compress_to_webp(
+    input_data: &[u8],
     input_format: &str,
-    _settings: &CompressionSettings,
-) -> CompressionResult<()> {
-    use image::ImageFormat;
+    settings: &CompressionSettings,
+) -> CompressionResult<Vec<u8>> {
+    let img = resize_for_settings(decode_input(input_data, input_format)?, settings);
 
-    match input_format.to_lowercase().as_str() {
-        "png" => {
-            // Pour PNG -> PNG, utilise oxipng directement sur les fichiers
-            let options = oxipng::Options::from_preset(3); // Preset 3 = bon compromis vitesse/compression
-            let input_data = std::fs::read(input_path).map_err(|e| {
-                CompressionError::IoError(format!("Failed to read PNG file: {}", e))
-            })?;
-            match oxipng::optimize_from_memory(&input_data, &options) {
-                Ok(optimized_data) => {
-                    // Écrire les données optimisées vers le fichier de sortie
-                    std::fs::write(output_path, optimized_data).map_err(|e| {
-                        CompressionError::IoError(format!("Failed to write optimized PNG: {}", e))
-                    })?;
-                    return Ok(());
-                }
-                Err(_) => {
-                    // Fallback: copie le fichier original
-                    std::fs::copy(input_path, output_path).map_err(|e| {
-                        CompressionError::IoError(format!("Failed to copy PNG file: {}", e))
-                    })?;
-                    return Ok(());
-                }
-            }
-        }
-        "jpg" | "jpeg" | "webp" => {
-            // Pour autres formats -> PNG, on doit décoder/encoder
-            let input_data = std::fs::read(input_path).map_err(|e| {
-                CompressionError::IoError(format!("Failed to read input file: {}", e))
-            })?;
-
-            let img_format = match input_format {
-                "jpg" | "jpeg" => ImageFormat::Jpeg,
-                "webp" => ImageFormat::WebP,
-                _ => unreachable!(),
-            };
+    // Encode en WebP avec webp crate
+    let alpha_quality = settings
+        .webp_alpha_quality
+        .filter(|_| img.color().has_alpha());
+    let rgba_img = img.to_rgba8();
+    let (width, height) = rgba_img.dimensions();
+    let encoded = encode_webp(
+        &rgba_img,
+        settings.quality,
+        settings.lossless,
+        alpha_quality,
+    );
 
-            let img =
-                image::load_from_memory_with_format(&input_data, img_format).map_err(|e| {
-                    CompressionError::ProcessingError(format!("Erreur décodage image: {}", e))
-                })?;
-
-            // Encode en PNG directement vers le fichier
-            let output_file = std::fs::File::create(output_path).map_err(|e| {
-                CompressionError::IoError(format!("Failed to create output file: {}", e))
-            })?;
-
-            let mut writer = std::io::BufWriter::new(output_file);
-            img.write_to(&mut writer, ImageFormat::Png).map_err(|e| {
-                CompressionError::ProcessingError(format!("Erreur encodage PNG: {}", e))
-            })?;
-
-            // Optimise le fichier PNG généré avec oxipng
-            let options = oxipng::Options::from_preset(3);
-            if let Ok(png_data) = std::fs::read(output_path) {
-                if let Ok(optimized_data) = oxipng::optimize_from_memory(&png_data, &options) {
-                    let _ = std::fs::write(output_path, optimized_data); // Ignore les erreurs d'optimisation
-                }
+    // libwebp n'écrit pas de profil ICC : on ajoute le chunk ICCP nous-mêmes
+    match color_profile_for_output(input_data, input_format, settings) {
+        Some(icc_profile) => embed_webp_icc_profile(&encoded, &icc_profile, width, height),
+        None => Ok(encoded),
+    }
+}
+
+fn encode_webp(
+    rgba_img: &image::RgbaImage,
+    quality: u8,
+    lossless: bool,
+    alpha_quality: Option<u8>,
+) -> Vec<u8> {
+    let encoder = webp::Encoder::from_rgba(rgba_img.as_raw(), rgba_img.width(), rgba_img.height());
+
+    if webp_encodes_lossless(quality, lossless) {
+        // Mode lossless pour qualité élevée ou si demandé explicitement
+        return encoder.encode_lossless().to_vec();
+    }
+
+    // Qualité alpha distincte : passe par la config avancée de libwebp
+    if let Some(alpha_quality) = alpha_quality {
+        if let Ok(mut config) = webp::WebPConfig::new() {
+            config.quality = quality as f32;
+            config.alpha_compression = 1;
+            config.alpha_quality = alpha_quality as i32;
+            if let Ok(encoded) = encoder.encode_advanced(&config) {
+                return encoded.to_vec();
             }
         }
-        _ => {
-            return Err(CompressionError::UnsupportedFormat(format!(
-                "Format {} non supporté pour PNG",
-                input_format
-            )))
-        }
     }
 
-    Ok(())
+    // Mode lossy avec qualité spécifiée
+    encoder.encode(quality as f32).to_vec()
 }
 
-fn compress_to_jpeg_file(
-    input_path: &Path,
-    output_path: &Path,
+fn compress_to_png(
+    input_data: &[u8],
     input_format: &str,
     settings: &CompressionSettings,
-) -> CompressionResult<()> {
-    use image::ImageFormat;
+) -> CompressionResult<Vec<u8>> {
+    let mut options = png_options(settings);
 
-    // Read input file data
-    let input_data = std::fs::read(input_path)
-        .map_err(|e| CompressionError::IoError(format!("Failed to read input file: {}", e)))?;
+    // Pour PNG -> PNG sans redimensionnement, utilise oxipng directement
+    let optimize_original =
+        input_format.eq_ignore_ascii_case("png") && settings.target_dimensions.is_none();
+    if optimize_original && !settings.palette_reduction {
+        // Fallback: conserve les données originales
+        return Ok(oxipng::optimize_from_memory(input_data, &options)
+            .unwrap_or_else(|_| input_data.to_vec()));
+    }
+
+    // Pour les autres cas, on doit décoder/encoder
+    let img = resize_for_settings(decode_input(input_data, input_format)?, settings);
+
+    // Avec de la transparence, les pixels invisibles doivent rester intacts
+    if img.color().has_alpha() {
+        options.optimize_alpha = false;
+    }
 
-    // Décode l'image selon le format d'entrée
-    let img = match input_format.to_lowercase().as_str() {
-        "png" => image::load_from_memory_with_format(&input_data, ImageFormat::Png),
-        "jpg" | "jpeg" => image::load_from_memory_with_format(&input_data, ImageFormat::Jpeg),
-        "webp" => image::load_from_memory_with_format(&input_data, ImageFormat::WebP),
-        _ => {
-            return Err(CompressionError::UnsupportedFormat(format!(
-                "Format {} non supporté pour JPEG",
-                input_format
-            )))
+    if settings.palette_reduction {
+        let icc_profile = color_profile_for_output(input_data, input_format, settings);
+        if let Some(palette_png) = encode_palette_png(&img, icc_profile.as_deref(), &options) {
+            return Ok(palette_png);
+        }
+        if optimize_original {
+            return Ok(oxipng::optimize_from_memory(input_data, &options)
+                .unwrap_or_else(|_| input_data.to_vec()));
         }
     }
-    .map_err(|e| CompressionError::ProcessingError(format!("Erreur décodage image: {}", e)))?;
 
-    // Convertit en RGB (JPEG ne supporte pas la transparence)
-    let rgb_img = img.to_rgb8();
+    let icc_profile = color_profile_for_output(input_data, input_format, settings);
+    encode_png(&img, icc_profile, &options)
+}
 
-    // Create output file and encode directly to it
-    let output_file = std::fs::File::create(output_path)
-        .map_err(|e| CompressionError::IoError(format!("Failed to create output file: {}", e)))?;
+/// Options oxipng dérivées des settings (profil ICC, entrelacement)
+fn png_options(settings: &CompressionSettings) -> oxipng::Options {
+    // Preset 3 = bon compromis vitesse/compression
+    let mut options = oxipng::Options::from_preset(3);
+    if !settings.keeps_color_profile() {
+        options.strip = oxipng::StripChunks::Strip(oxipng::indexset! {*b"iCCP"});
+    }
+    if let Some(interlace) = settings.png_interlace {
+        options.interlace = Some(if interlace {
+            oxipng::Interlacing::Adam7
+        } else {
+            oxipng::Interlacing::None
+        });
+    }
+    options
+}
 
-    let mut writer = std::io::BufWriter::new(output_file);
-    let mut encoder =
-        image::codecs::jpeg::JpegEncoder::new_with_quality(&mut writer, settings.quality);
-    let (width, height) = rgb_img.dimensions();
+fn encode_png(
+    img: &image::DynamicImage,
+    icc_profile: Option<Vec<u8>>,
+    options: &oxipng::Options,
+) -> CompressionResult<Vec<u8>> {
+    use image::ImageEncoder;
 
+    let mut png_data = Vec::new();
+    let mut encoder = image::codecs::png::PngEncoder::new(&mut png_data);
+    if let Some(icc_profile) = icc_profile {
+        // Un profil refusé par l'encodeur ne doit pas bloquer la compression
+        let _ = encoder.set_icc_profile(icc_profile);
+    }
     encoder
-        .encode(
-            rgb_img.as_raw(),
-            width,
-            height,
-            image::ExtendedColorType::Rgb8,
+        .write_image(
+            img.as_bytes(),
+            img.width(),
+            img.height(),
+            img.color().into(),
         )
-        .map_err(|e| CompressionError::ProcessingError(format!("Erreur encodage JPEG: {}", e)))?;
+        .map_err(|e| CompressionError::EncodeFailed(format!("PNG: {}", e)))?;
 
-    Ok(())
+    // Optimise le PNG généré avec oxipng (ignore les erreurs d'optimisation)
+    Ok(oxipng::optimize_from_memory(&png_data, options).unwrap_or(png_data))
+}
+
+/// Encode un PNG indexé quand l'image tient dans une palette de 256 couleurs (sans perte)
+fn encode_palette_png(
+    img: &image::DynamicImage,
+    icc_profile: Option<&[u8]>,
+    options: &oxipng::Options,
+) -> Option<Vec<u8>> {
+    let rgba_img = img.to_rgba8();
+    // Chaque couleur RGBA distincte occupe une entrée : l'alpha compte dans la limite
+    if count_unique_colors(&rgba_img) > 256 {
+        return None;
+    }
+
+    let mut palette: Vec<oxipng::RGBA8> = Vec::new();
+    let mut index_of = std::collections::HashMap::new();
+    let indices = rgba_img
+        .pixels()
+        .map(|pixel| {
+            *index_of.entry(pixel.0).or_insert_with(|| {
+                let [r, g, b, a] = pixel.0;
+                palette.push(oxipng::RGBA8::new(r, g, b, a));
+                (palette.len() - 1) as u8
+            })
+        })
+        .collect();
+
+    let mut raw = oxipng::RawImage::new(
+        rgba_img.width(),
+        rgba_img.height(),
+        oxipng::ColorType::Indexed { palette },
+        oxipng::BitDepth::Eight,
+        indices,
+    )
+    .ok()?;
+    if let Some(icc_profile) = icc_profile {
+        raw.add_icc_profile(icc_profile);
+    }
+    raw.create_optimized_png(options).ok()
+}
+
+/// Nombre de couleurs RGBA distinctes (s'arrête au-delà de 256)
+fn count_unique_colors(rgba_img: &image::RgbaImage) -> usize {
+    let mut colors = std::collections::HashSet::new();
+    for pixel in rgba_img.pixels() {
+        colors.insert(pixel.0);
+        if colors.len() > 256 {
+            break;
+        }
+    }
+    colors.len()
+}
+
+fn compress_to_jpeg(
+    input_data: &[u8],
+    input_format: &str,
+    settings: &CompressionSettings,
+) -> CompressionResult<Vec<u8>> {
+    // JPEG -> JPEG sans perte : les coefficients DCT sont recopiés, seul le codage entropique change
+    if uses_lossless_jpeg_transcode(input_format, settings) {
+        match optimize_jpeg_lossless(input_data, settings) {
+            Ok(optimized) => return Ok(optimized),
+            Err(e) => log::warn!("Lossless JPEG optimization failed, re-encoding: {}", e),
+        }
+    }
+
+    let img = resize_for_settings(decode_input(input_data, input_format)?, settings);
+
+    // JPEG ne supporte pas la transparence : composition sur la couleur de fond
+    let rgb_img = flatten_alpha(&img, settings.flatten_background);
+    let icc_profile = color_profile_for_output(input_data, input_format, settings);
+
+    encode_jpeg(
+        &rgb_img,
+        settings.quality,
+        icc_profile,
+        settings.jpeg_encoder,
+    )
+}
+
+fn encode_jpeg(
+    rgb_img: &image::RgbImage,
+    quality: u8,
+    icc_profile: Option<Vec<u8>>,
+    encoder: JpegEncoder,
+) -> CompressionResult<Vec<u8>> {
+    match encoder {
+        #[cfg(feature = "mozjpeg")]
+        JpegEncoder::MozJpeg => encode_jpeg_mozjpeg(rgb_img, quality, icc_profile),
+        // Sans la feature mozjpeg, l'encodeur intégré sert de repli
+        _ => encode_jpeg_builtin(rgb_img, quality, icc_profile),
+    }
+}
+
+/// libjpeg state for a coefficient-level transcode, freed on drop even after an error
+struct JpegTranscoder {
+    source: mozjpeg_sys::jpeg_decompress_struct,
+    destination: mozjpeg_sys::jpeg_compress_struct,
+    source_errors: mozjpeg_sys::jpeg_error_mgr,
+    destination_errors: mozjpeg_sys::jpeg_error_mgr,
+    output: *mut u8,
+    output_len: std::os::raw::c_ulong,
+}
+
+extern "C" {
+    // Le tampon de jpeg_mem_dest est alloué par malloc
+    fn free(ptr: *mut std::os::raw::c_void);
+}
+
+impl Drop for JpegTranscoder {
+    fn drop(&mut self) {
+        // SAFETY: destroying a zeroed (never created) struct is a no-op in libjpeg,
+        // and `output` is either null or the malloc'd buffer owned by this transcoder
+        unsafe {
+            mozjpeg_sys::jpeg_destroy_compress(&mut self.destination);
+            mozjpeg_sys::jpeg_destroy_decompress(&mut self.source);
+            if !self.output.is_null() {
+                free(self.output.cast());
+            }
+        }
+    }
+}
+
+/// libjpeg error handler: unwinds instead of exiting the process
+extern "C-unwind" fn jpeg_error_exit(cinfo: &mut mozjpeg_sys::jpeg_common_struct) {
+    // SAFETY: libjpeg only calls this handler with `err` pointing to a live error manager
+    let code = unsafe { cinfo.err.as_ref() }.map_or(0, |err| err.msg_code);
+    std::panic::resume_unwind(Box::new(format!("libjpeg error code {}", code)));
+}
+
+/// libjpeg warnings (corrupt data...) are not worth printing
+extern "C-unwind" fn jpeg_silence_message(
+    _cinfo: &mut mozjpeg_sys::jpeg_common_struct,
+    _level: std::os::raw::c_int,
+) {
+}
+
+/// Lossless JPEG optimization, jpegtran style: the DCT coefficients are copied untouched
+/// and re-encoded with optimized Huffman tables and progressive scans
+fn optimize_jpeg_lossless(
+    input_data: &[u8],
+    settings: &CompressionSettings,
+) -> CompressionResult<Vec<u8>> {
+    // APP2 porte le profil ICC ; les autres APPn/COM sont des métadonnées
+    let mut markers = Vec::new();
+    if settings.keeps_color_profile() {
+        markers.push(0xE2);
+    }
+    if settings.preserve_metadata {
+        markers.extend((0xE1..=0xEF).filter(|&marker| marker != 0xE2 && marker != 0xEE));
+        markers.push(0xFE);
+    }
+
+    let optimized =
+        std::panic::catch_unwind(|| transcode_jpeg(input_data, &markers)).map_err(|payload| {
+            let message = payload
+                .downcast_ref::<String>()
+                .cloned()
+                .unwrap_or_else(|| "libjpeg panicked".to_string());
+            CompressionError::EncodeFailed(format!("JPEG: {}", message))
+        })?;
+
+    // Sans perte de toute façon : on garde l'original s'il était déjà plus compact
+    if optimized.len() > input_data.len() {
+        Ok(input_data.to_vec())
+    } else {
+        Ok(optimized)
+    }
+}
+
+/// Copy the coefficients of `input_data` into a new progressive, Huffman-optimized JPEG,
+/// keeping the saved `markers` (APPn/COM codes)
+fn transcode_jpeg(input_data: &[u8], markers: &[std::os::raw::c_int]) -> Vec<u8> {
+    use mozjpeg_sys as ffi;
+
+    // SAFETY: the transcoder is boxed so the error manager pointers stay valid, every libjpeg
+    // error unwinds out of this function and Drop releases whatever was allocated so far
+    unsafe {
+        let mut transcoder: Box<JpegTranscoder> = Box::new(std::mem::zeroed());
+        let t = &mut *transcoder;
+
+        t.source.common.err = ffi::jpeg_std_error(&mut t.source_errors);
+        t.source_errors.error_exit = Some(jpeg_error_exit);
+        t.source_errors.emit_message = Some(jpeg_silence_message);
+        ffi::jpeg_create_decompress(&mut t.source);
+        ffi::jpeg_mem_src(
+            &mut t.source,
+            input_data.as_ptr(),
+            input_data.len() as std::os::raw::c_ulong,
+        );
+        for &marker in markers {
+            ffi::jpeg_save_markers(&mut t.source, marker, 0xFFFF);
+        }
+        ffi::jpeg_read_header(&mut t.source, 1);
+        let coefficients = ffi::jpeg_read_coefficients(&mut t.source);
+
+        t.destination.common.err = ffi::jpeg_std_error(&mut t.destination_errors);
+        t.destination_errors.error_exit = Some(jpeg_error_exit);
+        t.destination_errors.emit_message = Some(jpeg_silence_message);
+        ffi::jpeg_create_compress(&mut t.destination);
+        ffi::jpeg_copy_critical_parameters(&t.source, &mut t.destination);
+        t.destination.optimize_coding = 1;
+        ffi::jpeg_simple_progression(&mut t.destination);
+        ffi::jpeg_mem_dest(&mut t.destination, &mut t.output, &mut t.output_len);
+        ffi::jpeg_write_coefficients(&mut t.destination, coefficients);
+
+        let mut marker = t.source.marker_list;
+        while let Some(saved) = marker.as_ref() {
+            ffi::jpeg_write_marker(
+                &mut t.destination,
+                saved.marker as std::os::raw::c_int,
+                saved.data,
+                saved.data_length,
+            );
+            marker = saved.next;
+        }
+
+        ffi::jpeg_finish_compress(&mut t.destination);
+        ffi::jpeg_finish_decompress(&mut t.source);
+
+        std::slice::from_raw_parts(t.output, t.output_len as usize).to_vec()
+    }
+}
+
+/// Profile bytes per APP2 segment: 65533 minus the 14-byte ICC_PROFILE header
+#[cfg(feature = "mozjpeg")]
+const JPEG_ICC_CHUNK_LEN: usize = 65519;
+
+#[cfg(feature = "mozjpeg")]
+fn encode_jpeg_mozjpeg(
+    rgb_img: &image::RgbImage,
+    quality: u8,
+    icc_profile: Option<Vec<u8>>,
+) -> CompressionResult<Vec<u8>> {
+    // mozjpeg signale ses erreurs par panique : on les convertit en EncodeFailed
+    std::panic::catch_unwind(|| -> std::io::Result<Vec<u8>> {
+        let mut compress = mozjpeg::Compress::new(mozjpeg::ColorSpace::JCS_RGB);
+        compress.set_size(rgb_img.width() as usize, rgb_img.height() as usize);
+        compress.set_quality(quality as f32);
+
+        let mut started = compress.start_compress(Vec::new())?;
+        if let Some(icc_profile) = &icc_profile {
+            // Segments APP2 numérotés à partir de 1, comme l'exige la spécification ICC
+            let chunks: Vec<&[u8]> = icc_profile.chunks(JPEG_ICC_CHUNK_LEN).collect();
+            for (index, chunk) in chunks.iter().enumerate() {
+                let mut marker = b"ICC_PROFILE\0".to_vec();
+                marker.extend([index as u8 + 1, chunks.len() as u8]);
+                marker.extend_from_slice(chunk);
+                started.write_marker(mozjpeg::Marker::APP(2), &marker);
+            }
+        }
+        started.write_scanlines(rgb_img.as_raw())?;
+        started.finish()
+    })
+    .map_err(|_| CompressionError::EncodeFailed("JPEG: mozjpeg panicked".to_string()))?
+    .map_err(|e| CompressionError::EncodeFailed(format!("JPEG: {}", e)))
+}
+
+fn encode_jpeg_builtin(
+    rgb_img: &image::RgbImage,
+    quality: u8,
+    icc_profile: Option<Vec<u8>>,
+) -> CompressionResult<Vec<u8>> {
+    let mut jpeg_data = Vec::new();
+    let mut encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg_data, quality);
+    if let Some(icc_profile) = icc_profile {
+        let _ = image::ImageEncoder::set_icc_profile(&mut encoder, icc_profile);
+    }
+
+    encoder
+        .encode(
+            rgb_img.as_raw(),
+            rgb_img.width(),
+            rgb_img.height(),
+            image::ExtendedColorType::Rgb8,
+        )
+        .map_err(|e| CompressionError::EncodeFailed(format!("JPEG: {}", e)))?;
+
+    Ok(jpeg_data)
+}
+
+/// Encode one source into several (format, quality) targets, decoding it only once.
+/// Results are returned in the order of `formats`.
+pub fn compress_to_formats(
+    input_data: &[u8],
+    input_format: &str,
+    formats: Vec<(OutputFormat, u8)>,
+) -> Vec<CompressionResult<Vec<u8>>> {
+    let img = match decode_image(input_data, input_format) {
+        Ok(img) => img,
+        Err(e) => return formats.iter().map(|_| Err(e.clone())).collect(),
+    };
+
+    // Conversions partagées entre les cibles du même type
+    let rgba_img = std::cell::OnceCell::new();
+    let rgb_img = std::cell::OnceCell::new();
+
+    formats
+        .into_iter()
+        .map(|(format, quality)| {
+            if !(1..=100).contains(&quality) {
+                return Err(CompressionError::InvalidSettings(format!(
+                    "Quality must be between 1 and 100, got {}",
+                    quality
+                )));
+            }
+
+            match format {
+                OutputFormat::WebP => Ok(encode_webp(
+                    rgba_img.get_or_init(|| img.to_rgba8()),
+                    quality,
+                    false,
+                    None,
+                )),
+                OutputFormat::Jpeg => encode_jpeg(
+                    rgb_img.get_or_init(|| flatten_alpha(&img, [255, 255, 255])),
+                    quality,
+                    None,
+                    JpegEncoder::default(),
+                ),
+                OutputFormat::Png => {
+                    let mut options = png_options(&CompressionSettings::new(quality, format));
+                    if img.color().has_alpha() {
+                        options.optimize_alpha = false;
+                    }
+                    encode_png(&img, None, &options)
+                }
+            }
+        })
+        .collect()
+}
+
+/// Maximum number of qualities sampled by `quality_size_curve`
+pub const MAX_CURVE_POINTS: usize = 12;
+
+/// Encoded size at each requested quality, sorted by quality, decoding the source once.
+/// PNG ignores quality, so every point gets the same optimized size.
+pub fn quality_size_curve(
+    input_data: &[u8],
+    input_format: &str,
+    format: OutputFormat,
+    qualities: &[u8],
+) -> CompressionResult<Vec<(u8, u64)>> {
+    let mut qualities = qualities.to_vec();
+    qualities.sort_unstable();
+    qualities.dedup();
+
+    if qualities.is_empty() || qualities.len() > MAX_CURVE_POINTS {
+        return Err(CompressionError::InvalidSettings(format!(
+            "Between 1 and {} qualities required, got {}",
+            MAX_CURVE_POINTS,
+            qualities.len()
+        )));
+    }
+    if let Some(quality) = qualities.iter().find(|q| !(1..=100).contains(*q)) {
+        return Err(CompressionError::InvalidSettings(format!(
+            "Quality must be between 1 and 100, got {}",
+            quality
+        )));
+    }
+
+    let sizes: Vec<u64> = match format {
+        OutputFormat::Png => {
+            let settings = CompressionSettings::new(qualities[0], OutputFormat::Png);
+            let size = compress_to_png(input_data, input_format, &settings)?.len() as u64;
+            vec![size; qualities.len()]
+        }
+        OutputFormat::WebP => {
+            let rgba_img = decode_image(input_data, input_format)?.to_rgba8();
+            qualities
+                .iter()
+                .map(|&quality| encode_webp(&rgba_img, quality, false, None).len() as u64)
+                .collect()
+        }
+        OutputFormat::Jpeg => {
+            let img = decode_image(input_data, input_format)?;
+            let rgb_img = flatten_alpha(&img, [255, 255, 255]);
+            qualities
+                .iter()
+                .map(|&quality| {
+                    encode_jpeg(&rgb_img, quality, None, JpegEncoder::default())
+                        .map(|data| data.len() as u64)
+                })
+                .collect::<CompressionResult<_>>()?
+        }
+    };
+
+    Ok(qualities.into_iter().zip(sizes).collect())
+}
+
+/// Format `image` correspondant au format d'entrée
+fn image_format_for(input_format: &str) -> CompressionResult<image::ImageFormat> {
+    use image::ImageFormat;
+
+    match input_format.to_lowercase().as_str() {
+        "png" => Ok(ImageFormat::Png),
+        "jpg" | "jpeg" => Ok(ImageFormat::Jpeg),
+        "webp" => Ok(ImageFormat::WebP),
+        _ => Err(CompressionError::UnsupportedFormat(format!(
+            "Format {} non supporté",
+            input_format
+        ))),
+    }
+}
+
+/// Décode l'image selon le format d'entrée
+fn decode_image(input_data: &[u8], input_format: &str) -> CompressionResult<image::DynamicImage> {
+    image::load_from_memory_with_format(input_data, image_format_for(input_format)?)
+        .map_err(|e| CompressionError::DecodeFailed(e.to_string()))
+}
+
+/// Décode l'image source en signalant la progression si un callback est installé
+fn decode_input(input_data: &[u8], input_format: &str) -> CompressionResult<image::DynamicImage> {
+    let image_format = image_format_for(input_format)?;
+
+    DECODE_PROGRESS.with(|progress| match progress.borrow().as_deref() {
+        Some(on_progress) => image::ImageReader::with_format(
+            ProgressReader::new(input_data, on_progress),
+            image_format,
+        )
+        .decode()
+        .map_err(|e| CompressionError::DecodeFailed(e.to_string())),
+        None => decode_image(input_data, input_format),
+    })
+}
+
+/// Profil ICC à embarquer dans la sortie, uniquement si les settings le conservent
+fn color_profile_for_output(
+    input_data: &[u8],
+    input_format: &str,
+    settings: &CompressionSettings,
+) -> Option<Vec<u8>> {
+    if settings.keeps_color_profile() {
+        extract_icc_profile(input_data, input_format)
+    } else {
+        None
+    }
+}
+
+/// Redimensionne l'image selon les settings, sans jamais l'agrandir
+fn resize_for_settings(
+    img: image::DynamicImage,
+    settings: &CompressionSettings,
+) -> image::DynamicImage {
+    use image::imageops::FilterType;
+
+    let target = match &settings.target_dimensions {
+        Some(target) => target,
+        None => return img,
+    };
+
+    // Never upscale beyond the source dimensions
+    let width = target.width.min(img.width());
+    let height = target.height.min(img.height());
+
+    if width == img.width() && height == img.height() {
+        return img;
+    }
+
+    if settings.maintain_aspect_ratio {
+        img.resize(width, height, FilterType::Lanczos3)
+    } else {
+        img.resize_exact(width, height, FilterType::Lanczos3)
+    }
 }
 
 // Helper functions
 
+/// Estimate the decoded size from the image header and compare it to the memory limit
+fn check_memory_limit<R: std::io::BufRead + std::io::Seek>(
+    reader: image::ImageReader<R>,
+    settings: &CompressionSettings,
+) -> CompressionResult<()> {
+    let limit_mb = match settings.memory_limit_mb {
+        Some(limit_mb) => limit_mb,
+        None => return Ok(()),
+    };
+
+    // En-tête illisible : le décodage signalera l'erreur lui-même
+    let required_bytes = match estimated_decoded_bytes(reader) {
+        Some(required_bytes) => required_bytes,
+        None => return Ok(()),
+    };
+
+    if required_bytes > limit_mb * 1024 * 1024 {
+        return Err(CompressionError::MemoryLimitExceeded(
+            required_bytes.div_ceil(1024 * 1024),
+            limit_mb,
+        ));
+    }
+    Ok(())
+}
+
+/// Decoded size in bytes (width × height × bytes per pixel) read from the image header
+fn estimated_decoded_bytes<R: std::io::BufRead + std::io::Seek>(
+    reader: image::ImageReader<R>,
+) -> Option<u64> {
+    use image::ImageDecoder;
+
+    let decoder = reader.with_guessed_format().ok()?.into_decoder().ok()?;
+    let (width, height) = decoder.dimensions();
+    let bytes_per_pixel = decoder.color_type().bytes_per_pixel() as u64;
+    Some(width as u64 * height as u64 * bytes_per_pixel)
+}
+
+/// Enforce `max_dimensions`: reject the image or fold the limit into the resize target
+fn apply_max_dimensions(
+    input_data: &[u8],
+    settings: &CompressionSettings,
+) -> CompressionResult<CompressionSettings> {
+    let max = match &settings.max_dimensions {
+        Some(max) => max,
+        None => return Ok(settings.clone()),
+    };
+
+    let (width, height) = match image::ImageReader::new(std::io::Cursor::new(input_data))
+        .with_guessed_format()
+        .ok()
+        .and_then(|reader| reader.into_dimensions().ok())
+    {
+        Some(dimensions) => dimensions,
+        None => return Ok(settings.clone()),
+    };
+
+    if width <= max.width && height <= max.height {
+        return Ok(settings.clone());
+    }
+
+    match settings.on_oversize {
+        OversizePolicy::Reject => Err(CompressionError::ProcessingError(format!(
+            "Image {}x{} exceeds maximum dimensions {}x{}",
+            width, height, max.width, max.height
+        ))),
+        OversizePolicy::Downscale => {
+            // Combine avec un éventuel redimensionnement déjà demandé
+            let target = match &settings.target_dimensions {
+                Some(target) => Dimensions {
+                    width: target.width.min(max.width),
+                    height: target.height.min(max.height),
+                },
+                None => max.clone(),
+            };
+            let mut settings = settings.clone();
+            settings.target_dimensions = Some(target);
+            settings.maintain_aspect_ratio = true;
+            Ok(settings)
+        }
+    }
+}
+
+/// Sources larger than this multiple of `max_dimensions` are rejected even when downscaling
+const MAX_DOWNSCALE_FACTOR: u32 = 8;
+
+/// Reject absurd header dimensions before any pixel buffer is allocated
+fn check_declared_dimensions(
+    input_data: &[u8],
+    settings: &CompressionSettings,
+) -> CompressionResult<()> {
+    let max = match &settings.max_dimensions {
+        Some(max) => max,
+        None => return Ok(()),
+    };
+
+    // En-tête illisible : le décodage signalera l'erreur lui-même
+    let (width, height) = match image::ImageReader::new(std::io::Cursor::new(input_data))
+        .with_guessed_format()
+        .ok()
+        .and_then(|reader| reader.into_dimensions().ok())
+    {
+        Some(dimensions) => dimensions,
+        None => return Ok(()),
+    };
+
+    let factor = match settings.on_oversize {
+        OversizePolicy::Reject => 1,
+        OversizePolicy::Downscale => MAX_DOWNSCALE_FACTOR,
+    };
+    validate_dimensions(
+        width,
+        height,
+        max.width.saturating_mul(factor),
+        max.height.saturating_mul(factor),
+    )
+    .map_err(|e| match e {
+        DomainError::InvalidInput(msg) => CompressionError::InvalidDimensions(msg),
+        other => CompressionError::InvalidDimensions(other.to_string()),
+    })
+}
+
 fn validate_settings(settings: &CompressionSettings) -> CompressionResult<()> {
     if !settings.is_valid() {
         return Err(CompressionError::InvalidSettings(format!(
@@ -347,4 +1598,900 @@ mod tests {
         invalid_settings.quality = 200; // Invalid quality
         assert!(validate_settings(&invalid_settings).is_err());
     }
+
+    fn write_test_png(path: &Path, width: u32, height: u32) {
+        let img = image::RgbImage::from_fn(width, height, |x, y| {
+            image::Rgb([(x % 256) as u8, (y % 256) as u8, 128])
+        });
+        img.save_with_format(path, image::ImageFormat::Png).unwrap();
+    }
+
+    #[test]
+    fn test_calibrate_predictions_uses_observed_ratios() {
+        use crate::domain::compression::SqliteStatsStore;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let samples: Vec<_> = [(64, 48), (80, 60), (96, 72)]
+            .iter()
+            .enumerate()
+            .map(|(i, &(width, height))| {
+                let path = temp_dir.path().join(format!("sample_{}.png", i));
+                write_test_png(&path, width, height);
+                path
+            })
+            .collect();
+
+        let settings = CompressionSettings::new(80, OutputFormat::WebP);
+        let query = EstimationQuery {
+            input_format: "png".to_string(),
+            output_format: "webp".to_string(),
+            original_size: std::fs::metadata(&samples[0]).unwrap().len(),
+            quality_setting: 80,
+            lossy_mode: true,
+        };
+        let mut store = SqliteStatsStore::in_memory().unwrap();
+        let heuristic = store.get_estimation(&query).unwrap();
+
+        let calibration = calibrate_predictions(&mut store, &samples, &settings).unwrap();
+        assert_eq!(calibration.files.len(), samples.len());
+        // Rien n'est écrit à côté des échantillons
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 3);
+
+        let estimation = store.get_estimation(&query).unwrap();
+        assert_eq!(estimation.sample_count, 3);
+
+        let observed: Vec<f64> = samples
+            .iter()
+            .map(|path| {
+                let data = std::fs::read(path).unwrap();
+                let compressed = compress_bytes(&data, "png", &settings).unwrap();
+                (data.len() as f64 - compressed.len() as f64) / data.len() as f64 * 100.0
+            })
+            .collect();
+        let min = observed.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = observed.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        assert!(estimation.percent >= min - 0.01 && estimation.percent <= max + 0.01);
+        assert!((estimation.percent - heuristic.percent).abs() > f64::EPSILON);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_calibration_rejects_blocked_samples() {
+        use crate::domain::compression::SqliteStatsStore;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let sample = temp_dir.path().join("sample.png");
+        write_test_png(&sample, 32, 32);
+        // Un lien vers /etc passe l'extension mais sort des racines autorisées
+        let escape = temp_dir.path().join("escape.png");
+        std::os::unix::fs::symlink("/etc/passwd", &escape).unwrap();
+
+        let settings = CompressionSettings::new(80, OutputFormat::WebP);
+        let mut store = SqliteStatsStore::in_memory().unwrap();
+        assert!(calibrate_predictions(&mut store, &[&sample, &escape], &settings).is_err());
+        assert_eq!(store.count_stats().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_compress_with_resize() {
+        use crate::domain::image::Dimensions;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let input_path = temp_dir.path().join("large.png");
+        let output_path = temp_dir.path().join("large.webp");
+        write_test_png(&input_path, 2000, 2000);
+
+        let settings = CompressionSettings::new(80, OutputFormat::WebP)
+            .with_resize(Dimensions::new(500, 500).unwrap(), true);
+        let output = compress_file_to_file(&input_path, &output_path, &settings).unwrap();
+
+        let compressed = image::open(&output.output_path).unwrap();
+        assert_eq!((compressed.width(), compressed.height()), (500, 500));
+    }
+
+    #[test]
+    fn test_compress_bytes_uses_cache() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let input_path = temp_dir.path().join("cached.png");
+        write_test_png(&input_path, 64, 64);
+        let input_data = std::fs::read(&input_path).unwrap();
+
+        let cache = std::sync::Mutex::new(CompressionCache::new(1024 * 1024));
+        let settings = CompressionSettings::new(80, OutputFormat::WebP);
+
+        let first = compress_bytes_with_cache(&input_data, "png", &settings, &cache).unwrap();
+        assert_eq!(cache.lock().unwrap().hits(), 0);
+
+        let second = compress_bytes_with_cache(&input_data, "png", &settings, &cache).unwrap();
+        assert_eq!(cache.lock().unwrap().hits(), 1);
+        assert_eq!(first, second);
+
+        // A different quality is a different cache entry
+        let other_settings = CompressionSettings::new(60, OutputFormat::WebP);
+        compress_bytes_with_cache(&input_data, "png", &other_settings, &cache).unwrap();
+        let cache = cache.lock().unwrap();
+        assert_eq!(cache.hits(), 1);
+        assert_eq!(cache.misses(), 2);
+        assert_eq!(cache.len(), 2);
+    }
+
+    /// JPEG tagged with a (truncated) Display P3 profile
+    fn display_p3_jpeg() -> (Vec<u8>, Vec<u8>) {
+        use image::ImageEncoder;
+
+        let mut icc_profile = vec![0u8; 128];
+        icc_profile[12..16].copy_from_slice(b"mntr");
+        icc_profile[16..20].copy_from_slice(b"RGB ");
+        icc_profile[36..40].copy_from_slice(b"acsp");
+        icc_profile.extend_from_slice(b"desc Display P3");
+
+        let img =
+            image::RgbImage::from_fn(32, 32, |x, y| image::Rgb([x as u8 * 8, y as u8 * 8, 64]));
+        let mut jpeg_data = Vec::new();
+        let mut encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg_data, 90);
+        encoder.set_icc_profile(icc_profile.clone()).unwrap();
+        encoder
+            .write_image(img.as_raw(), 32, 32, image::ExtendedColorType::Rgb8)
+            .unwrap();
+
+        (jpeg_data, icc_profile)
+    }
+
+    #[test]
+    fn test_icc_profile_round_trip() {
+        let (jpeg_data, icc_profile) = display_p3_jpeg();
+        assert_eq!(
+            extract_icc_profile(&jpeg_data, "jpg"),
+            Some(icc_profile.clone())
+        );
+
+        for (format, extension) in [(OutputFormat::Jpeg, "jpg"), (OutputFormat::WebP, "webp")] {
+            let settings = CompressionSettings::new(80, format).with_metadata_preservation(true);
+            let output = encode_for_settings(&jpeg_data, "jpg", &settings).unwrap();
+            assert_eq!(
+                extract_icc_profile(&output, extension),
+                Some(icc_profile.clone())
+            );
+        }
+    }
+
+    #[test]
+    fn test_icc_profile_stripped() {
+        let (jpeg_data, _) = display_p3_jpeg();
+
+        let stripping = [
+            CompressionSettings::new(80, OutputFormat::WebP),
+            CompressionSettings::new(80, OutputFormat::Jpeg)
+                .with_metadata_preservation(true)
+                .with_color_profile_preservation(false),
+        ];
+        for settings in stripping {
+            let output = encode_for_settings(&jpeg_data, "jpg", &settings).unwrap();
+            let extension = settings.format.to_string().to_lowercase();
+            assert_eq!(extract_icc_profile(&output, &extension), None);
+        }
+    }
+
+    #[test]
+    fn test_memory_limit_rejects_large_image() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let input_path = temp_dir.path().join("huge.png");
+        let output_path = temp_dir.path().join("huge.webp");
+        write_test_png(&input_path, 1024, 1024); // ~3 MB once decoded
+
+        let settings = CompressionSettings::new(80, OutputFormat::WebP).with_memory_limit(1);
+        let result = compress_file_to_file(&input_path, &output_path, &settings);
+
+        assert_eq!(
+            result.unwrap_err(),
+            CompressionError::MemoryLimitExceeded(3, 1)
+        );
+        assert!(!output_path.exists());
+
+        let settings = CompressionSettings::new(80, OutputFormat::WebP).with_memory_limit(16);
+        assert!(compress_file_to_file(&input_path, &output_path, &settings).is_ok());
+    }
+
+    #[test]
+    fn test_stat_records_image_type() {
+        use crate::domain::compression::store::{SqliteStatsStore, StatsStore};
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let input_path = temp_dir.path().join("logo.png");
+        let output_path = temp_dir.path().join("logo.webp");
+        write_test_png(&input_path, 32, 32);
+
+        let settings = CompressionSettings::new(80, OutputFormat::WebP);
+        let output = compress_file_to_file(&input_path, &output_path, &settings).unwrap();
+        assert_eq!(output.image_type.as_deref(), Some("logo"));
+
+        let stat = create_compression_stat("png", &output, &settings);
+        assert_eq!(stat.image_type.as_deref(), Some("logo"));
+
+        let mut store = SqliteStatsStore::in_memory().unwrap();
+        assert!(store.save_stat(stat).is_ok());
+    }
+
+    #[test]
+    fn test_forced_lossless_webp() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let input_path = temp_dir.path().join("archive.png");
+        write_test_png(&input_path, 48, 48);
+        let input_data = std::fs::read(&input_path).unwrap();
+
+        let settings = CompressionSettings::new(50, OutputFormat::WebP).with_lossless(true);
+        let output = compress_bytes(&input_data, "png", &settings).unwrap();
+
+        // VP8L is the lossless WebP bitstream
+        assert!(output.windows(4).any(|w| w == b"VP8L"));
+        let original = image::load_from_memory(&input_data).unwrap().to_rgb8();
+        let decoded = image::load_from_memory(&output).unwrap().to_rgb8();
+        assert_eq!(original, decoded);
+    }
+
+    #[test]
+    fn test_decode_reports_intermediate_progress() {
+        // Bruit pseudo-aléatoire : le PNG reste gros et se décode par morceaux
+        let img = image::RgbImage::from_fn(1024, 768, |x, y| {
+            let noise = (x.wrapping_mul(2_654_435_761) ^ y.wrapping_mul(40_503)) >> 7;
+            image::Rgb([noise as u8, (noise >> 8) as u8, (noise >> 16) as u8])
+        });
+        let mut input_data = Vec::new();
+        image::DynamicImage::ImageRgb8(img)
+            .write_to(
+                &mut std::io::Cursor::new(&mut input_data),
+                image::ImageFormat::Png,
+            )
+            .unwrap();
+
+        let reported = std::rc::Rc::new(RefCell::new(Vec::new()));
+        let sink = reported.clone();
+        let settings = CompressionSettings::new(70, OutputFormat::Jpeg);
+        let cache = std::sync::Mutex::new(CompressionCache::new(0));
+        with_decode_progress(
+            move |fraction| sink.borrow_mut().push(fraction),
+            || compress_bytes_with_cache(&input_data, "png", &settings, &cache),
+        )
+        .unwrap();
+
+        let reported = reported.borrow();
+        assert!(reported
+            .iter()
+            .any(|&fraction| fraction > 0.0 && fraction < 1.0));
+        assert!(reported.windows(2).all(|pair| pair[0] <= pair[1]));
+
+        // Hors de with_decode_progress, plus aucun callback n'est installé
+        DECODE_PROGRESS.with(|progress| assert!(progress.borrow().is_none()));
+    }
+
+    #[test]
+    fn test_lossless_jpeg_optimization() {
+        let img = image::RgbImage::from_fn(120, 90, |x, y| {
+            image::Rgb([(x * 2) as u8, (y * 2) as u8, ((x * y) % 256) as u8])
+        });
+        let mut input_data = Vec::new();
+        image::codecs::jpeg::JpegEncoder::new_with_quality(&mut input_data, 85)
+            .encode_image(&img)
+            .unwrap();
+
+        let settings = CompressionSettings::new(40, OutputFormat::Jpeg).with_lossless(true);
+        let output = compress_bytes(&input_data, "jpg", &settings).unwrap();
+
+        assert!(output.len() <= input_data.len());
+        let original = image::load_from_memory(&input_data).unwrap().to_rgb8();
+        let optimized = image::load_from_memory(&output).unwrap().to_rgb8();
+        assert_eq!(original, optimized);
+
+        // Données corrompues : erreur propre, pas d'abandon du processus
+        assert!(optimize_jpeg_lossless(&input_data[..200], &settings).is_err());
+        assert!(optimize_jpeg_lossless(b"not a jpeg", &settings).is_err());
+    }
+
+    #[test]
+    fn test_webp_alpha_quality() {
+        // Découpe avec ombre douce : l'alpha varie en dégradé
+        let img = image::RgbaImage::from_fn(128, 128, |x, y| {
+            let distance = (x as f32 - 64.0).hypot(y as f32 - 64.0);
+            let alpha = (255.0 - distance * 4.0).clamp(0.0, 255.0) as u8;
+            image::Rgba([(x * 2) as u8, (y * 2) as u8, 90, alpha])
+        });
+        let mut input_data = Vec::new();
+        image::DynamicImage::ImageRgba8(img)
+            .write_to(
+                &mut std::io::Cursor::new(&mut input_data),
+                image::ImageFormat::Png,
+            )
+            .unwrap();
+
+        let settings = CompressionSettings::new(75, OutputFormat::WebP);
+        let sharp = compress_bytes(
+            &input_data,
+            "png",
+            &settings.clone().with_webp_alpha_quality(100),
+        )
+        .unwrap();
+        let soft =
+            compress_bytes(&input_data, "png", &settings.with_webp_alpha_quality(5)).unwrap();
+
+        assert_ne!(sharp.len(), soft.len());
+        assert!(soft.len() < sharp.len());
+    }
+
+    #[test]
+    fn test_oversize_image_rejected() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let input_path = temp_dir.path().join("huge.png");
+        write_test_png(&input_path, 300, 200);
+
+        let input_data = std::fs::read(&input_path).unwrap();
+        let settings = CompressionSettings::new(80, OutputFormat::Png)
+            .with_max_dimensions(Dimensions::new(100, 100).unwrap(), OversizePolicy::Reject);
+        let result = compress_bytes(&input_data, "png", &settings);
+
+        assert!(matches!(result, Err(CompressionError::ProcessingError(_))));
+    }
+
+    #[test]
+    fn test_run_with_timeout() {
+        let timeout = std::time::Duration::from_millis(20);
+
+        // Compresseur identité volontairement lent
+        let slow = run_with_timeout(timeout, || {
+            std::thread::sleep(std::time::Duration::from_millis(500));
+            Ok(b"input".to_vec())
+        });
+        assert!(
+            matches!(slow, Err(CompressionError::ProcessingError(msg)) if msg.contains("timed out"))
+        );
+
+        let fast = run_with_timeout(timeout, || Ok(b"input".to_vec()));
+        assert_eq!(fast.unwrap(), b"input");
+    }
+
+    #[test]
+    fn test_abandoned_workers_are_awaited() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let finished = std::sync::Arc::new(AtomicBool::new(false));
+        let worker_finished = finished.clone();
+        let result = run_with_timeout(std::time::Duration::from_millis(10), move || {
+            std::thread::sleep(std::time::Duration::from_millis(300));
+            worker_finished.store(true, Ordering::SeqCst);
+            Ok(())
+        });
+        assert!(result.is_err());
+        // Le worker n'est pas annulé : il tourne encore et reste compté
+        assert!(abandoned_workers() >= 1);
+
+        wait_for_abandoned_workers();
+        assert!(finished.load(Ordering::SeqCst));
+        assert_eq!(abandoned_workers(), 0);
+    }
+
+    #[test]
+    fn test_timed_out_file_is_never_written() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let input_path = temp_dir.path().join("gradient.png");
+        let output_path = temp_dir.path().join("gradient.webp");
+        write_test_png(&input_path, 256, 256);
+        let settings = CompressionSettings::new(80, OutputFormat::WebP);
+
+        let result = compress_file_to_file_with_timeout(
+            &input_path,
+            &output_path,
+            &settings,
+            Some(std::time::Duration::ZERO),
+            |_| {},
+        );
+        assert!(
+            matches!(result, Err(CompressionError::ProcessingError(msg)) if msg.contains("timed out"))
+        );
+
+        // Le worker termine en arrière-plan sans rien écrire
+        std::thread::sleep(std::time::Duration::from_millis(1500));
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+
+        let output = compress_file_to_file_with_timeout(
+            &input_path,
+            &output_path,
+            &settings,
+            Some(std::time::Duration::from_secs(30)),
+            |_| {},
+        )
+        .unwrap();
+        assert_eq!(output.output_path, output_path);
+        assert!(output_path.exists());
+    }
+
+    #[cfg(feature = "mozjpeg")]
+    #[test]
+    fn test_mozjpeg_not_larger_than_builtin() {
+        // Dégradés et bruit déterministe, proche d'une photo pour l'encodeur
+        let img = image::RgbImage::from_fn(256, 192, |x, y| {
+            let noise =
+                (x.wrapping_mul(7919) ^ y.wrapping_mul(104_729)).wrapping_mul(2_654_435_761) >> 27;
+            image::Rgb([
+                (x + noise) as u8,
+                (y + noise * 2) as u8,
+                ((x + y) / 2 + noise) as u8,
+            ])
+        });
+        let mut input_data = Vec::new();
+        img.write_to(
+            &mut std::io::Cursor::new(&mut input_data),
+            image::ImageFormat::Png,
+        )
+        .unwrap();
+
+        let settings = CompressionSettings::new(80, OutputFormat::Jpeg);
+        let builtin = compress_to_jpeg(
+            &input_data,
+            "png",
+            &settings.clone().with_jpeg_encoder(JpegEncoder::Builtin),
+        )
+        .unwrap();
+        let mozjpeg = compress_to_jpeg(
+            &input_data,
+            "png",
+            &settings.with_jpeg_encoder(JpegEncoder::MozJpeg),
+        )
+        .unwrap();
+
+        let decoded =
+            image::load_from_memory_with_format(&mozjpeg, image::ImageFormat::Jpeg).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (256, 192));
+        assert!(mozjpeg.len() <= builtin.len());
+    }
+
+    #[test]
+    fn test_compress_to_formats() {
+        let mut input_data = Vec::new();
+        image::RgbaImage::from_fn(40, 30, |x, y| {
+            image::Rgba([(x * 6) as u8, (y * 8) as u8, 90, 200])
+        })
+        .write_to(
+            &mut std::io::Cursor::new(&mut input_data),
+            image::ImageFormat::Png,
+        )
+        .unwrap();
+
+        let outputs = compress_to_formats(
+            &input_data,
+            "png",
+            vec![(OutputFormat::WebP, 75), (OutputFormat::Jpeg, 85)],
+        );
+
+        assert_eq!(outputs.len(), 2);
+        let webp = image::load_from_memory_with_format(
+            outputs[0].as_ref().unwrap(),
+            image::ImageFormat::WebP,
+        )
+        .unwrap();
+        let jpeg = image::load_from_memory_with_format(
+            outputs[1].as_ref().unwrap(),
+            image::ImageFormat::Jpeg,
+        )
+        .unwrap();
+        assert_eq!((webp.width(), webp.height()), (40, 30));
+        assert_eq!((jpeg.width(), jpeg.height()), (40, 30));
+
+        let invalid = compress_to_formats(b"not an image", "png", vec![(OutputFormat::WebP, 75)]);
+        assert!(matches!(invalid[0], Err(CompressionError::DecodeFailed(_))));
+    }
+
+    #[test]
+    fn test_verify_encoded_output() {
+        let mut input_data = Vec::new();
+        image::RgbImage::from_fn(48, 32, |x, y| {
+            image::Rgb([(x * 5) as u8, (y * 7) as u8, 64])
+        })
+        .write_to(
+            &mut std::io::Cursor::new(&mut input_data),
+            image::ImageFormat::Png,
+        )
+        .unwrap();
+
+        let settings =
+            CompressionSettings::new(80, OutputFormat::WebP).with_output_verification(true);
+        let output = compress_bytes(&input_data, "png", &settings).unwrap();
+        assert!(verify_encoded_output(&output, &input_data, &settings).is_ok());
+
+        let truncated = &output[..output.len() / 2];
+        assert!(matches!(
+            verify_encoded_output(truncated, &input_data, &settings),
+            Err(CompressionError::EncodeFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_png_interlace() {
+        let mut input_data = Vec::new();
+        image::RgbImage::from_fn(64, 64, |x, y| {
+            image::Rgb([(x * 4) as u8, (y * 4) as u8, 128])
+        })
+        .write_to(
+            &mut std::io::Cursor::new(&mut input_data),
+            image::ImageFormat::Png,
+        )
+        .unwrap();
+
+        // Signature (8) + longueur et type du chunk (8) + largeur, hauteur, profondeur,
+        // type de couleur, compression, filtre (12) : l'octet d'entrelacement de l'IHDR
+        const IHDR_INTERLACE_OFFSET: usize = 28;
+        for (interlace, expected) in [(true, 1), (false, 0)] {
+            let settings =
+                CompressionSettings::new(80, OutputFormat::Png).with_png_interlace(interlace);
+            let output = compress_bytes(&input_data, "png", &settings).unwrap();
+            assert_eq!(output[IHDR_INTERLACE_OFFSET], expected);
+        }
+    }
+
+    #[test]
+    fn test_huge_declared_dimensions_rejected() {
+        let mut input_data = Vec::new();
+        image::RgbImage::new(8, 8)
+            .write_to(
+                &mut std::io::Cursor::new(&mut input_data),
+                image::ImageFormat::Jpeg,
+            )
+            .unwrap();
+
+        // En-tête SOF0 falsifié : 60000×60000 annoncés pour 8×8 réels
+        let sof = input_data
+            .windows(2)
+            .position(|marker| marker == [0xFF, 0xC0])
+            .unwrap();
+        input_data[sof + 5..sof + 9].copy_from_slice(&[0xEA, 0x60, 0xEA, 0x60]);
+
+        let settings = CompressionSettings::new(80, OutputFormat::WebP).with_max_dimensions(
+            Dimensions::new(4096, 4096).unwrap(),
+            OversizePolicy::Downscale,
+        );
+        let error = compress_bytes(&input_data, "jpeg", &settings).unwrap_err();
+
+        assert!(matches!(error, CompressionError::InvalidDimensions(_)));
+        assert!(matches!(
+            DomainError::from(error),
+            DomainError::InvalidInput(_)
+        ));
+    }
+
+    #[test]
+    fn test_quality_size_curve_monotonic() {
+        // Dégradés et bruit déterministe, proche d'une photo pour l'encodeur
+        let img = image::RgbImage::from_fn(256, 192, |x, y| {
+            let noise =
+                (x.wrapping_mul(7919) ^ y.wrapping_mul(104_729)).wrapping_mul(2_654_435_761) >> 27;
+            image::Rgb([
+                (x + noise) as u8,
+                (y + noise * 2) as u8,
+                ((x + y) / 2 + noise) as u8,
+            ])
+        });
+        let mut input_data = Vec::new();
+        img.write_to(
+            &mut std::io::Cursor::new(&mut input_data),
+            image::ImageFormat::Png,
+        )
+        .unwrap();
+
+        for format in [OutputFormat::WebP, OutputFormat::Jpeg] {
+            let curve =
+                quality_size_curve(&input_data, "png", format, &[85, 20, 50, 70, 35, 50]).unwrap();
+            let qualities: Vec<u8> = curve.iter().map(|(quality, _)| *quality).collect();
+            assert_eq!(qualities, vec![20, 35, 50, 70, 85]);
+            for pair in curve.windows(2) {
+                assert!(pair[0].1 <= pair[1].1, "{:?}: {:?}", format, curve);
+            }
+        }
+
+        let too_many: Vec<u8> = (1..=20).collect();
+        assert!(quality_size_curve(&input_data, "png", OutputFormat::WebP, &too_many).is_err());
+        assert!(quality_size_curve(&input_data, "png", OutputFormat::WebP, &[0, 50]).is_err());
+    }
+
+    #[test]
+    fn test_already_optimized_webp_is_skipped() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let input_path = temp_dir.path().join("tight.webp");
+        let output_path = temp_dir.path().join("tight_out.webp");
+
+        // Dégradé encodé à basse qualité : bien en dessous de 5% de la taille brute
+        let img =
+            image::RgbaImage::from_fn(256, 256, |x, y| image::Rgba([x as u8, y as u8, 128, 255]));
+        let input_data = webp::Encoder::from_rgba(img.as_raw(), 256, 256)
+            .encode(10.0)
+            .to_vec();
+        std::fs::write(&input_path, &input_data).unwrap();
+
+        let settings = CompressionSettings::new(80, OutputFormat::WebP).with_skip_if_no_gain(true);
+        let output = compress_file_to_file(&input_path, &output_path, &settings).unwrap();
+        assert!(output.skipped_already_optimized);
+        assert_eq!(std::fs::read(&output_path).unwrap(), input_data);
+
+        // Sans le réglage, ou vers un autre format, le fichier est ré-encodé
+        let settings = CompressionSettings::new(80, OutputFormat::WebP);
+        let output = compress_file_to_file(&input_path, &output_path, &settings).unwrap();
+        assert!(!output.skipped_already_optimized);
+
+        let settings = CompressionSettings::new(80, OutputFormat::Png).with_skip_if_no_gain(true);
+        assert!(!compress_bytes(&input_data, "webp", &settings)
+            .unwrap()
+            .starts_with(b"RIFF"));
+    }
+
+    #[test]
+    fn test_never_grow_keeps_original() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let input_path = temp_dir.path().join("noise.jpg");
+        let output_dir = temp_dir.path().join("out");
+        std::fs::create_dir(&output_dir).unwrap();
+        let output_path = output_dir.join("noise.png");
+
+        // Bruit en JPEG basse qualité : le PNG sans perte sera bien plus lourd
+        let img = image::RgbImage::from_fn(128, 128, |x, y| {
+            let noise =
+                (x.wrapping_mul(7919) ^ y.wrapping_mul(104_729)).wrapping_mul(2_654_435_761);
+            image::Rgb([(noise >> 24) as u8, (noise >> 16) as u8, (noise >> 8) as u8])
+        });
+        let mut input_data = Vec::new();
+        image::codecs::jpeg::JpegEncoder::new_with_quality(&mut input_data, 40)
+            .encode_image(&img)
+            .unwrap();
+        std::fs::write(&input_path, &input_data).unwrap();
+
+        let settings = CompressionSettings::new(80, OutputFormat::Png).with_never_grow(true);
+        let output = compress_file_to_file(&input_path, &output_path, &settings).unwrap();
+        assert!(output.kept_original);
+        assert_eq!(output.savings_percent, 0.0);
+        assert_eq!(output.format, OutputFormat::Jpeg);
+        assert_eq!(output.output_path, output_dir.join("noise.jpg"));
+        assert_eq!(std::fs::read(&output.output_path).unwrap(), input_data);
+        assert!(!output_path.exists());
+
+        // Un fichier existant au chemin conservé n'est jamais écrasé
+        std::fs::write(output_dir.join("noise.jpg"), b"another photo").unwrap();
+        let output = compress_file_to_file(&input_path, &output_path, &settings).unwrap();
+        assert_eq!(output.output_path, output_dir.join("noise (1).jpg"));
+        assert_eq!(std::fs::read(&output.output_path).unwrap(), input_data);
+        assert_eq!(
+            std::fs::read(output_dir.join("noise.jpg")).unwrap(),
+            b"another photo"
+        );
+
+        // Même dossier, même nom : l'original serait sa propre sortie
+        let beside_input = temp_dir.path().join("noise.png");
+        assert!(compress_file_to_file(&input_path, &beside_input, &settings).is_err());
+        assert_eq!(std::fs::read(&input_path).unwrap(), input_data);
+
+        let settings = CompressionSettings::new(80, OutputFormat::Png);
+        let output = compress_file_to_file(&input_path, &output_path, &settings).unwrap();
+        assert!(!output.kept_original);
+        assert!(output.savings_percent < 0.0);
+    }
+
+    #[test]
+    fn test_stat_lossy_mode_follows_encoding() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let input_path = temp_dir.path().join("gradient.png");
+        write_test_png(&input_path, 64, 64);
+
+        let cases = [
+            (CompressionSettings::new(50, OutputFormat::Png), false),
+            (
+                CompressionSettings::new(50, OutputFormat::WebP).with_lossless(true),
+                false,
+            ),
+            (CompressionSettings::new(80, OutputFormat::WebP), true),
+            // libwebp passe en lossless à partir de q90
+            (CompressionSettings::new(95, OutputFormat::WebP), false),
+            (
+                CompressionSettings::new(95, OutputFormat::Jpeg).with_lossless(true),
+                true,
+            ),
+        ];
+        for (i, (settings, expected)) in cases.into_iter().enumerate() {
+            let output_path =
+                temp_dir
+                    .path()
+                    .join(format!("out_{}.{}", i, settings.format.extension()));
+            let output = compress_file_to_file(&input_path, &output_path, &settings).unwrap();
+            let stat = create_compression_stat("png", &output, &settings);
+            assert_eq!(stat.lossy_mode, expected, "{:?}", settings.format);
+        }
+
+        // JPEG -> JPEG sans perte : transcodage des coefficients
+        let settings = CompressionSettings::new(80, OutputFormat::Jpeg).with_lossless(true);
+        assert!(!is_lossy_encoding("jpg", &settings));
+        assert!(is_lossy_encoding("jpg", &settings.with_lossless(false)));
+    }
+
+    #[test]
+    fn test_downscaled_lossless_jpeg_is_lossy() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let input_path = temp_dir.path().join("photo.jpg");
+        let img = image::RgbImage::from_fn(200, 150, |x, y| {
+            image::Rgb([(x % 256) as u8, (y % 256) as u8, ((x + y) % 256) as u8])
+        });
+        img.save_with_format(&input_path, image::ImageFormat::Jpeg)
+            .unwrap();
+
+        let settings = CompressionSettings::new(80, OutputFormat::Jpeg).with_lossless(true);
+        let output =
+            compress_file_to_file(&input_path, &temp_dir.path().join("kept.jpg"), &settings)
+                .unwrap();
+        assert!(!output.lossy_mode);
+
+        // max_dimensions plus petit que la source : redimensionné puis réencodé
+        let settings = settings.with_max_dimensions(
+            Dimensions::new(100, 100).unwrap(),
+            OversizePolicy::Downscale,
+        );
+        let output_path = temp_dir.path().join("resized.jpg");
+        let output = compress_file_to_file(&input_path, &output_path, &settings).unwrap();
+        assert_eq!(image::image_dimensions(&output_path).unwrap(), (100, 75));
+        assert!(output.lossy_mode);
+        assert!(create_compression_stat("jpg", &output, &settings).lossy_mode);
+    }
+
+    #[test]
+    fn test_garbage_input_is_decode_failure() {
+        let garbage = b"definitely not a png, just some bytes".repeat(4);
+        let settings = CompressionSettings::new(80, OutputFormat::WebP);
+
+        let result = compress_bytes(&garbage, "png", &settings);
+        assert!(matches!(result, Err(CompressionError::DecodeFailed(_))));
+
+        let domain_error: crate::domain::DomainError = result.unwrap_err().into();
+        assert!(matches!(
+            domain_error,
+            crate::domain::DomainError::InvalidInput(_)
+        ));
+    }
+
+    #[test]
+    fn test_oversize_image_downscaled() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let input_path = temp_dir.path().join("huge.png");
+        write_test_png(&input_path, 300, 200);
+
+        let input_data = std::fs::read(&input_path).unwrap();
+        let settings = CompressionSettings::new(80, OutputFormat::Png).with_max_dimensions(
+            Dimensions::new(100, 100).unwrap(),
+            OversizePolicy::Downscale,
+        );
+        let compressed = compress_bytes(&input_data, "png", &settings).unwrap();
+
+        let img = image::load_from_memory(&compressed).unwrap();
+        assert_eq!((img.width(), img.height()), (100, 67));
+    }
+
+    #[test]
+    fn test_palette_reduction_shrinks_logo() {
+        // 16 couleurs en bandes bruitées, encodé en RGBA 8 bits
+        let img = image::RgbaImage::from_fn(128, 128, |x, y| {
+            let index = ((x * 7 + y * 13 + (x * y) % 5) % 16) as u8;
+            image::Rgba([index * 16, 255 - index * 8, index * 3, 255])
+        });
+        let mut input_data = Vec::new();
+        img.write_to(
+            &mut std::io::Cursor::new(&mut input_data),
+            image::ImageFormat::Png,
+        )
+        .unwrap();
+
+        let straight = compress_bytes(
+            &input_data,
+            "png",
+            &CompressionSettings::new(80, OutputFormat::Png),
+        )
+        .unwrap();
+        let mut settings = CompressionSettings::new(80, OutputFormat::Png);
+        settings.palette_reduction = true;
+        let reduced = compress_bytes(&input_data, "png", &settings).unwrap();
+
+        // Indexed color type in IHDR, far smaller than the RGBA source
+        assert_eq!(reduced[25], 3);
+        assert!(reduced.len() * 10 < input_data.len());
+        assert!(reduced.len() <= straight.len());
+        let decoded = image::load_from_memory(&reduced).unwrap().to_rgba8();
+        assert_eq!(decoded, img);
+    }
+
+    #[test]
+    fn test_jpeg_flattens_transparency_on_white() {
+        // Moitié gauche opaque rouge, moitié droite entièrement transparente
+        let img = image::RgbaImage::from_fn(32, 32, |x, _| {
+            if x < 16 {
+                image::Rgba([200, 0, 0, 255])
+            } else {
+                image::Rgba([0, 0, 0, 0])
+            }
+        });
+        let mut input_data = Vec::new();
+        img.write_to(
+            &mut std::io::Cursor::new(&mut input_data),
+            image::ImageFormat::Png,
+        )
+        .unwrap();
+
+        let settings = CompressionSettings::new(90, OutputFormat::Jpeg);
+        let output = compress_bytes(&input_data, "png", &settings).unwrap();
+
+        let decoded = image::load_from_memory(&output).unwrap().to_rgb8();
+        let pixel = decoded.get_pixel(28, 16).0;
+        assert!(pixel.iter().all(|&channel| channel > 240), "{:?}", pixel);
+    }
+
+    #[test]
+    fn test_memory_limit_forces_serial_batch() {
+        // Quatre images 4K déclarées (~32 MB décodées chacune)
+        let estimated_bytes = vec![3840 * 2160 * 4; 4];
+
+        let waves = schedule_waves(&estimated_bytes, 4, Some(1024 * 1024));
+        assert_eq!(waves.len(), 4);
+        assert!(waves.iter().all(|wave| wave.len() == 1));
+
+        let waves = schedule_waves(&estimated_bytes, 4, Some(100 * 1024 * 1024));
+        assert_eq!(waves, vec![vec![0, 1, 2], vec![3]]);
+
+        let waves = schedule_waves(&estimated_bytes, 4, None);
+        assert_eq!(waves, vec![vec![0, 1, 2, 3]]);
+    }
+
+    #[test]
+    fn test_parallel_batch_keeps_order() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let files: Vec<_> = (0..3u32)
+            .map(|index| {
+                let input_path = temp_dir.path().join(format!("image_{}.png", index));
+                write_test_png(&input_path, 16 + index * 8, 16);
+                (
+                    input_path,
+                    temp_dir.path().join(format!("image_{}.webp", index)),
+                )
+            })
+            .collect();
+
+        let settings = CompressionSettings::new(80, OutputFormat::WebP).with_memory_limit(1);
+        let results = compress_batch_files_parallel(files.clone(), &settings, 2);
+
+        assert_eq!(results.len(), 3);
+        for ((_, output_path), result) in files.iter().zip(results) {
+            assert_eq!(&result.unwrap().output_path, output_path);
+        }
+    }
+
+    #[test]
+    fn test_failed_write_leaves_no_partial_output() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let input_path = temp_dir.path().join("input.png");
+        write_test_png(&input_path, 32, 32);
+        let output_path = temp_dir.path().join("output.webp");
+        std::fs::create_dir(&output_path).unwrap();
+
+        let settings = CompressionSettings::new(80, OutputFormat::WebP);
+        let result = compress_file_to_file(&input_path, &output_path, &settings);
+
+        assert!(matches!(result, Err(CompressionError::IoError(_))));
+        assert!(output_path.is_dir());
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_resize_never_upscales() {
+        use crate::domain::image::Dimensions;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let input_path = temp_dir.path().join("small.png");
+        write_test_png(&input_path, 200, 100);
+
+        let input_data = std::fs::read(&input_path).unwrap();
+        let settings = CompressionSettings::new(80, OutputFormat::Png)
+            .with_resize(Dimensions::new(1000, 1000).unwrap(), false);
+        let compressed = compress_bytes(&input_data, "png", &settings).unwrap();
+
+        let img = image::load_from_memory(&compressed).unwrap();
+        assert_eq!((img.width(), img.height()), (200, 100));
+    }
 }
@@ -16,6 +16,14 @@ pub enum CompressionError {
     IoError(String),
     /// Compression ratio too low
     InsufficientCompression(f64),
+    /// Decoded image would exceed the memory limit (required MB, limit MB)
+    MemoryLimitExceeded(u64, u64),
+    /// Input could not be decoded (corrupt or mislabeled file)
+    DecodeFailed(String),
+    /// Encoder failed to produce the output image
+    EncodeFailed(String),
+    /// Declared dimensions are zero or too large to decode safely
+    InvalidDimensions(String),
 }
 
 impl fmt::Display for CompressionError {
@@ -39,6 +47,22 @@ impl fmt::Display for CompressionError {
             CompressionError::InsufficientCompression(ratio) => {
                 write!(f, "Compression ratio too low: {:.2}%", ratio * 100.0)
             }
+            CompressionError::MemoryLimitExceeded(required_mb, limit_mb) => {
+                write!(
+                    f,
+                    "Image too large: needs ~{} MB decoded, limit is {} MB",
+                    required_mb, limit_mb
+                )
+            }
+            CompressionError::DecodeFailed(msg) => {
+                write!(f, "Failed to decode image: {}", msg)
+            }
+            CompressionError::EncodeFailed(msg) => {
+                write!(f, "Failed to encode image: {}", msg)
+            }
+            CompressionError::InvalidDimensions(msg) => {
+                write!(f, "Invalid image dimensions: {}", msg)
+            }
         }
     }
 }
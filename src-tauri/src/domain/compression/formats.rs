@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 
 /// Supported output formats for image compression
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum OutputFormat {
     Png,
     Jpeg,
@@ -9,6 +9,11 @@ pub enum OutputFormat {
 }
 
 impl OutputFormat {
+    /// Every format the compression engine can write
+    pub fn all() -> [OutputFormat; 3] {
+        [OutputFormat::Png, OutputFormat::Jpeg, OutputFormat::WebP]
+    }
+
     /// Returns the file extension for this format
     pub fn extension(&self) -> &'static str {
         match self {
@@ -41,6 +46,49 @@ impl OutputFormat {
     pub fn supports_lossless(&self) -> bool {
         matches!(self, OutputFormat::Png | OutputFormat::WebP)
     }
+
+    /// Returns true if this format can store an alpha channel
+    pub fn supports_alpha(&self) -> bool {
+        matches!(self, OutputFormat::Png | OutputFormat::WebP)
+    }
+
+    /// Output formats worth offering for an input format, most recommended first
+    pub fn valid_conversions_from(input: &str) -> Vec<OutputFormat> {
+        match OutputFormat::from_string(input) {
+            Some(OutputFormat::Png) => {
+                vec![OutputFormat::WebP, OutputFormat::Png, OutputFormat::Jpeg]
+            }
+            // Repasser un JPEG en PNG ne fait que grossir le fichier
+            Some(OutputFormat::Jpeg) => vec![OutputFormat::WebP, OutputFormat::Jpeg],
+            Some(OutputFormat::WebP) => {
+                vec![OutputFormat::WebP, OutputFormat::Jpeg, OutputFormat::Png]
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// Explain what a conversion loses, if anything worth telling the user
+    pub fn conversion_warning(input: &str, output: OutputFormat) -> Option<String> {
+        let input_format = match OutputFormat::from_string(input) {
+            Some(input_format) => input_format,
+            None => return Some(format!("Unsupported input format: {}", input)),
+        };
+
+        if input_format.supports_alpha() && !output.supports_alpha() {
+            return Some(format!(
+                "{} has no transparency: transparent areas of the {} will be filled with the background color",
+                output, input_format
+            ));
+        }
+
+        if input_format == OutputFormat::Jpeg && output == OutputFormat::Png {
+            return Some(
+                "PNG keeps JPEG artifacts losslessly: the file will usually get larger".to_string(),
+            );
+        }
+
+        None
+    }
 }
 
 impl std::fmt::Display for OutputFormat {
@@ -76,6 +124,30 @@ mod tests {
         assert_eq!(OutputFormat::from_string("unknown"), None);
     }
 
+    #[test]
+    fn test_valid_conversions() {
+        let from_png = OutputFormat::valid_conversions_from("PNG");
+        assert_eq!(from_png.len(), 3);
+        assert_eq!(from_png[0], OutputFormat::WebP);
+        assert!(!OutputFormat::valid_conversions_from("jpg").contains(&OutputFormat::Png));
+        assert!(OutputFormat::valid_conversions_from("gif").is_empty());
+    }
+
+    #[test]
+    fn test_conversion_warning() {
+        let warning = OutputFormat::conversion_warning("png", OutputFormat::Jpeg).unwrap();
+        assert!(warning.contains("transparency"));
+        assert_eq!(
+            OutputFormat::conversion_warning("png", OutputFormat::WebP),
+            None
+        );
+        assert_eq!(
+            OutputFormat::conversion_warning("jpeg", OutputFormat::WebP),
+            None
+        );
+        assert!(OutputFormat::conversion_warning("jpg", OutputFormat::Png).is_some());
+    }
+
     #[test]
     fn test_lossless_support() {
         assert!(OutputFormat::Png.supports_lossless());
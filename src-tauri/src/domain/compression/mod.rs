@@ -3,6 +3,8 @@
 // This module provides image compression functionality using pure functions
 // and data structures, following Rust idioms for zero-cost abstractions.
 
+pub mod cache;
+pub mod color_profile;
 pub mod engine;
 pub mod error;
 pub mod formats;
@@ -15,21 +17,37 @@ pub mod store;
 // Re-export core types and functions for easy access
 pub use error::{CompressionError, CompressionResult, StatsError, StatsResult};
 pub use formats::OutputFormat;
-pub use settings::CompressionSettings;
+pub use settings::{
+    webp_encodes_lossless, CompressionSettings, JpegEncoder, OversizePolicy,
+    WEBP_LOSSLESS_MIN_QUALITY,
+};
+
+// Result cache keyed by content and settings
+pub use cache::{configure_global_cache, global_cache, CacheKey, CompressionCache};
+
+// ICC color profile handling
+pub use color_profile::{embed_webp_icc_profile, extract_icc_profile};
 
 // Engine functions - core compression operations
 pub use engine::{
-    compress_batch_files, compress_file_to_file, create_compression_stat, CompressionOutput,
+    abandoned_workers, calibrate_predictions, calibration_stats, classify_input,
+    compress_batch_files, compress_batch_files_parallel, compress_bytes, compress_bytes_with_cache,
+    compress_bytes_with_timeout, compress_file_to_file, compress_file_to_file_with_timeout,
+    compress_to_formats, create_compression_stat, is_lossy_encoding, plan_batch_waves,
+    quality_size_curve, record_calibration, run_with_timeout, wait_for_abandoned_workers,
+    with_decode_progress, CompressionOutput, MAX_CURVE_POINTS,
 };
 
 // Statistics types and functions
 pub use stats::{
-    calculate_confidence, create_stat, estimate_compression, get_size_range, CompressionStat,
-    EstimationQuery, EstimationResult,
+    calculate_confidence, create_stat, estimate_compression, get_size_range, heuristic_interval,
+    percentile, recency_weighted_reduction, BatchEstimation, CompressionStat, EstimationQuery,
+    EstimationResult, FileEstimation, ImageTypeCompression, PredictionAccuracy, PredictionBias,
+    CALIBRATION_SOURCE, DEFAULT_STATS_HALF_LIFE_DAYS,
 };
 
 // Storage trait and implementations
-pub use store::{SqliteStatsStore, StatsStore};
+pub use store::{estimate_with_fallback, SqliteStatsStore, StatsStore};
 
 // Prediction service for size estimation
 pub use prediction::{create_prediction_query, CompressionPredictionService};
@@ -54,6 +72,58 @@ pub fn max_compression_settings() -> CompressionSettings {
     CompressionSettings::new(70, OutputFormat::WebP).with_alpha_optimization(true)
 }
 
+/// Named, fully-specified settings the UI can offer
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CompressionPreset {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub settings: CompressionSettings,
+}
+
+/// Every built-in preset, in display order
+pub fn list_presets() -> Vec<CompressionPreset> {
+    use crate::domain::image::Dimensions;
+
+    // Les photos partagées sont réduites : inutile d'envoyer un capteur 24 Mpx par mail
+    let fit = |side: u32| Dimensions::new(side, side).expect("non-zero preset dimensions");
+
+    vec![
+        CompressionPreset {
+            name: "web",
+            description: "Balanced WebP for websites",
+            settings: web_optimized_settings(),
+        },
+        CompressionPreset {
+            name: "archive",
+            description: "Lossless WebP keeping metadata and color profile",
+            settings: CompressionSettings::new(100, OutputFormat::WebP)
+                .with_lossless(true)
+                .with_metadata_preservation(true)
+                .with_never_grow(true),
+        },
+        CompressionPreset {
+            name: "email",
+            description: "Small JPEG attachments, at most 1600px",
+            settings: CompressionSettings::new(70, OutputFormat::Jpeg)
+                .with_max_dimensions(fit(1600), OversizePolicy::Downscale)
+                .with_never_grow(true),
+        },
+        CompressionPreset {
+            name: "social",
+            description: "JPEG sized for social networks, at most 2048px, metadata stripped",
+            settings: CompressionSettings::new(85, OutputFormat::Jpeg)
+                .with_max_dimensions(fit(2048), OversizePolicy::Downscale),
+        },
+    ]
+}
+
+/// Look up a built-in preset, ignoring case
+pub fn preset_by_name(name: &str) -> Option<CompressionPreset> {
+    list_presets()
+        .into_iter()
+        .find(|preset| preset.name.eq_ignore_ascii_case(name.trim()))
+}
+
 /// Quick file-to-file compression with default settings
 pub fn quick_compress_file<P: AsRef<std::path::Path>>(
     input_path: P,
@@ -63,10 +133,65 @@ pub fn quick_compress_file<P: AsRef<std::path::Path>>(
     compress_file_to_file(input_path, output_path, &settings)
 }
 
+/// Convert an image file to PNG without any quality loss (e.g. WebP assets for editing tools)
+pub fn convert_to_png<P: AsRef<std::path::Path>>(
+    input_path: P,
+    output_path: P,
+) -> CompressionResult<CompressionOutput> {
+    let settings = CompressionSettings::new(100, OutputFormat::Png)
+        .with_lossless(true)
+        .with_metadata_preservation(true);
+    compress_file_to_file(input_path, output_path, &settings)
+}
+
 #[cfg(test)]
 mod integration_tests {
     use super::*;
 
+    #[test]
+    fn test_convert_transparent_webp_to_png() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let input_path = temp_dir.path().join("asset.webp");
+        let output_path = temp_dir.path().join("asset.png");
+
+        // Dégradé d'alpha, avec des pixels entièrement transparents
+        let rgba: Vec<u8> = (0..32u32 * 32)
+            .flat_map(|i| {
+                [
+                    (i % 256) as u8,
+                    80,
+                    (i / 4 % 256) as u8,
+                    (i * 7 % 256) as u8,
+                ]
+            })
+            .collect();
+        let webp_data = webp::Encoder::from_rgba(&rgba, 32, 32).encode_lossless();
+        std::fs::write(&input_path, &*webp_data).unwrap();
+
+        convert_to_png(&input_path, &output_path).unwrap();
+
+        let original = image::load_from_memory(&webp_data).unwrap().to_rgba8();
+        let converted = image::open(&output_path).unwrap();
+        assert!(converted.color().has_alpha());
+        assert_eq!(original, converted.to_rgba8());
+    }
+
+    #[test]
+    fn test_preset_lookup() {
+        let names: Vec<_> = list_presets().iter().map(|preset| preset.name).collect();
+        assert_eq!(names, vec!["web", "archive", "email", "social"]);
+
+        let email = preset_by_name("Email").unwrap();
+        assert_eq!(email.settings.format, OutputFormat::Jpeg);
+        assert_eq!(email.settings.quality, 70);
+        assert_eq!(email.settings.max_dimensions.unwrap().width, 1600);
+
+        let archive = preset_by_name("archive").unwrap();
+        assert!(archive.settings.lossless && archive.settings.preserve_metadata);
+        assert!(!preset_by_name("social").unwrap().settings.preserve_metadata);
+        assert!(preset_by_name("thumbnail").is_none());
+    }
+
     #[test]
     fn test_estimation_workflow() {
         let settings = CompressionSettings::new(80, OutputFormat::WebP);
@@ -1,5 +1,7 @@
 use crate::database::{models::CompressionRecord, DatabaseManager};
-use crate::domain::compression::{get_size_range, EstimationQuery, EstimationResult};
+use crate::domain::compression::{
+    get_size_range, heuristic_interval, EstimationQuery, EstimationResult,
+};
 use crate::domain::shared::DomainResult;
 use tauri::AppHandle;
 
@@ -58,11 +60,16 @@ impl CompressionPredictionService {
         // Get sample count (approximate for confidence calculation)
         let sample_count = self.estimate_sample_count(input_format, output_format);
 
+        let confidence = self.calculate_confidence(confidence, sample_count);
+        let (p25_percent, p75_percent) = heuristic_interval(percent, confidence);
+
         Ok(EstimationResult {
             percent,
             ratio,
-            confidence: self.calculate_confidence(confidence, sample_count),
+            confidence,
             sample_count,
+            p25_percent,
+            p75_percent,
         })
     }
 
@@ -180,7 +187,7 @@ mod tests {
     #[test]
     fn test_size_adjustment() {
         let service = CompressionPredictionService {
-            db_manager: DatabaseManager::new(&mock_app_handle()).unwrap(),
+            db_manager: unconnected_db_manager(),
         };
 
         // Small files should have reduced compression
@@ -199,7 +206,7 @@ mod tests {
     #[test]
     fn test_confidence_calculation() {
         let service = CompressionPredictionService {
-            db_manager: DatabaseManager::new(&mock_app_handle()).unwrap(),
+            db_manager: unconnected_db_manager(),
         };
 
         // Low sample count should reduce confidence
@@ -211,9 +218,8 @@ mod tests {
         assert!(high_confidence >= 0.8);
     }
 
-    fn mock_app_handle() -> AppHandle {
-        // This would need to be implemented with a proper mock
-        // For now, this test will fail but shows the intended structure
-        unimplemented!("Mock app handle needed for tests")
+    /// Ces calculs n'ouvrent jamais la base : un chemin sans connexion suffit
+    fn unconnected_db_manager() -> DatabaseManager {
+        DatabaseManager::from_path(std::env::temp_dir().join("plume_prediction_test.db"))
     }
 }
@@ -1,13 +1,67 @@
 use crate::domain::compression::formats::OutputFormat;
+use crate::domain::image::Dimensions;
 use serde::{Deserialize, Serialize};
 
+/// WebP quality from which the encoder switches to lossless
+pub const WEBP_LOSSLESS_MIN_QUALITY: u8 = 90;
+
+/// Whether WebP output is encoded losslessly, for the encoder and the stats alike
+pub fn webp_encodes_lossless(quality: u8, lossless: bool) -> bool {
+    lossless || quality >= WEBP_LOSSLESS_MIN_QUALITY
+}
+
+/// What to do with images larger than the configured maximum dimensions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum OversizePolicy {
+    /// Fail the compression
+    Reject,
+    /// Shrink the image to fit, keeping its aspect ratio
+    #[default]
+    Downscale,
+}
+
+/// JPEG encoder backend
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JpegEncoder {
+    /// Baseline encoder from the `image` crate
+    Builtin,
+    /// mozjpeg: trellis quantization and optimized Huffman tables, smaller files
+    MozJpeg,
+}
+
+impl Default for JpegEncoder {
+    /// mozjpeg when the `mozjpeg` feature is enabled, the builtin encoder otherwise
+    fn default() -> Self {
+        if cfg!(feature = "mozjpeg") {
+            JpegEncoder::MozJpeg
+        } else {
+            JpegEncoder::Builtin
+        }
+    }
+}
+
 /// Configuration settings for image compression operations
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompressionSettings {
     pub quality: u8,
     pub format: OutputFormat,
     pub preserve_metadata: bool,
+    pub preserve_color_profile: bool, // Only honoured when preserve_metadata is set
     pub optimize_alpha: bool,
+    pub lossless: bool, // Force lossless encoding regardless of quality
+    pub flatten_background: [u8; 3], // RGB color transparency is composited over for JPEG
+    pub palette_reduction: bool, // Write PNGs with at most 256 colors as indexed palettes
+    pub skip_if_no_gain: bool, // Keep already-optimized lossy files as they are
+    pub never_grow: bool, // Keep the original file when the output would be larger
+    pub jpeg_encoder: JpegEncoder,
+    pub webp_alpha_quality: Option<u8>, // Lossy WebP alpha plane quality, None = same as color
+    pub png_interlace: Option<bool>, // Force Adam7 interlacing on or off, None = optimizer default
+    pub verify_output: bool,         // Re-decode the encoded bytes before handing them back
+    pub target_dimensions: Option<Dimensions>,
+    pub maintain_aspect_ratio: bool,
+    pub memory_limit_mb: Option<u64>, // Max decoded image size, None = unlimited
+    pub max_dimensions: Option<Dimensions>, // Largest accepted source image, None = unlimited
+    pub on_oversize: OversizePolicy,
 }
 
 impl CompressionSettings {
@@ -17,7 +71,22 @@ impl CompressionSettings {
             quality: quality.clamp(1, 100),
             format,
             preserve_metadata: false,
+            preserve_color_profile: true,
             optimize_alpha: true,
+            lossless: false,
+            flatten_background: [255, 255, 255],
+            palette_reduction: false,
+            skip_if_no_gain: false,
+            never_grow: false,
+            jpeg_encoder: JpegEncoder::default(),
+            webp_alpha_quality: None,
+            png_interlace: None,
+            verify_output: false,
+            target_dimensions: None,
+            maintain_aspect_ratio: true,
+            memory_limit_mb: None,
+            max_dimensions: None,
+            on_oversize: OversizePolicy::default(),
         }
     }
 
@@ -33,12 +102,106 @@ impl CompressionSettings {
         self
     }
 
+    /// Sets ICC color profile preservation (requires metadata preservation)
+    pub fn with_color_profile_preservation(mut self, preserve: bool) -> Self {
+        self.preserve_color_profile = preserve;
+        self
+    }
+
+    /// Returns true when the source ICC profile should be embedded in the output
+    pub fn keeps_color_profile(&self) -> bool {
+        self.preserve_metadata && self.preserve_color_profile
+    }
+
+    /// Whether the requested encoding discards data (PNG and lossless modes do not)
+    pub fn is_lossy(&self) -> bool {
+        match self.format {
+            OutputFormat::Png => false,
+            OutputFormat::WebP => !webp_encodes_lossless(self.quality, self.lossless),
+            OutputFormat::Jpeg => !self.lossless,
+        }
+    }
+
     /// Sets alpha channel optimization
     pub fn with_alpha_optimization(mut self, optimize: bool) -> Self {
         self.optimize_alpha = optimize;
         self
     }
 
+    /// Forces lossless encoding (WebP lossless, PNG) whatever the quality
+    pub fn with_lossless(mut self, lossless: bool) -> Self {
+        self.lossless = lossless;
+        self
+    }
+
+    /// Sets the background color used when flattening transparency (JPEG output)
+    pub fn with_flatten_background(mut self, background: [u8; 3]) -> Self {
+        self.flatten_background = background;
+        self
+    }
+
+    /// Enables lossless palette conversion for PNGs with few colors
+    pub fn with_palette_reduction(mut self, enabled: bool) -> Self {
+        self.palette_reduction = enabled;
+        self
+    }
+
+    /// Returns already-optimized lossy inputs unchanged instead of re-encoding them
+    pub fn with_skip_if_no_gain(mut self, enabled: bool) -> Self {
+        self.skip_if_no_gain = enabled;
+        self
+    }
+
+    /// Keeps the original bytes and extension when compressing would grow the file
+    pub fn with_never_grow(mut self, enabled: bool) -> Self {
+        self.never_grow = enabled;
+        self
+    }
+
+    /// Selects the JPEG encoder backend
+    pub fn with_jpeg_encoder(mut self, encoder: JpegEncoder) -> Self {
+        self.jpeg_encoder = encoder;
+        self
+    }
+
+    /// Encodes the WebP alpha plane at its own quality (0-100), independently of color
+    pub fn with_webp_alpha_quality(mut self, quality: u8) -> Self {
+        self.webp_alpha_quality = Some(quality.min(100));
+        self
+    }
+
+    /// Forces PNG output to be Adam7-interlaced (progressive) or not
+    pub fn with_png_interlace(mut self, interlace: bool) -> Self {
+        self.png_interlace = Some(interlace);
+        self
+    }
+
+    /// Checks that the encoded output decodes to the expected dimensions before it is written
+    pub fn with_output_verification(mut self, enabled: bool) -> Self {
+        self.verify_output = enabled;
+        self
+    }
+
+    /// Sets the maximum output dimensions (the image is never upscaled)
+    pub fn with_resize(mut self, dimensions: Dimensions, maintain_aspect_ratio: bool) -> Self {
+        self.target_dimensions = Some(dimensions);
+        self.maintain_aspect_ratio = maintain_aspect_ratio;
+        self
+    }
+
+    /// Sets the maximum memory a decoded image may use, in MB
+    pub fn with_memory_limit(mut self, memory_limit_mb: u64) -> Self {
+        self.memory_limit_mb = Some(memory_limit_mb);
+        self
+    }
+
+    /// Sets the largest accepted source dimensions and how to handle bigger images
+    pub fn with_max_dimensions(mut self, dimensions: Dimensions, policy: OversizePolicy) -> Self {
+        self.max_dimensions = Some(dimensions);
+        self.on_oversize = policy;
+        self
+    }
+
     /// Validates the settings
     pub fn is_valid(&self) -> bool {
         (1..=100).contains(&self.quality)
@@ -16,8 +16,15 @@ pub struct CompressionStat {
     pub compression_time_ms: Option<u64>,
     pub timestamp: String,
     pub image_type: Option<String>, // 'photo', 'logo', 'graphic'
+    #[serde(default)]
+    pub predicted_percent: Option<f64>, // Estimation shown before compressing
+    #[serde(default)]
+    pub source_type: Option<String>, // 'calibration' for measured samples, None for user runs
 }
 
+/// `source_type` of stats recorded by `calibrate_predictions`
+pub const CALIBRATION_SOURCE: &str = "calibration";
+
 /// Query parameters for compression estimation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EstimationQuery {
@@ -35,6 +42,193 @@ pub struct EstimationResult {
     pub ratio: f64,
     pub confidence: f64, // 0.0 à 1.0
     pub sample_count: u32,
+    pub p25_percent: f64, // Lower quartile of the expected reduction
+    pub p75_percent: f64, // Upper quartile of the expected reduction
+}
+
+/// Projected compression for a single file (dry run, nothing written)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileEstimation {
+    pub file_path: String,
+    pub output_format: String,
+    pub original_size: u64,
+    pub estimated_size: u64,
+    pub estimation: EstimationResult,
+}
+
+impl FileEstimation {
+    /// Projects the output size from the source size and the estimated ratio
+    pub fn new(
+        file_path: String,
+        output_format: String,
+        original_size: u64,
+        estimation: EstimationResult,
+    ) -> Self {
+        let estimated_size =
+            (original_size as f64 * estimation.ratio.clamp(0.0, 1.0)).round() as u64;
+
+        Self {
+            file_path,
+            output_format,
+            original_size,
+            estimated_size,
+            estimation,
+        }
+    }
+}
+
+/// Aggregated projection for a batch of files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchEstimation {
+    pub files: Vec<FileEstimation>,
+    pub total_original_size: u64,
+    pub total_estimated_size: u64,
+    pub estimated_savings_percent: f64,
+}
+
+impl BatchEstimation {
+    /// Aggregates per-file estimations into batch totals
+    pub fn from_files(files: Vec<FileEstimation>) -> Self {
+        let total_original_size: u64 = files.iter().map(|f| f.original_size).sum();
+        let total_estimated_size: u64 = files.iter().map(|f| f.estimated_size).sum();
+
+        let estimated_savings_percent = if total_original_size > 0 {
+            (total_original_size.saturating_sub(total_estimated_size) as f64
+                / total_original_size as f64)
+                * 100.0
+        } else {
+            0.0
+        };
+
+        Self {
+            files,
+            total_original_size,
+            total_estimated_size,
+            estimated_savings_percent,
+        }
+    }
+}
+
+/// Direction of the systematic prediction error
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PredictionBias {
+    Overestimate,  // Predicted more savings than achieved
+    Underestimate, // Predicted less savings than achieved
+    Neutral,
+}
+
+/// Average savings for one image type (photo, logo...) of a format pair
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImageTypeCompression {
+    pub image_type: String, // "unknown" for stats recorded without a type
+    pub average_reduction_percent: f64,
+    pub sample_count: u32,
+}
+
+/// How far stored predictions are from the actual savings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PredictionAccuracy {
+    pub input_format: String,
+    pub output_format: String,
+    pub sample_count: u32,
+    pub mean_absolute_error: f64, // In percentage points
+    pub mean_error: f64,          // Predicted - actual, signed
+    pub bias: PredictionBias,
+}
+
+impl PredictionAccuracy {
+    /// Computes accuracy from (predicted_percent, actual_percent) pairs
+    pub fn from_pairs(input_format: String, output_format: String, pairs: &[(f64, f64)]) -> Self {
+        let sample_count = pairs.len() as u32;
+        let (mean_absolute_error, mean_error) = if pairs.is_empty() {
+            (0.0, 0.0)
+        } else {
+            let count = pairs.len() as f64;
+            (
+                pairs.iter().map(|(p, a)| (p - a).abs()).sum::<f64>() / count,
+                pairs.iter().map(|(p, a)| p - a).sum::<f64>() / count,
+            )
+        };
+
+        // Moins d'un demi-point d'écart moyen : pas de biais significatif
+        let bias = if mean_error > 0.5 {
+            PredictionBias::Overestimate
+        } else if mean_error < -0.5 {
+            PredictionBias::Underestimate
+        } else {
+            PredictionBias::Neutral
+        };
+
+        Self {
+            input_format,
+            output_format,
+            sample_count,
+            mean_absolute_error,
+            mean_error,
+            bias,
+        }
+    }
+}
+
+/// Default number of days after which a stat counts half as much in estimates
+pub const DEFAULT_STATS_HALF_LIFE_DAYS: f64 = 90.0;
+
+/// Weighted mean and variance of (reduction_percent, RFC 3339 timestamp) samples,
+/// each weight halving every `half_life_days` (no decay when it is not positive)
+pub fn recency_weighted_reduction(
+    samples: &[(f64, String)],
+    now: chrono::DateTime<chrono::Utc>,
+    half_life_days: f64,
+) -> Option<(f64, f64)> {
+    let weighted: Vec<(f64, f64)> = samples
+        .iter()
+        .map(|(percent, timestamp)| {
+            // Horodatage illisible : la mesure est traitée comme récente
+            let age_days = chrono::DateTime::parse_from_rfc3339(timestamp)
+                .map(|t| (now - t.with_timezone(&chrono::Utc)).num_seconds() as f64 / 86_400.0)
+                .unwrap_or(0.0)
+                .max(0.0);
+            let weight = if half_life_days > 0.0 {
+                0.5f64.powf(age_days / half_life_days)
+            } else {
+                1.0
+            };
+            (*percent, weight)
+        })
+        .collect();
+
+    let total_weight: f64 = weighted.iter().map(|(_, w)| w).sum();
+    if total_weight <= 0.0 {
+        return None;
+    }
+
+    let mean = weighted.iter().map(|(p, w)| p * w).sum::<f64>() / total_weight;
+    let variance = weighted
+        .iter()
+        .map(|(p, w)| w * (p - mean).powi(2))
+        .sum::<f64>()
+        / total_weight;
+    Some((mean, variance))
+}
+
+/// Linear-interpolated percentile (0.0 to 1.0) of sorted values
+pub fn percentile(sorted: &[f64], p: f64) -> Option<f64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = p.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    Some(sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64))
+}
+
+/// Interquartile range guessed from the confidence when there is too little history:
+/// ±5 points when confident, up to ±20 points when not
+pub fn heuristic_interval(percent: f64, confidence: f64) -> (f64, f64) {
+    let spread = 5.0 + 15.0 * (1.0 - confidence.clamp(0.0, 1.0));
+    (
+        (percent - spread).clamp(0.0, 100.0),
+        (percent + spread).clamp(0.0, 100.0),
+    )
 }
 
 /// Determines the size range for a given file size in bytes
@@ -72,12 +266,15 @@ pub fn estimate_compression(
     };
 
     let ratio = (100.0 - percent) / 100.0;
+    let (p25_percent, p75_percent) = heuristic_interval(percent, confidence);
 
     EstimationResult {
         percent,
         ratio,
         confidence,
         sample_count: if confidence > 0.7 { 100 } else { 10 }, // Simulated sample count
+        p25_percent,
+        p75_percent,
     }
 }
 
@@ -110,7 +307,7 @@ pub fn create_stat(
     settings: &CompressionSettings,
 ) -> CompressionStat {
     let size_reduction_percent = if original_size > 0 {
-        ((original_size - compressed_size) as f64 / original_size as f64) * 100.0
+        ((original_size as f64 - compressed_size as f64) / original_size as f64) * 100.0
     } else {
         0.0
     };
@@ -121,13 +318,15 @@ pub fn create_stat(
         output_format,
         input_size_range: get_size_range(original_size),
         quality_setting: settings.quality,
-        lossy_mode: settings.quality < 90, // Simplified heuristic
+        lossy_mode: settings.is_lossy(),
         size_reduction_percent,
         original_size,
         compressed_size,
         compression_time_ms: None, // Will be set when the stat is recorded with timing
         timestamp: chrono::Utc::now().to_rfc3339(),
         image_type: None, // Could be determined by analysis
+        predicted_percent: None,
+        source_type: None,
     }
 }
 
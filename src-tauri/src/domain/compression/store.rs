@@ -2,9 +2,28 @@ use crate::domain::compression::{
     error::{StatsError, StatsResult},
     formats::OutputFormat,
     settings::CompressionSettings,
-    stats::{CompressionStat, EstimationQuery, EstimationResult},
+    stats::{
+        estimate_compression, get_size_range, heuristic_interval, percentile,
+        recency_weighted_reduction, CompressionStat, EstimationQuery, EstimationResult,
+        ImageTypeCompression, PredictionAccuracy, DEFAULT_STATS_HALF_LIFE_DAYS,
+    },
 };
-use rusqlite::{Connection, OptionalExtension};
+use rusqlite::Connection;
+
+/// Samples needed in the query's size range before ignoring other sizes
+const MIN_SIZE_RANGE_SAMPLES: u32 = 3;
+
+/// Samples needed before quartiles come from history rather than the heuristic spread
+const MIN_PERCENTILE_SAMPLES: u32 = 5;
+
+/// Aggregate of the historical reductions matching a query
+struct ReductionStats {
+    mean: f64, // Recency-weighted
+    count: u32,
+    variance: f64,
+    p25: f64,
+    p75: f64,
+}
 
 /// Trait for storing and retrieving compression statistics
 pub trait StatsStore {
@@ -14,6 +33,13 @@ pub trait StatsStore {
     /// Get compression estimation based on historical data
     fn get_estimation(&self, query: &EstimationQuery) -> StatsResult<EstimationResult>;
 
+    /// Compare stored predictions with actual savings for a format pair
+    fn get_prediction_accuracy(
+        &self,
+        input_format: &str,
+        output_format: &str,
+    ) -> StatsResult<PredictionAccuracy>;
+
     /// Clear all statistics
     fn clear_all(&mut self) -> StatsResult<()>;
 
@@ -24,6 +50,7 @@ pub trait StatsStore {
 /// SQLite implementation of the stats store
 pub struct SqliteStatsStore {
     conn: Connection,
+    half_life_days: f64,
 }
 
 impl SqliteStatsStore {
@@ -32,7 +59,10 @@ impl SqliteStatsStore {
         let conn =
             Connection::open(db_path).map_err(|e| StatsError::DatabaseError(e.to_string()))?;
 
-        let store = SqliteStatsStore { conn };
+        let store = SqliteStatsStore {
+            conn,
+            half_life_days: DEFAULT_STATS_HALF_LIFE_DAYS,
+        };
         store.init_tables()?;
         Ok(store)
     }
@@ -42,11 +72,20 @@ impl SqliteStatsStore {
         let conn =
             Connection::open_in_memory().map_err(|e| StatsError::DatabaseError(e.to_string()))?;
 
-        let store = SqliteStatsStore { conn };
+        let store = SqliteStatsStore {
+            conn,
+            half_life_days: DEFAULT_STATS_HALF_LIFE_DAYS,
+        };
         store.init_tables()?;
         Ok(store)
     }
 
+    /// Set how many days it takes for a stat to lose half its weight in estimates
+    pub fn with_half_life_days(mut self, half_life_days: f64) -> Self {
+        self.half_life_days = half_life_days;
+        self
+    }
+
     fn init_tables(&self) -> StatsResult<()> {
         self.conn
             .execute(
@@ -63,15 +102,212 @@ impl SqliteStatsStore {
                 compressed_size INTEGER NOT NULL,
                 compression_time_ms INTEGER,
                 timestamp TEXT NOT NULL,
-                image_type TEXT
+                image_type TEXT,
+                predicted_percent REAL,
+                source_type TEXT
             )
             "#,
                 [],
             )
             .map_err(|e| StatsError::DatabaseError(e.to_string()))?;
 
+        // Migration des bases créées avant l'ajout de predicted_percent
+        if !self.has_column("predicted_percent")? {
+            self.conn
+                .execute(
+                    "ALTER TABLE compression_stats ADD COLUMN predicted_percent REAL",
+                    [],
+                )
+                .map_err(|e| StatsError::DatabaseError(e.to_string()))?;
+        }
+
+        // Idem pour source_type
+        if !self.has_column("source_type")? {
+            self.conn
+                .execute(
+                    "ALTER TABLE compression_stats ADD COLUMN source_type TEXT",
+                    [],
+                )
+                .map_err(|e| StatsError::DatabaseError(e.to_string()))?;
+        }
+
         Ok(())
     }
+
+    /// Recency-weighted average reduction, sample count and variance of similar
+    /// operations, optionally restricted to one input size range
+    fn query_reduction(
+        &self,
+        query: &EstimationQuery,
+        size_range: Option<&str>,
+    ) -> StatsResult<Option<ReductionStats>> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                r#"
+            SELECT size_reduction_percent, timestamp
+            FROM compression_stats 
+            WHERE input_format = ?1 
+            AND output_format = ?2 
+            AND quality_setting BETWEEN ?3 AND ?4
+            AND lossy_mode = ?5
+            AND (?6 IS NULL OR input_size_range = ?6)
+            "#,
+            )
+            .map_err(|e| StatsError::DatabaseError(e.to_string()))?;
+
+        let quality_range = 10; // +/- 10 quality points
+        let min_quality = (query.quality_setting as i32 - quality_range).max(1) as u8;
+        let max_quality = (query.quality_setting as i32 + quality_range).min(100) as u8;
+
+        let samples = stmt
+            .query_map(
+                rusqlite::params![
+                    query.input_format,
+                    query.output_format,
+                    min_quality,
+                    max_quality,
+                    query.lossy_mode,
+                    size_range,
+                ],
+                |row| Ok((row.get::<_, f64>(0)?, row.get::<_, String>(1)?)),
+            )
+            .map_err(|e| StatsError::DatabaseError(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| StatsError::DatabaseError(e.to_string()))?;
+
+        let mut percents: Vec<f64> = samples.iter().map(|(percent, _)| *percent).collect();
+        percents.sort_by(f64::total_cmp);
+
+        Ok(
+            recency_weighted_reduction(&samples, chrono::Utc::now(), self.half_life_days).map(
+                |(mean, variance)| ReductionStats {
+                    mean,
+                    count: samples.len() as u32,
+                    variance,
+                    p25: percentile(&percents, 0.25).unwrap_or(mean),
+                    p75: percentile(&percents, 0.75).unwrap_or(mean),
+                },
+            ),
+        )
+    }
+
+    /// Average reduction per image type for a format pair, sorted by type
+    pub fn average_compression_by_image_type(
+        &self,
+        input_format: &str,
+        output_format: &str,
+    ) -> StatsResult<Vec<ImageTypeCompression>> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                r#"
+            SELECT COALESCE(image_type, 'unknown') AS type_name,
+                   AVG(size_reduction_percent),
+                   COUNT(*)
+            FROM compression_stats
+            WHERE input_format = ?1
+            AND output_format = ?2
+            GROUP BY type_name
+            ORDER BY type_name
+            "#,
+            )
+            .map_err(|e| StatsError::DatabaseError(e.to_string()))?;
+
+        let rows = stmt
+            .query_map(rusqlite::params![input_format, output_format], |row| {
+                Ok(ImageTypeCompression {
+                    image_type: row.get(0)?,
+                    average_reduction_percent: row.get(1)?,
+                    sample_count: row.get(2)?,
+                })
+            })
+            .map_err(|e| StatsError::DatabaseError(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| StatsError::DatabaseError(e.to_string()))?;
+        Ok(rows)
+    }
+
+    /// Delete stats recorded before `older_than`, then all but the `max_count` most
+    /// recent ones; returns the number of deleted rows
+    pub fn prune_stats(
+        &mut self,
+        max_count: Option<u32>,
+        older_than: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> StatsResult<u32> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, timestamp FROM compression_stats")
+            .map_err(|e| StatsError::DatabaseError(e.to_string()))?;
+        let raw_rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(|e| StatsError::DatabaseError(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| StatsError::DatabaseError(e.to_string()))?;
+        drop(stmt);
+
+        let mut rows: Vec<_> = raw_rows
+            .into_iter()
+            .map(|(id, timestamp)| {
+                let recorded_at = chrono::DateTime::parse_from_rfc3339(&timestamp)
+                    .ok()
+                    .map(|t| t.with_timezone(&chrono::Utc));
+                (id, recorded_at)
+            })
+            .collect();
+
+        // Horodatage illisible : conservé par l'élagage par âge, mais trié comme le plus ancien
+        rows.sort_by(|a, b| b.1.cmp(&a.1).then(b.0.cmp(&a.0)));
+
+        let mut to_delete = Vec::new();
+        if let Some(cutoff) = older_than {
+            rows.retain(|&(id, recorded_at)| {
+                let expired = recorded_at.is_some_and(|recorded_at| recorded_at < cutoff);
+                if expired {
+                    to_delete.push(id);
+                }
+                !expired
+            });
+        }
+        if let Some(max_count) = max_count {
+            to_delete.extend(rows.iter().skip(max_count as usize).map(|&(id, _)| id));
+        }
+
+        let tx = self
+            .conn
+            .transaction()
+            .map_err(|e| StatsError::DatabaseError(e.to_string()))?;
+        {
+            let mut stmt = tx
+                .prepare("DELETE FROM compression_stats WHERE id = ?1")
+                .map_err(|e| StatsError::DatabaseError(e.to_string()))?;
+            for id in &to_delete {
+                stmt.execute([id])
+                    .map_err(|e| StatsError::DatabaseError(e.to_string()))?;
+            }
+        }
+        tx.commit()
+            .map_err(|e| StatsError::DatabaseError(e.to_string()))?;
+
+        Ok(to_delete.len() as u32)
+    }
+
+    fn has_column(&self, column: &str) -> StatsResult<bool> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT name FROM pragma_table_info('compression_stats')")
+            .map_err(|e| StatsError::DatabaseError(e.to_string()))?;
+
+        let names = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(|e| StatsError::DatabaseError(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| StatsError::DatabaseError(e.to_string()))?;
+
+        Ok(names.iter().any(|name| name == column))
+    }
 }
 
 impl StatsStore for SqliteStatsStore {
@@ -83,8 +319,8 @@ impl StatsStore for SqliteStatsStore {
             INSERT INTO compression_stats (
                 input_format, output_format, input_size_range, quality_setting,
                 lossy_mode, size_reduction_percent, original_size, compressed_size,
-                compression_time_ms, timestamp, image_type
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+                compression_time_ms, timestamp, image_type, predicted_percent, source_type
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
             "#,
                 rusqlite::params![
                     stat.input_format,
@@ -98,6 +334,8 @@ impl StatsStore for SqliteStatsStore {
                     stat.compression_time_ms,
                     stat.timestamp,
                     stat.image_type,
+                    stat.predicted_percent,
+                    stat.source_type,
                 ],
             )
             .map_err(|e| StatsError::DatabaseError(e.to_string()))?;
@@ -108,78 +346,75 @@ impl StatsStore for SqliteStatsStore {
     }
 
     fn get_estimation(&self, query: &EstimationQuery) -> StatsResult<EstimationResult> {
-        // Try to find similar compression operations
-        let mut stmt = self
-            .conn
-            .prepare(
-                r#"
-            SELECT 
-                AVG(size_reduction_percent) as avg_reduction,
-                COUNT(*) as count,
-                STDEV(size_reduction_percent) as variance
-            FROM compression_stats 
-            WHERE input_format = ?1 
-            AND output_format = ?2 
-            AND quality_setting BETWEEN ?3 AND ?4
-            AND lossy_mode = ?5
-            "#,
-            )
-            .map_err(|e| StatsError::DatabaseError(e.to_string()))?;
-
-        let quality_range = 10; // +/- 10 quality points
-        let min_quality = (query.quality_setting as i32 - quality_range).max(1) as u8;
-        let max_quality = (query.quality_setting as i32 + quality_range).min(100) as u8;
-
-        let row = stmt
-            .query_row(
-                rusqlite::params![
-                    query.input_format,
-                    query.output_format,
-                    min_quality,
-                    max_quality,
-                    query.lossy_mode,
-                ],
-                |row| {
-                    Ok((
-                        row.get::<_, Option<f64>>("avg_reduction")?,
-                        row.get::<_, u32>("count")?,
-                        row.get::<_, Option<f64>>("variance")?,
-                    ))
-                },
-            )
-            .optional()
-            .map_err(|e| StatsError::DatabaseError(e.to_string()))?;
+        // Les résultats dépendent fortement de la taille : même tranche d'abord,
+        // toutes tailles confondues si l'historique de la tranche est trop mince
+        let size_range = get_size_range(query.original_size);
+        let row = match self.query_reduction(query, Some(&size_range))? {
+            Some(stats) if stats.count >= MIN_SIZE_RANGE_SAMPLES => Some(stats),
+            _ => self.query_reduction(query, None)?,
+        };
 
         match row {
-            Some((Some(avg_reduction), count, variance)) => {
+            Some(stats) => {
                 let confidence = crate::domain::compression::stats::calculate_confidence(
-                    count,
-                    variance.unwrap_or(0.0),
+                    stats.count,
+                    stats.variance,
                 );
+                // La moyenne pondérée reste toujours dans l'intervalle affiché
+                let (p25_percent, p75_percent) = if stats.count >= MIN_PERCENTILE_SAMPLES {
+                    (stats.p25.min(stats.mean), stats.p75.max(stats.mean))
+                } else {
+                    heuristic_interval(stats.mean, confidence)
+                };
                 Ok(EstimationResult {
-                    percent: avg_reduction,
-                    ratio: (100.0 - avg_reduction) / 100.0,
+                    percent: stats.mean,
+                    ratio: (100.0 - stats.mean) / 100.0,
                     confidence,
-                    sample_count: count,
+                    sample_count: stats.count,
+                    p25_percent,
+                    p75_percent,
                 })
             }
             _ => {
                 // No historical data, use heuristics
-                let fallback = crate::domain::compression::stats::estimate_compression(
-                    &query.input_format,
-                    &query.output_format,
-                    query.original_size,
-                    &CompressionSettings::new(
-                        query.quality_setting,
-                        OutputFormat::from_string(&query.output_format)
-                            .unwrap_or(OutputFormat::WebP),
-                    ),
-                );
-                Ok(fallback)
+                Ok(heuristic_estimation(query))
             }
         }
     }
 
+    fn get_prediction_accuracy(
+        &self,
+        input_format: &str,
+        output_format: &str,
+    ) -> StatsResult<PredictionAccuracy> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                r#"
+            SELECT predicted_percent, size_reduction_percent
+            FROM compression_stats
+            WHERE input_format = ?1
+            AND output_format = ?2
+            AND predicted_percent IS NOT NULL
+            "#,
+            )
+            .map_err(|e| StatsError::DatabaseError(e.to_string()))?;
+
+        let pairs = stmt
+            .query_map(rusqlite::params![input_format, output_format], |row| {
+                Ok((row.get::<_, f64>(0)?, row.get::<_, f64>(1)?))
+            })
+            .map_err(|e| StatsError::DatabaseError(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| StatsError::DatabaseError(e.to_string()))?;
+
+        Ok(PredictionAccuracy::from_pairs(
+            input_format.to_string(),
+            output_format.to_string(),
+            &pairs,
+        ))
+    }
+
     fn clear_all(&mut self) -> StatsResult<()> {
         self.conn
             .execute("DELETE FROM compression_stats", [])
@@ -199,6 +434,29 @@ impl StatsStore for SqliteStatsStore {
     }
 }
 
+/// Heuristic estimation used when no historical data is available
+fn heuristic_estimation(query: &EstimationQuery) -> EstimationResult {
+    estimate_compression(
+        &query.input_format,
+        &query.output_format,
+        query.original_size,
+        &CompressionSettings::new(
+            query.quality_setting,
+            OutputFormat::from_string(&query.output_format).unwrap_or(OutputFormat::WebP),
+        ),
+    )
+}
+
+/// Get an estimation from the store, falling back to heuristics if the store fails
+pub fn estimate_with_fallback<S: StatsStore + ?Sized>(
+    store: &S,
+    query: &EstimationQuery,
+) -> EstimationResult {
+    store
+        .get_estimation(query)
+        .unwrap_or_else(|_| heuristic_estimation(query))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -228,4 +486,354 @@ mod tests {
         let count = store.count_stats().unwrap();
         assert_eq!(count, 1);
     }
+
+    #[test]
+    fn test_prediction_accuracy() {
+        let mut store = SqliteStatsStore::in_memory().unwrap();
+        let settings = CompressionSettings::new(80, OutputFormat::WebP);
+
+        // Actual savings: 60% and 80%; predictions: 70% and 75%
+        for (compressed_size, predicted) in [(400_000, Some(70.0)), (200_000, Some(75.0))] {
+            let mut stat = stats::create_stat(
+                "png".to_string(),
+                "webp".to_string(),
+                1_000_000,
+                compressed_size,
+                &settings,
+            );
+            stat.predicted_percent = predicted;
+            store.save_stat(stat).unwrap();
+        }
+        // Rows without prediction are ignored
+        store
+            .save_stat(stats::create_stat(
+                "png".to_string(),
+                "webp".to_string(),
+                1_000_000,
+                900_000,
+                &settings,
+            ))
+            .unwrap();
+
+        let accuracy = store.get_prediction_accuracy("png", "webp").unwrap();
+        assert_eq!(accuracy.sample_count, 2);
+        assert!((accuracy.mean_absolute_error - 7.5).abs() < 1e-9);
+        assert!((accuracy.mean_error - 2.5).abs() < 1e-9);
+        assert_eq!(accuracy.bias, stats::PredictionBias::Overestimate);
+
+        let empty = store.get_prediction_accuracy("jpg", "webp").unwrap();
+        assert_eq!(empty.sample_count, 0);
+        assert_eq!(empty.bias, stats::PredictionBias::Neutral);
+    }
+
+    #[test]
+    fn test_estimation_prefers_same_size_range() {
+        let mut store = SqliteStatsStore::in_memory().unwrap();
+        let settings = CompressionSettings::new(80, OutputFormat::WebP);
+
+        // Petits fichiers : 20% de gain, gros fichiers : 80%
+        for _ in 0..5 {
+            for (original_size, compressed_size) in [(500_000, 400_000), (8_000_000, 1_600_000)] {
+                store
+                    .save_stat(stats::create_stat(
+                        "png".to_string(),
+                        "webp".to_string(),
+                        original_size,
+                        compressed_size,
+                        &settings,
+                    ))
+                    .unwrap();
+            }
+        }
+
+        let mut query = EstimationQuery {
+            input_format: "png".to_string(),
+            output_format: "webp".to_string(),
+            original_size: 10_000_000,
+            quality_setting: 80,
+            lossy_mode: true,
+        };
+        let large = store.get_estimation(&query).unwrap();
+        assert_eq!(large.sample_count, 5);
+        assert!((large.percent - 80.0).abs() < 1e-9);
+
+        query.original_size = 200_000;
+        let small = store.get_estimation(&query).unwrap();
+        assert!((small.percent - 20.0).abs() < 1e-9);
+
+        // Aucune ligne "medium" : repli sur toutes les tailles
+        query.original_size = 3_000_000;
+        let medium = store.get_estimation(&query).unwrap();
+        assert_eq!(medium.sample_count, 10);
+        assert!((medium.percent - 50.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_estimation_quartiles() {
+        let mut store = SqliteStatsStore::in_memory().unwrap();
+        let settings = CompressionSettings::new(80, OutputFormat::WebP);
+        for compressed_size in [
+            100_000, 150_000, 200_000, 250_000, 300_000, 350_000, 400_000,
+        ] {
+            store
+                .save_stat(stats::create_stat(
+                    "png".to_string(),
+                    "webp".to_string(),
+                    500_000,
+                    compressed_size,
+                    &settings,
+                ))
+                .unwrap();
+        }
+
+        let query = EstimationQuery {
+            input_format: "png".to_string(),
+            output_format: "webp".to_string(),
+            original_size: 500_000,
+            quality_setting: 80,
+            lossy_mode: true,
+        };
+        // Réductions de 20% à 80% : quartiles à 35% et 65%
+        let estimation = store.get_estimation(&query).unwrap();
+        assert!(estimation.p25_percent < estimation.percent);
+        assert!(estimation.percent < estimation.p75_percent);
+        assert!((estimation.p25_percent - 35.0).abs() < 1e-9);
+        assert!((estimation.p75_percent - 65.0).abs() < 1e-9);
+
+        // Historique vide : intervalle heuristique autour de l'estimation
+        let empty = SqliteStatsStore::in_memory().unwrap();
+        let heuristic = empty.get_estimation(&query).unwrap();
+        assert!(heuristic.p25_percent < heuristic.percent);
+        assert!(heuristic.percent < heuristic.p75_percent);
+    }
+
+    #[test]
+    fn test_estimation_weights_recent_stats() {
+        let mut store = SqliteStatsStore::in_memory().unwrap();
+        let settings = CompressionSettings::new(80, OutputFormat::WebP);
+
+        // Une ancienne mesure à 20% (version précédente), trois récentes à 60%
+        let mut old_stat = stats::create_stat(
+            "png".to_string(),
+            "webp".to_string(),
+            500_000,
+            400_000,
+            &settings,
+        );
+        old_stat.timestamp = (chrono::Utc::now() - chrono::Duration::days(365)).to_rfc3339();
+        store.save_stat(old_stat).unwrap();
+        for _ in 0..3 {
+            store
+                .save_stat(stats::create_stat(
+                    "png".to_string(),
+                    "webp".to_string(),
+                    500_000,
+                    200_000,
+                    &settings,
+                ))
+                .unwrap();
+        }
+
+        let query = EstimationQuery {
+            input_format: "png".to_string(),
+            output_format: "webp".to_string(),
+            original_size: 500_000,
+            quality_setting: 80,
+            lossy_mode: true,
+        };
+        // Moyenne simple : 50%
+        let estimation = store.get_estimation(&query).unwrap();
+        assert_eq!(estimation.sample_count, 4);
+        assert!(estimation.percent > 59.0 && estimation.percent < 60.0);
+
+        // Sans décroissance, toutes les lignes pèsent autant
+        let flat = store
+            .with_half_life_days(0.0)
+            .get_estimation(&query)
+            .unwrap();
+        assert!((flat.percent - 50.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_estimation_variance() {
+        let settings = CompressionSettings::new(80, OutputFormat::WebP);
+        let query = EstimationQuery {
+            input_format: "png".to_string(),
+            output_format: "webp".to_string(),
+            original_size: 500_000,
+            quality_setting: 80,
+            lossy_mode: true,
+        };
+        let store_with = |compressed_sizes: &[u64]| {
+            let mut store = SqliteStatsStore::in_memory()
+                .unwrap()
+                .with_half_life_days(0.0);
+            for &compressed_size in compressed_sizes {
+                store
+                    .save_stat(stats::create_stat(
+                        "png".to_string(),
+                        "webp".to_string(),
+                        500_000,
+                        compressed_size,
+                        &settings,
+                    ))
+                    .unwrap();
+            }
+            store
+        };
+
+        // Réductions de 40% et 60% : variance de population de 100
+        let spread = store_with(&[300_000, 200_000])
+            .get_estimation(&query)
+            .unwrap();
+        assert_eq!(spread.sample_count, 2);
+        assert!((spread.percent - 50.0).abs() < 1e-9);
+        assert_eq!(spread.confidence, stats::calculate_confidence(2, 100.0));
+
+        // Mesures identiques : pas de pénalité de variance
+        let steady = store_with(&[250_000, 250_000])
+            .get_estimation(&query)
+            .unwrap();
+        assert_eq!(steady.confidence, stats::calculate_confidence(2, 0.0));
+        assert!(steady.confidence > spread.confidence);
+    }
+
+    #[test]
+    fn test_average_compression_by_image_type() {
+        let mut store = SqliteStatsStore::in_memory().unwrap();
+        let settings = CompressionSettings::new(80, OutputFormat::WebP);
+        // (type, taille compressée sur 1000 octets)
+        let rows = [
+            (Some("photo"), 200),
+            (Some("photo"), 400),
+            (Some("logo"), 700),
+            (None, 900),
+        ];
+        for (image_type, compressed_size) in rows {
+            let mut stat = stats::create_stat(
+                "png".to_string(),
+                "webp".to_string(),
+                1000,
+                compressed_size,
+                &settings,
+            );
+            stat.image_type = image_type.map(str::to_string);
+            store.save_stat(stat).unwrap();
+        }
+
+        let grouped = store
+            .average_compression_by_image_type("png", "webp")
+            .unwrap();
+        let summary: Vec<(&str, f64, u32)> = grouped
+            .iter()
+            .map(|row| {
+                (
+                    row.image_type.as_str(),
+                    row.average_reduction_percent,
+                    row.sample_count,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("logo", 30.0, 1), ("photo", 70.0, 2), ("unknown", 10.0, 1)]
+        );
+
+        assert!(store
+            .average_compression_by_image_type("jpg", "webp")
+            .unwrap()
+            .is_empty());
+    }
+
+    /// Store holding one stat per age, in days
+    fn store_with_ages(ages_days: &[i64]) -> SqliteStatsStore {
+        let mut store = SqliteStatsStore::in_memory().unwrap();
+        let settings = CompressionSettings::new(80, OutputFormat::WebP);
+        for &age in ages_days {
+            let mut stat = stats::create_stat(
+                "png".to_string(),
+                "webp".to_string(),
+                500_000,
+                250_000,
+                &settings,
+            );
+            stat.timestamp = (chrono::Utc::now() - chrono::Duration::days(age)).to_rfc3339();
+            store.save_stat(stat).unwrap();
+        }
+        store
+    }
+
+    #[test]
+    fn test_prune_stats_by_count() {
+        let mut store = store_with_ages(&[1, 10, 2, 30, 5]);
+        assert_eq!(store.prune_stats(Some(3), None).unwrap(), 2);
+        assert_eq!(store.count_stats().unwrap(), 3);
+
+        // Les plus récentes sont conservées
+        let cutoff = chrono::Utc::now() - chrono::Duration::days(6);
+        assert_eq!(store.prune_stats(None, Some(cutoff)).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_prune_stats_by_age() {
+        let mut store = store_with_ages(&[1, 100, 2, 400, 5]);
+        let cutoff = chrono::Utc::now() - chrono::Duration::days(90);
+        assert_eq!(store.prune_stats(None, Some(cutoff)).unwrap(), 2);
+        assert_eq!(store.count_stats().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_prune_stats_by_age_and_count() {
+        let mut store = store_with_ages(&[1, 100, 2, 400, 5, 3]);
+        let cutoff = chrono::Utc::now() - chrono::Duration::days(90);
+        assert_eq!(store.prune_stats(Some(2), Some(cutoff)).unwrap(), 4);
+        assert_eq!(store.count_stats().unwrap(), 2);
+
+        assert_eq!(store.prune_stats(Some(10), None).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_batch_estimation_totals() {
+        let mut store = SqliteStatsStore::in_memory().unwrap();
+        let settings = CompressionSettings::new(80, OutputFormat::WebP);
+        store
+            .save_stat(stats::create_stat(
+                "png".to_string(),
+                "webp".to_string(),
+                1000000,
+                400000,
+                &settings,
+            ))
+            .unwrap();
+
+        // png -> webp has history, jpg -> webp falls back to heuristics
+        let files = [("a.png", "png", 2_000_000u64), ("b.jpg", "jpg", 500_000u64)];
+        let estimations: Vec<_> = files
+            .iter()
+            .map(|(path, input_format, size)| {
+                let query = EstimationQuery {
+                    input_format: input_format.to_string(),
+                    output_format: "webp".to_string(),
+                    original_size: *size,
+                    quality_setting: 80,
+                    lossy_mode: true,
+                };
+                stats::FileEstimation::new(
+                    path.to_string(),
+                    "webp".to_string(),
+                    *size,
+                    estimate_with_fallback(&store, &query),
+                )
+            })
+            .collect();
+
+        assert_eq!(estimations[0].estimated_size, 800_000);
+        assert_eq!(estimations[1].estimated_size, 375_000);
+
+        let expected_total: u64 = estimations.iter().map(|e| e.estimated_size).sum();
+        let batch = stats::BatchEstimation::from_files(estimations);
+        assert_eq!(batch.total_original_size, 2_500_000);
+        assert_eq!(batch.total_estimated_size, expected_total);
+        assert!(batch.estimated_savings_percent > 0.0);
+    }
 }
@@ -0,0 +1,108 @@
+// Periodic cleanup of the application's temporary files
+
+use super::operations::cleanup_temp_files_older_than;
+use crate::domain::shared::{temp_files_cleaned_up_event, AppConfig, EventBus};
+use std::path::PathBuf;
+use std::sync::{Arc, Condvar, Mutex, RwLock};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// Background thread running the temp cleanup every `cleanup_interval_hours`
+///
+/// The thread stops when `stop` is called or the scheduler is dropped.
+pub struct TempCleanupScheduler {
+    stop: Arc<(Mutex<bool>, Condvar)>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl TempCleanupScheduler {
+    /// Start the cleanup loop for `temp_dir`
+    pub fn start(
+        temp_dir: PathBuf,
+        config: Arc<RwLock<AppConfig>>,
+        event_bus: Arc<RwLock<EventBus>>,
+    ) -> Self {
+        let stop = Arc::new((Mutex::new(false), Condvar::new()));
+        let signal = Arc::clone(&stop);
+
+        let handle = std::thread::spawn(move || loop {
+            // Relu à chaque tour pour suivre les changements de config
+            let hours = config
+                .read()
+                .map(|config| config.cleanup_interval_hours)
+                .unwrap_or(0);
+            let interval = Duration::from_secs(u64::from(hours) * 3600);
+
+            if hours > 0 && temp_dir.is_dir() {
+                match cleanup_temp_files_older_than(&temp_dir, interval) {
+                    Ok((removed, _)) if removed.is_empty() => {}
+                    Ok((removed, freed)) => {
+                        let count = removed.len() as u32;
+                        log::info!("Cleaned {} stale temporary files", count);
+                        if let Ok(mut bus) = event_bus.write() {
+                            let _ = bus.publish(temp_files_cleaned_up_event(count, freed));
+                        }
+                    }
+                    Err(e) => log::warn!("Scheduled temp cleanup failed: {}", e),
+                }
+            }
+
+            // Désactivé (0) : on revérifie la config toutes les heures
+            let wait = if hours > 0 {
+                interval
+            } else {
+                Duration::from_secs(3600)
+            };
+            let (lock, condvar) = &*signal;
+            let stopped = lock.lock().unwrap_or_else(|e| e.into_inner());
+            let (stopped, _) = condvar
+                .wait_timeout_while(stopped, wait, |stopped| !*stopped)
+                .unwrap_or_else(|e| e.into_inner());
+            if *stopped {
+                break;
+            }
+        });
+
+        Self {
+            stop,
+            handle: Some(handle),
+        }
+    }
+
+    /// Signal the thread and wait for it to finish
+    pub fn stop(&mut self) {
+        let (lock, condvar) = &*self.stop;
+        *lock.lock().unwrap_or_else(|e| e.into_inner()) = true;
+        condvar.notify_all();
+
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for TempCleanupScheduler {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_scheduler_stops_promptly() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Arc::new(RwLock::new(AppConfig::default()));
+        let event_bus = Arc::new(RwLock::new(EventBus::new()));
+
+        let started = std::time::Instant::now();
+        let mut scheduler =
+            TempCleanupScheduler::start(temp_dir.path().to_path_buf(), config, event_bus);
+        scheduler.stop();
+
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
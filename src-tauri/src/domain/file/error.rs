@@ -15,6 +15,8 @@ pub enum FileError {
     UnsupportedFormat(String),
     /// Path traversal or security violation
     SecurityViolation(String),
+    /// Not enough disk space (needed bytes, available bytes)
+    InsufficientSpace(u64, u64),
 }
 
 impl fmt::Display for FileError {
@@ -26,6 +28,11 @@ impl fmt::Display for FileError {
             FileError::IoError(msg) => write!(f, "IO error: {}", msg),
             FileError::UnsupportedFormat(format) => write!(f, "Unsupported format: {}", format),
             FileError::SecurityViolation(msg) => write!(f, "Security violation: {}", msg),
+            FileError::InsufficientSpace(needed, available) => write!(
+                f,
+                "Insufficient disk space: {} bytes needed, {} available",
+                needed, available
+            ),
         }
     }
 }
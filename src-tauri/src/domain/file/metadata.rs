@@ -86,6 +86,16 @@ impl FileMetadata {
                 .unwrap_or(false)
     }
 
+    /// Smallest size a file of this format can have and still hold a complete header
+    pub fn min_header_size(&self) -> u64 {
+        match self.extension.as_deref() {
+            Some("png") => 33,         // Signature + IHDR chunk
+            Some("webp") => 20,        // RIFF header + first chunk header
+            Some("jpg" | "jpeg") => 4, // SOI + first marker
+            _ => 1,
+        }
+    }
+
     /// Get human-readable file size
     pub fn human_readable_size(&self) -> String {
         format_file_size(self.size)
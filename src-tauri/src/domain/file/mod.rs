@@ -3,20 +3,25 @@
 // This module provides file I/O operations and path utilities using pure functions
 // and data structures, following Rust idioms for safe file handling.
 
+pub mod cleanup;
 pub mod error;
 pub mod metadata;
 pub mod operations;
 pub mod path;
 
 // Re-export core types and functions for easy access
+pub use cleanup::TempCleanupScheduler;
 pub use error::{FileError, FileResult};
 pub use metadata::{format_file_size, get_file_extension, is_supported_image_file, FileMetadata};
-pub use path::{generate_output_path, PathUtils};
+pub use path::{generate_output_path, mirror_output_dir, PathUtils};
 
 // File operations - core I/O functions
 pub use operations::{
-    batch_copy_files, cleanup_temp_files, copy_file, create_backup, delete_file, file_exists,
-    get_file_info, move_file, read_file, write_file, FileOperation, OperationType,
+    atomic_write, batch_copy_files, batch_copy_files_unique, batch_move_files, check_free_space,
+    cleanup_temp_files, cleanup_temp_files_older_than, copy_file, copy_file_unique,
+    copy_file_unique_into, create_backup, delete_file, file_exists, find_duplicates, get_file_info,
+    is_app_temp_file, link_or_copy_file, move_file, read_file, write_file, FileOperation,
+    OperationType, TEMP_FILE_PREFIXES,
 };
 
 // Convenience functions for common operations
@@ -52,7 +57,13 @@ pub fn write_compressed_image<P: AsRef<std::path::Path>>(
 pub fn get_temp_file_path(prefix: &str, extension: &str) -> std::path::PathBuf {
     let temp_dir = std::env::temp_dir();
     let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S_%3f");
-    let filename = format!("{}_{}.{}", prefix, timestamp, extension);
+    let filename = format!(
+        "{}_{}_{}.{}",
+        prefix,
+        timestamp,
+        crate::domain::shared::unique_suffix(),
+        extension
+    );
     temp_dir.join(filename)
 }
 
@@ -67,6 +78,18 @@ pub fn validate_image_file<P: AsRef<std::path::Path>>(path: P) -> FileResult<Fil
         )));
     }
 
+    if metadata.size == 0 {
+        return Err(FileError::InvalidPath("empty file".to_string()));
+    }
+
+    // Un fichier plus court que l'en-tête de son format est forcément tronqué
+    if metadata.size < metadata.min_header_size() {
+        return Err(FileError::InvalidPath(format!(
+            "Truncated image: {} bytes is shorter than the {:?} header",
+            metadata.size, metadata.extension
+        )));
+    }
+
     // Check file size limits (e.g., max 100MB)
     const MAX_FILE_SIZE: u64 = 100 * 1024 * 1024;
     if metadata.size > MAX_FILE_SIZE {
@@ -136,6 +159,25 @@ mod integration_tests {
         assert!(output_path.ends_with(".webp"));
     }
 
+    #[test]
+    fn test_validate_rejects_empty_and_truncated_files() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let empty_path = temp_dir.path().join("empty.png");
+        fs::write(&empty_path, b"").unwrap();
+        assert!(matches!(
+            validate_image_file(&empty_path),
+            Err(FileError::InvalidPath(msg)) if msg == "empty file"
+        ));
+
+        let truncated_path = temp_dir.path().join("truncated.png");
+        fs::write(&truncated_path, [0x89, b'P', b'N']).unwrap();
+        assert!(matches!(
+            validate_image_file(&truncated_path),
+            Err(FileError::InvalidPath(_))
+        ));
+    }
+
     #[test]
     fn test_file_operations_workflow() {
         let temp_dir = TempDir::new().unwrap();
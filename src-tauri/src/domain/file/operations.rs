@@ -3,7 +3,11 @@ use crate::domain::file::{
     metadata::FileMetadata,
     path::PathUtils,
 };
+use crate::domain::shared::{content_sha256, unique_suffix};
+use std::collections::{hash_map::Entry, HashMap};
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime};
 
 /// File operation result
 #[derive(Debug, Clone)]
@@ -26,7 +30,7 @@ pub enum OperationType {
 
 /// Read file data
 pub fn read_file<P: AsRef<Path>>(path: P) -> FileResult<Vec<u8>> {
-    PathUtils::validate_safe_path(&path)?;
+    PathUtils::canonicalize_and_verify(&path)?;
 
     let data = std::fs::read(&path)?;
     Ok(data)
@@ -34,14 +38,18 @@ pub fn read_file<P: AsRef<Path>>(path: P) -> FileResult<Vec<u8>> {
 
 /// Write file data
 pub fn write_file<P: AsRef<Path>>(path: P, data: &[u8]) -> FileResult<FileOperation> {
-    PathUtils::validate_safe_path(&path)?;
+    PathUtils::canonicalize_and_verify(&path)?;
 
     // Ensure parent directory exists
     if let Some(parent) = path.as_ref().parent() {
         PathUtils::ensure_dir_exists(parent)?;
     }
 
-    std::fs::write(&path, data)?;
+    if let Some(parent) = path.as_ref().parent() {
+        check_free_space(parent, data.len() as u64)?;
+    }
+
+    atomic_write(&path, data)?;
 
     Ok(FileOperation {
         source_path: String::new(),
@@ -52,13 +60,71 @@ pub fn write_file<P: AsRef<Path>>(path: P, data: &[u8]) -> FileResult<FileOperat
     })
 }
 
+/// Write data to a sibling temp file then rename it into place, so an interrupted
+/// write never leaves a partial file at `path`
+pub fn atomic_write<P: AsRef<Path>>(path: P, data: &[u8]) -> FileResult<()> {
+    use std::io::Write;
+
+    let path = path.as_ref();
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| FileError::InvalidPath(path.to_string_lossy().to_string()))?;
+    let temp_path = path.with_file_name(format!(
+        ".{}.{}.tmp",
+        file_name.to_string_lossy(),
+        unique_suffix()
+    ));
+
+    let result = std::fs::File::create(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(data)?;
+            file.sync_all()
+        })
+        .and_then(|_| std::fs::rename(&temp_path, path));
+
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// Check that the filesystem holding `dir` has at least `needed_bytes` available
+pub fn check_free_space<P: AsRef<Path>>(dir: P, needed_bytes: u64) -> FileResult<()> {
+    let dir = dir.as_ref();
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+
+    // Le dossier de sortie peut ne pas encore exister : on mesure sur son plus proche ancêtre
+    let existing_dir = match dir.ancestors().find(|path| path.exists()) {
+        Some(existing_dir) => existing_dir,
+        None => return Ok(()),
+    };
+
+    // Espace inconnu (API indisponible) : on ne bloque pas l'écriture
+    match fs2::available_space(existing_dir) {
+        Ok(available_bytes) => ensure_free_space(needed_bytes, available_bytes),
+        Err(_) => Ok(()),
+    }
+}
+
+fn ensure_free_space(needed_bytes: u64, available_bytes: u64) -> FileResult<()> {
+    if needed_bytes > available_bytes {
+        return Err(FileError::InsufficientSpace(needed_bytes, available_bytes));
+    }
+    Ok(())
+}
+
 /// Copy file to new location
 pub fn copy_file<P: AsRef<Path>, Q: AsRef<Path>>(
     source: P,
     target: Q,
 ) -> FileResult<FileOperation> {
-    PathUtils::validate_safe_path(&source)?;
-    PathUtils::validate_safe_path(&target)?;
+    PathUtils::canonicalize_and_verify(&source)?;
+    PathUtils::canonicalize_and_verify(&target)?;
 
     // Ensure target directory exists
     if let Some(parent) = target.as_ref().parent() {
@@ -81,8 +147,8 @@ pub fn move_file<P: AsRef<Path>, Q: AsRef<Path>>(
     source: P,
     target: Q,
 ) -> FileResult<FileOperation> {
-    PathUtils::validate_safe_path(&source)?;
-    PathUtils::validate_safe_path(&target)?;
+    PathUtils::canonicalize_and_verify(&source)?;
+    PathUtils::canonicalize_and_verify(&target)?;
 
     // Get file size before moving
     let metadata = FileMetadata::from_path(&source)?;
@@ -93,7 +159,9 @@ pub fn move_file<P: AsRef<Path>, Q: AsRef<Path>>(
         PathUtils::ensure_dir_exists(parent)?;
     }
 
-    std::fs::rename(&source, &target)?;
+    rename_or_copy(source.as_ref(), target.as_ref(), |from, to| {
+        std::fs::rename(from, to)
+    })?;
 
     Ok(FileOperation {
         source_path: source.as_ref().to_string_lossy().to_string(),
@@ -104,6 +172,57 @@ pub fn move_file<P: AsRef<Path>, Q: AsRef<Path>>(
     })
 }
 
+/// Rename, falling back to copy-then-delete when source and target are on different filesystems
+fn rename_or_copy<F>(source: &Path, target: &Path, rename: F) -> FileResult<()>
+where
+    F: FnOnce(&Path, &Path) -> std::io::Result<()>,
+{
+    match rename(source, target) {
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+            std::fs::copy(source, target)?;
+            if let Err(e) = std::fs::remove_file(source) {
+                // Ne laisse pas deux copies derrière un déplacement échoué
+                let _ = std::fs::remove_file(target);
+                return Err(e.into());
+            }
+            Ok(())
+        }
+        result => Ok(result?),
+    }
+}
+
+/// Move every (source, target) pair, or none: on the first failure the files already
+/// moved are put back where they were before the error is returned
+pub fn batch_move_files<P: AsRef<Path>, Q: AsRef<Path>>(
+    pairs: &[(P, Q)],
+) -> FileResult<Vec<FileOperation>> {
+    let mut operations = Vec::with_capacity(pairs.len());
+
+    for (source, target) in pairs {
+        match move_file(source, target) {
+            Ok(operation) => operations.push(operation),
+            Err(e) => {
+                // Annulation dans l'ordre inverse, au mieux
+                for operation in operations.iter().rev() {
+                    if let Err(rollback_error) =
+                        move_file(&operation.target_path, &operation.source_path)
+                    {
+                        log::warn!(
+                            "Failed to move {} back to {}: {}",
+                            operation.target_path,
+                            operation.source_path,
+                            rollback_error
+                        );
+                    }
+                }
+                return Err(e);
+            }
+        }
+    }
+
+    Ok(operations)
+}
+
 /// Delete file
 pub fn delete_file<P: AsRef<Path>>(path: P) -> FileResult<FileOperation> {
     PathUtils::validate_safe_path(&path)?;
@@ -193,8 +312,209 @@ pub fn batch_copy_files<P: AsRef<Path>, Q: AsRef<Path>>(
         .collect()
 }
 
-/// Cleanup temporary files matching a pattern
-pub fn cleanup_temp_files<P: AsRef<Path>>(dir: P, pattern: &str) -> FileResult<Vec<String>> {
+/// Copy a file to `target`, or to the next free "name (n)" variant when it exists.
+/// The name is reserved with `create_new`, so concurrent copies never share a target.
+pub fn copy_file_unique<P: AsRef<Path>, Q: AsRef<Path>>(
+    source: P,
+    target: Q,
+) -> FileResult<FileOperation> {
+    PathUtils::canonicalize_and_verify(&source)?;
+    PathUtils::canonicalize_and_verify(&target)?;
+
+    if let Some(parent) = target.as_ref().parent() {
+        PathUtils::ensure_dir_exists(parent)?;
+    }
+
+    for _ in 0..MAX_UNIQUE_NAME_ATTEMPTS {
+        let candidate = PathUtils::make_unique_filename(&target);
+
+        // Un autre thread a pu réserver ce nom entre la vérification et la création
+        let mut file = match std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&candidate)
+        {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e.into()),
+        };
+
+        let copied =
+            std::fs::File::open(&source).and_then(|mut src| std::io::copy(&mut src, &mut file));
+        return match copied {
+            Ok(bytes_copied) => Ok(FileOperation {
+                source_path: source.as_ref().to_string_lossy().to_string(),
+                target_path: candidate.to_string_lossy().to_string(),
+                operation_type: OperationType::Copy,
+                bytes_processed: bytes_copied,
+                success: true,
+            }),
+            Err(e) => {
+                let _ = std::fs::remove_file(&candidate);
+                Err(e.into())
+            }
+        };
+    }
+
+    Err(FileError::IoError(format!(
+        "No free file name for {}",
+        target.as_ref().display()
+    )))
+}
+
+const MAX_UNIQUE_NAME_ATTEMPTS: usize = 32;
+
+/// Copy a file into `target_dir` under its own name, or the next free "name (n)" variant
+pub fn copy_file_unique_into<P: AsRef<Path>, Q: AsRef<Path>>(
+    source: P,
+    target_dir: Q,
+) -> FileResult<FileOperation> {
+    let file_name = source
+        .as_ref()
+        .file_name()
+        .ok_or_else(|| FileError::InvalidPath("Invalid file name".to_string()))?;
+    copy_file_unique(&source, target_dir.as_ref().join(file_name))
+}
+
+/// Copy files into `target_dir` on up to `max_concurrency` threads, renaming on collision.
+/// Results are returned in input order.
+pub fn batch_copy_files_unique<P: AsRef<Path> + Sync, Q: AsRef<Path> + Sync>(
+    files: &[P],
+    target_dir: Q,
+    max_concurrency: usize,
+) -> Vec<FileResult<FileOperation>> {
+    let next_index = AtomicUsize::new(0);
+    let copy_one = |file: &P| copy_file_unique_into(file, &target_dir);
+
+    let indexed_results: Vec<(usize, FileResult<FileOperation>)> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..max_concurrency.clamp(1, files.len().max(1)))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut results = Vec::new();
+                    loop {
+                        let index = next_index.fetch_add(1, Ordering::Relaxed);
+                        match files.get(index) {
+                            Some(file) => results.push((index, copy_one(file))),
+                            None => return results,
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap_or_default())
+            .collect()
+    });
+
+    let mut results: Vec<Option<FileResult<FileOperation>>> = files.iter().map(|_| None).collect();
+    for (index, result) in indexed_results {
+        results[index] = Some(result);
+    }
+
+    // Un worker paniqué perd ses résultats : ses fichiers sont signalés en échec
+    results
+        .into_iter()
+        .zip(files)
+        .map(|(result, file)| {
+            result.unwrap_or_else(|| {
+                Err(FileError::IoError(format!(
+                    "Copy of {} was interrupted",
+                    file.as_ref().display()
+                )))
+            })
+        })
+        .collect()
+}
+
+/// Hard-link a file to a new location, copying it when linking is not possible
+pub fn link_or_copy_file<P: AsRef<Path>, Q: AsRef<Path>>(
+    source: P,
+    target: Q,
+) -> FileResult<FileOperation> {
+    PathUtils::canonicalize_and_verify(&source)?;
+    PathUtils::canonicalize_and_verify(&target)?;
+
+    if let Some(parent) = target.as_ref().parent() {
+        PathUtils::ensure_dir_exists(parent)?;
+    }
+
+    // Lien impossible (autre volume, cible existante...) : copie classique
+    if std::fs::hard_link(&source, &target).is_err() {
+        return copy_file(source, target);
+    }
+
+    Ok(FileOperation {
+        source_path: source.as_ref().to_string_lossy().to_string(),
+        target_path: target.as_ref().to_string_lossy().to_string(),
+        operation_type: OperationType::Copy,
+        bytes_processed: FileMetadata::from_path(&target)?.size,
+        success: true,
+    })
+}
+
+/// Group byte-identical files by content hash, returning clusters of two or more
+pub fn find_duplicates<P: AsRef<Path>>(paths: &[P]) -> Vec<Vec<String>> {
+    let mut clusters: Vec<Vec<String>> = Vec::new();
+    let mut cluster_by_hash: HashMap<String, usize> = HashMap::new();
+
+    for path in paths {
+        // Fichiers illisibles : ignorés, ils échoueront plus tard avec une erreur claire
+        let data = match read_file(path) {
+            Ok(data) => data,
+            Err(_) => continue,
+        };
+        let path = path.as_ref().to_string_lossy().to_string();
+
+        match cluster_by_hash.entry(content_sha256(&data)) {
+            Entry::Occupied(entry) => clusters[*entry.get()].push(path),
+            Entry::Vacant(entry) => {
+                entry.insert(clusters.len());
+                clusters.push(vec![path]);
+            }
+        }
+    }
+
+    clusters.retain(|cluster| cluster.len() > 1);
+    clusters
+}
+
+/// Prefixes of the temporary files created by Plume
+pub const TEMP_FILE_PREFIXES: [&str; 2] = ["dropped", "plume"];
+
+/// Whether `name` looks like a temp file we generated: `{prefix}_{YYYYMMDD}_{HHMMSS}...`
+pub fn is_app_temp_file(name: &str, prefix: &str, extension: Option<&str>) -> bool {
+    let Some(rest) = name
+        .strip_prefix(prefix)
+        .and_then(|rest| rest.strip_prefix('_'))
+    else {
+        return false;
+    };
+
+    // Horodatage de generate_temp_filename / get_temp_file_path
+    let stamp = rest.as_bytes();
+    let has_timestamp = stamp.len() > 15
+        && stamp[..8].iter().all(u8::is_ascii_digit)
+        && stamp[8] == b'_'
+        && stamp[9..15].iter().all(u8::is_ascii_digit);
+
+    let has_extension = extension.is_none_or(|ext| {
+        Path::new(name)
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| e.eq_ignore_ascii_case(ext.trim_start_matches('.')))
+    });
+
+    has_timestamp && has_extension
+}
+
+/// Cleanup temporary files created with `prefix`, optionally restricted to one extension
+pub fn cleanup_temp_files<P: AsRef<Path>>(
+    dir: P,
+    prefix: &str,
+    extension: Option<&str>,
+) -> FileResult<Vec<String>> {
     let mut cleaned_files = Vec::new();
 
     for entry in std::fs::read_dir(dir)? {
@@ -203,7 +523,7 @@ pub fn cleanup_temp_files<P: AsRef<Path>>(dir: P, pattern: &str) -> FileResult<V
 
         if path.is_file() {
             if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                if name.contains(pattern) {
+                if is_app_temp_file(name, prefix, extension) {
                     delete_file(&path)?;
                     cleaned_files.push(path.to_string_lossy().to_string());
                 }
@@ -214,12 +534,154 @@ pub fn cleanup_temp_files<P: AsRef<Path>>(dir: P, pattern: &str) -> FileResult<V
     Ok(cleaned_files)
 }
 
+/// Cleanup Plume temp files not modified for `max_age`, returning the removed paths and bytes freed
+pub fn cleanup_temp_files_older_than<P: AsRef<Path>>(
+    dir: P,
+    max_age: Duration,
+) -> FileResult<(Vec<String>, u64)> {
+    let cutoff = SystemTime::now()
+        .checked_sub(max_age)
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+    let mut cleaned_files = Vec::new();
+    let mut bytes_freed = 0;
+
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let metadata = entry.metadata()?;
+        if !metadata.is_file() {
+            continue;
+        }
+
+        // Seuls nos fichiers : la base de stats vit dans le même dossier
+        let is_ours = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| {
+                TEMP_FILE_PREFIXES
+                    .iter()
+                    .any(|prefix| is_app_temp_file(name, prefix, None))
+            });
+        let is_stale = metadata.modified().is_ok_and(|modified| modified <= cutoff);
+
+        if is_ours && is_stale {
+            delete_file(&path)?;
+            bytes_freed += metadata.len();
+            cleaned_files.push(path.to_string_lossy().to_string());
+        }
+    }
+
+    Ok((cleaned_files, bytes_freed))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::fs;
     use tempfile::TempDir;
 
+    #[test]
+    fn test_move_falls_back_across_devices() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("photo.webp");
+        let target = temp_dir.path().join("photo_moved.webp");
+        fs::write(&source, b"compressed image").unwrap();
+
+        // Simule un renommage entre deux systèmes de fichiers (EXDEV)
+        rename_or_copy(&source, &target, |_, _| {
+            Err(std::io::Error::from(std::io::ErrorKind::CrossesDevices))
+        })
+        .unwrap();
+
+        assert!(!source.exists());
+        assert_eq!(fs::read(&target).unwrap(), b"compressed image");
+
+        let other = rename_or_copy(&target, &source, |_, _| {
+            Err(std::io::Error::from(std::io::ErrorKind::PermissionDenied))
+        });
+        assert!(other.is_err());
+        assert!(target.exists());
+    }
+
+    #[test]
+    fn test_batch_move_rolls_back_on_failure() {
+        let temp_dir = TempDir::new().unwrap();
+        let sources: Vec<_> = (0..3)
+            .map(|i| {
+                let source = temp_dir.path().join(format!("output_{}.webp", i));
+                fs::write(&source, format!("image {}", i)).unwrap();
+                source
+            })
+            .collect();
+
+        // La troisième cible passe par un fichier au lieu d'un dossier
+        let blocker = temp_dir.path().join("blocker");
+        fs::write(&blocker, b"not a directory").unwrap();
+        let pairs = vec![
+            (
+                sources[0].clone(),
+                temp_dir.path().join("moved/output_0.webp"),
+            ),
+            (
+                sources[1].clone(),
+                temp_dir.path().join("moved/output_1.webp"),
+            ),
+            (sources[2].clone(), blocker.join("output_2.webp")),
+        ];
+
+        assert!(batch_move_files(&pairs).is_err());
+        for (i, source) in sources.iter().enumerate() {
+            assert_eq!(fs::read_to_string(source).unwrap(), format!("image {}", i));
+        }
+        assert!(!pairs[0].1.exists());
+        assert!(!pairs[1].1.exists());
+
+        let pairs = &pairs[..2];
+        assert_eq!(batch_move_files(pairs).unwrap().len(), 2);
+        assert!(pairs
+            .iter()
+            .all(|(source, target)| !source.exists() && target.exists()));
+    }
+
+    #[test]
+    fn test_insufficient_free_space() {
+        assert!(matches!(
+            ensure_free_space(10 * 1024 * 1024, 1024),
+            Err(FileError::InsufficientSpace(10485760, 1024))
+        ));
+        assert!(ensure_free_space(1024, 1024).is_ok());
+
+        let temp_dir = TempDir::new().unwrap();
+        assert!(check_free_space(temp_dir.path(), 1).is_ok());
+        assert!(matches!(
+            check_free_space(temp_dir.path().join("not/created/yet"), u64::MAX),
+            Err(FileError::InsufficientSpace(u64::MAX, _))
+        ));
+    }
+
+    #[test]
+    fn test_atomic_write_failure_leaves_no_partial_file() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let target = temp_dir.path().join("output.webp");
+        fs::write(&target, b"previous valid output").unwrap();
+        atomic_write(&target, b"new output").unwrap();
+        assert_eq!(fs::read(&target).unwrap(), b"new output");
+
+        // Un dossier à la place de la cible fait échouer le renommage
+        let blocked = temp_dir.path().join("blocked.webp");
+        fs::create_dir(&blocked).unwrap();
+        assert!(atomic_write(&blocked, b"partial data").is_err());
+
+        assert!(blocked.is_dir());
+        let mut names: Vec<_> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["blocked.webp", "output.webp"]);
+    }
+
     #[test]
     fn test_read_write_file() {
         let temp_dir = TempDir::new().unwrap();
@@ -284,6 +746,84 @@ mod tests {
         assert!(!file_exists(&test_path));
     }
 
+    #[test]
+    fn test_batch_copy_files_unique_concurrently() {
+        let source_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+
+        // Même nom de fichier partout : chaque copie doit obtenir son propre nom
+        let sources: Vec<_> = (0..40)
+            .map(|i| {
+                let dir = source_dir.path().join(format!("dir{}", i));
+                fs::create_dir(&dir).unwrap();
+                let path = dir.join("image.png");
+                fs::write(&path, format!("content {}", i)).unwrap();
+                path
+            })
+            .collect();
+
+        let results = batch_copy_files_unique(&sources, target_dir.path(), 8);
+        assert_eq!(results.len(), sources.len());
+
+        let mut targets = std::collections::HashSet::new();
+        for (i, result) in results.into_iter().enumerate() {
+            let operation = result.unwrap();
+            assert_eq!(
+                fs::read_to_string(&operation.target_path).unwrap(),
+                format!("content {}", i)
+            );
+            assert!(targets.insert(operation.target_path));
+        }
+        assert_eq!(fs::read_dir(target_dir.path()).unwrap().count(), 40);
+    }
+
+    #[test]
+    fn test_copy_file_unique_into_names() {
+        let source_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+        let source = source_dir.path().join("photo.png");
+        fs::write(&source, b"png bytes").unwrap();
+
+        let first = copy_file_unique_into(&source, target_dir.path()).unwrap();
+        let second = copy_file_unique_into(&source, target_dir.path()).unwrap();
+
+        assert_eq!(
+            Path::new(&first.target_path),
+            target_dir.path().join("photo.png")
+        );
+        let second = Path::new(&second.target_path);
+        assert_eq!(second, target_dir.path().join("photo (1).png"));
+        let name = second.file_name().unwrap().to_str().unwrap();
+        assert!(!name.contains(". ") && !name.ends_with(' '));
+        assert_eq!(second.extension().unwrap(), "png");
+        assert_eq!(fs::read(second).unwrap(), b"png bytes");
+    }
+
+    #[test]
+    fn test_find_duplicates() {
+        let temp_dir = TempDir::new().unwrap();
+        let first = temp_dir.path().join("first.png");
+        let copy = temp_dir.path().join("copy.png");
+        let unique = temp_dir.path().join("unique.png");
+
+        fs::write(&first, b"same bytes").unwrap();
+        fs::write(&copy, b"same bytes").unwrap();
+        fs::write(&unique, b"other bytes").unwrap();
+
+        let clusters = find_duplicates(&[&first, &unique, &copy]);
+        assert_eq!(
+            clusters,
+            vec![vec![
+                first.to_string_lossy().to_string(),
+                copy.to_string_lossy().to_string()
+            ]]
+        );
+
+        let linked = temp_dir.path().join("out/linked.png");
+        assert!(link_or_copy_file(&first, &linked).unwrap().success);
+        assert_eq!(read_file(&linked).unwrap(), b"same bytes");
+    }
+
     #[test]
     fn test_create_backup() {
         let temp_dir = TempDir::new().unwrap();
@@ -299,4 +839,72 @@ mod tests {
         let backup_data = read_file(&backup_path).unwrap();
         assert_eq!(backup_data, test_data);
     }
+
+    #[test]
+    fn test_cleanup_temp_files_ignores_decoys() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+
+        let genuine = dir.join("plume_20240101_120000_123_7f3a.webp");
+        let genuine_png = dir.join("plume_20240101_120000_456_9c1e.png");
+        let decoys = [
+            "my-plume-notes.png",
+            "plume_notes.webp",
+            "notes_plume_20240101_120000_123.webp",
+            "plume-20240101_120000_123.webp",
+        ];
+        fs::write(&genuine, b"temp").unwrap();
+        fs::write(&genuine_png, b"temp").unwrap();
+        for decoy in decoys {
+            fs::write(dir.join(decoy), b"user file").unwrap();
+        }
+
+        let cleaned = cleanup_temp_files(dir, "plume", Some("webp")).unwrap();
+        assert_eq!(cleaned, vec![genuine.to_string_lossy().to_string()]);
+        assert!(genuine_png.exists());
+
+        let cleaned = cleanup_temp_files(dir, "plume", None).unwrap();
+        assert_eq!(cleaned.len(), 1);
+        assert!(!genuine_png.exists());
+        for decoy in decoys {
+            assert!(dir.join(decoy).exists(), "{} was deleted", decoy);
+        }
+    }
+
+    #[test]
+    fn test_cleanup_temp_files_older_than_respects_cutoff() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        let day = Duration::from_secs(24 * 3600);
+        let backdate = |path: &Path, age: Duration| {
+            let file = fs::File::options().write(true).open(path).unwrap();
+            file.set_modified(SystemTime::now() - age).unwrap();
+        };
+
+        let old = dir.join("dropped_20240101_120000_000_a1b2.png");
+        let older = dir.join("plume_20231201_090000_000_e5f6.webp");
+        let recent = dir.join("plume_20240102_080000_000_c3d4.webp");
+        let stats_db = dir.join("compression_stats.db");
+        fs::write(&old, vec![0u8; 1500]).unwrap();
+        fs::write(&older, vec![0u8; 500]).unwrap();
+        fs::write(&recent, b"recent").unwrap();
+        fs::write(&stats_db, b"sqlite").unwrap();
+        backdate(&old, day * 2);
+        backdate(&older, day * 40);
+        backdate(&recent, Duration::from_secs(3600));
+        backdate(&stats_db, day * 30);
+
+        let (mut removed, bytes_freed) = cleanup_temp_files_older_than(dir, day).unwrap();
+        removed.sort();
+
+        let mut expected = vec![
+            old.to_string_lossy().to_string(),
+            older.to_string_lossy().to_string(),
+        ];
+        expected.sort();
+        assert_eq!(removed, expected);
+        assert_eq!(bytes_freed, 2000);
+        assert!(recent.exists());
+        assert!(stats_db.exists());
+    }
 }
@@ -1,12 +1,32 @@
 use crate::domain::file::error::{FileError, FileResult};
+use crate::domain::shared::SecurityConfig;
 use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, RwLock};
+
+// Configuration de sécurité active, appliquée au démarrage et à chaque changement de config
+static SECURITY_CONFIG: LazyLock<RwLock<SecurityConfig>> =
+    LazyLock::new(|| RwLock::new(SecurityConfig::default()));
 
 /// Path utilities and validation
 pub struct PathUtils;
 
 impl PathUtils {
+    /// Use these allowed/blocked roots for every subsequent path validation
+    pub fn apply_security_config(config: &SecurityConfig) {
+        *SECURITY_CONFIG.write().unwrap() = config.clone();
+    }
+
     /// Validate that a path is safe (no traversal attacks)
     pub fn validate_safe_path<P: AsRef<Path>>(path: P) -> FileResult<()> {
+        let config = SECURITY_CONFIG.read().unwrap();
+        Self::validate_safe_path_with(path, &config)
+    }
+
+    /// Validate a path against the allowed and blocked roots of `config`
+    pub fn validate_safe_path_with<P: AsRef<Path>>(
+        path: P,
+        config: &SecurityConfig,
+    ) -> FileResult<()> {
         let path_ref = path.as_ref();
 
         // Check for path traversal attempts
@@ -18,21 +38,25 @@ impl PathUtils {
 
         // Check for absolute paths outside allowed directories
         if path_ref.is_absolute() {
-            // Allow temp directory paths for file processing
-            let temp_dir = std::env::temp_dir();
-            let downloads_dir = dirs::download_dir();
-            let home_dir = dirs::home_dir();
-            let desktop_dir = dirs::desktop_dir();
-            let pictures_dir = dirs::picture_dir();
-
-            let is_temp = path_ref.starts_with(&temp_dir);
-            let is_downloads = downloads_dir.is_some_and(|d| path_ref.starts_with(d));
-            let is_home = home_dir.is_some_and(|d| path_ref.starts_with(d));
-            let is_desktop = desktop_dir.is_some_and(|d| path_ref.starts_with(d));
-            let is_pictures = pictures_dir.is_some_and(|d| path_ref.starts_with(d));
-
-            // Allow access to user directories for reading image files
-            if !is_temp && !is_downloads && !is_home && !is_desktop && !is_pictures {
+            let is_blocked = config
+                .blocked_roots()
+                .iter()
+                .any(|root| path_ref.starts_with(root));
+            if is_blocked {
+                return Err(FileError::SecurityViolation(format!(
+                    "Access to {} is blocked",
+                    path_ref.display()
+                )));
+            }
+
+            // Le dossier temporaire reste autorisé pour le traitement des fichiers
+            let is_temp = path_ref.starts_with(std::env::temp_dir());
+            let is_allowed = config
+                .allowed_roots()
+                .iter()
+                .any(|root| path_ref.starts_with(root));
+
+            if !is_temp && !is_allowed {
                 return Err(FileError::SecurityViolation(
                     "Absolute paths not allowed".to_string(),
                 ));
@@ -42,6 +66,37 @@ impl PathUtils {
         Ok(())
     }
 
+    /// Resolve symlinks in `path` and re-check its real location against the roots
+    pub fn canonicalize_and_verify<P: AsRef<Path>>(path: P) -> FileResult<PathBuf> {
+        let config = SECURITY_CONFIG.read().unwrap();
+        Self::canonicalize_and_verify_with(path, &config)
+    }
+
+    /// Like `validate_safe_path_with`, then rejects symlinks escaping the allowed roots
+    ///
+    /// Only the existing part of the path is resolved, so a target about to be
+    /// created is still checked through its parent directories.
+    pub fn canonicalize_and_verify_with<P: AsRef<Path>>(
+        path: P,
+        config: &SecurityConfig,
+    ) -> FileResult<PathBuf> {
+        let path_ref = path.as_ref();
+        Self::validate_safe_path_with(path_ref, config)?;
+
+        let resolved = resolve_real_path(path_ref)?;
+
+        // Un chemin relatif reste accepté tant qu'il ne sort pas du dossier courant
+        if path_ref.is_relative() {
+            let cwd = std::env::current_dir().and_then(std::fs::canonicalize);
+            if cwd.is_ok_and(|cwd| resolved.starts_with(cwd)) {
+                return Ok(resolved);
+            }
+        }
+
+        check_resolved_roots(&resolved, config)?;
+        Ok(resolved)
+    }
+
     /// Get the parent directory of a path
     pub fn get_parent_dir<P: AsRef<Path>>(path: P) -> FileResult<PathBuf> {
         path.as_ref()
@@ -168,6 +223,92 @@ pub fn generate_output_path<P: AsRef<Path>>(
     Ok(PathUtils::make_unique_filename(output_path))
 }
 
+/// Re-root the input's parent directory under the output base, creating it if needed
+pub fn mirror_output_dir<P: AsRef<Path>, B: AsRef<Path>, O: AsRef<Path>>(
+    input_path: P,
+    input_base_dir: B,
+    output_base_dir: O,
+) -> FileResult<PathBuf> {
+    let relative_parent = input_path
+        .as_ref()
+        .parent()
+        .and_then(|parent| parent.strip_prefix(input_base_dir.as_ref()).ok())
+        .ok_or_else(|| {
+            FileError::InvalidPath(format!(
+                "{} is not inside {}",
+                input_path.as_ref().display(),
+                input_base_dir.as_ref().display()
+            ))
+        })?;
+
+    let output_dir = output_base_dir.as_ref().join(relative_parent);
+    PathUtils::ensure_dir_exists(&output_dir)?;
+    Ok(output_dir)
+}
+
+/// Canonicalize the longest existing prefix of `path` and append the rest
+fn resolve_real_path(path: &Path) -> FileResult<PathBuf> {
+    let mut existing = path;
+    let mut missing = Vec::new();
+
+    loop {
+        let candidate = if existing.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            existing
+        };
+
+        match std::fs::canonicalize(candidate) {
+            Ok(real) => {
+                return Ok(missing
+                    .iter()
+                    .rev()
+                    .fold(real, |real, name| real.join(name)))
+            }
+            // Un lien cassé pourrait être créé vers n'importe quelle cible
+            Err(_) if std::fs::symlink_metadata(candidate).is_ok() => {
+                return Err(FileError::SecurityViolation(format!(
+                    "Cannot resolve symlink {}",
+                    candidate.display()
+                )));
+            }
+            Err(e) => match (existing.parent(), existing.file_name()) {
+                (Some(parent), Some(name)) => {
+                    missing.push(name);
+                    existing = parent;
+                }
+                _ => return Err(e.into()),
+            },
+        }
+    }
+}
+
+/// Check a resolved path against the roots, themselves compared as configured and resolved
+fn check_resolved_roots(resolved: &Path, config: &SecurityConfig) -> FileResult<()> {
+    let under_any = |roots: &[PathBuf]| {
+        roots.iter().any(|root| {
+            resolved.starts_with(root)
+                || std::fs::canonicalize(root).is_ok_and(|real| resolved.starts_with(real))
+        })
+    };
+
+    if under_any(&config.blocked_roots()) {
+        return Err(FileError::SecurityViolation(format!(
+            "{} resolves into a blocked directory",
+            resolved.display()
+        )));
+    }
+
+    if !under_any(&[std::env::temp_dir()]) && !under_any(&config.allowed_roots()) {
+        return Err(FileError::SecurityViolation(format!(
+            "{} resolves outside the allowed directories",
+            resolved.display()
+        )));
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -181,6 +322,64 @@ mod tests {
         assert!(PathUtils::validate_safe_path("/absolute/path.jpg").is_err());
     }
 
+    #[test]
+    fn test_validate_safe_path_with_custom_root() {
+        let mut config = SecurityConfig::default();
+        let external = Path::new("/media/photos");
+        assert!(PathUtils::validate_safe_path_with(external.join("a.jpg"), &config).is_err());
+
+        config.allowed_paths.push("/media/photos".to_string());
+        assert!(PathUtils::validate_safe_path_with(external.join("2024/a.jpg"), &config).is_ok());
+        assert!(PathUtils::validate_safe_path_with("/media/other/a.jpg", &config).is_err());
+        assert!(PathUtils::validate_safe_path_with("/media/photos-old/a.jpg", &config).is_err());
+
+        // Un dossier bloqué l'emporte sur un dossier autorisé
+        config
+            .blocked_paths
+            .push("/media/photos/private".to_string());
+        assert!(
+            PathUtils::validate_safe_path_with("/media/photos/private/a.jpg", &config).is_err()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_canonicalize_rejects_symlink_escape() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = SecurityConfig::default();
+
+        let inside = temp_dir.path().join("photo.jpg");
+        fs::write(&inside, b"jpeg").unwrap();
+        assert!(PathUtils::canonicalize_and_verify_with(&inside, &config).is_ok());
+        assert!(
+            PathUtils::canonicalize_and_verify_with(temp_dir.path().join("new.jpg"), &config)
+                .is_ok()
+        );
+
+        // Le lien est dans le dossier temporaire autorisé, sa cible non
+        let link = temp_dir.path().join("escape");
+        std::os::unix::fs::symlink("/etc", &link).unwrap();
+        assert!(PathUtils::validate_safe_path_with(link.join("hosts"), &config).is_ok());
+        assert!(PathUtils::canonicalize_and_verify_with(link.join("hosts"), &config).is_err());
+        assert!(PathUtils::canonicalize_and_verify_with(link.join("new.jpg"), &config).is_err());
+
+        let dangling = temp_dir.path().join("dangling.jpg");
+        std::os::unix::fs::symlink("/etc/plume-missing.jpg", &dangling).unwrap();
+        assert!(PathUtils::canonicalize_and_verify_with(&dangling, &config).is_err());
+    }
+
+    #[test]
+    fn test_validate_safe_path_expands_tilde() {
+        let Some(home) = dirs::home_dir() else {
+            return;
+        };
+        let config = SecurityConfig {
+            allowed_paths: vec!["~/Photos".to_string()],
+            ..SecurityConfig::default()
+        };
+        assert!(PathUtils::validate_safe_path_with(home.join("Photos/a.jpg"), &config).is_ok());
+    }
+
     #[test]
     fn test_change_extension() {
         assert_eq!(
@@ -218,6 +417,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_mirror_output_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let input_base = temp_dir.path().join("a");
+        let output_base = temp_dir.path().join("out");
+
+        let top = mirror_output_dir(input_base.join("top.png"), &input_base, &output_base).unwrap();
+        assert_eq!(top, output_base);
+        assert!(top.is_dir());
+
+        let nested =
+            mirror_output_dir(input_base.join("b/nested.png"), &input_base, &output_base).unwrap();
+        assert_eq!(nested, output_base.join("b"));
+        assert!(nested.is_dir());
+
+        let outside = temp_dir.path().join("elsewhere/file.png");
+        assert!(mirror_output_dir(outside, &input_base, &output_base).is_err());
+    }
+
     #[test]
     fn test_generate_output_path() {
         let input = "input/test.png";
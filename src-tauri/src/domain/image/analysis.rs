@@ -1,10 +1,13 @@
 use crate::domain::image::{
-    error::ImageResult,
+    error::{ImageError, ImageResult},
     metadata::{ColorSpace, ImageMetadata, ImageType},
+    processing::decode_image,
 };
+use serde::{Deserialize, Serialize};
 
 /// Image quality assessment result
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct QualityAssessment {
     pub sharpness_score: f64, // 0.0 - 1.0
     pub noise_level: f64,     // 0.0 - 1.0
@@ -14,7 +17,8 @@ pub struct QualityAssessment {
 }
 
 /// Color distribution analysis
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ColorAnalysis {
     pub dominant_colors: Vec<(u8, u8, u8)>, // RGB values of most frequent colors
     pub unique_color_estimate: u32,
@@ -24,7 +28,8 @@ pub struct ColorAnalysis {
 }
 
 /// Compression potential analysis
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct CompressionPotential {
     pub lossy_suitable: bool,
     pub estimated_savings_percent: f64,
@@ -32,19 +37,95 @@ pub struct CompressionPotential {
     pub risk_level: RiskLevel,
 }
 
+/// Per-channel 256-bucket histograms of the decoded pixels
 #[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    pub red: [u32; 256],
+    pub green: [u32; 256],
+    pub blue: [u32; 256],
+    pub luminance: [u32; 256],
+    pub pixel_count: u64,
+}
+
+impl Histogram {
+    /// Build the histograms from RGBA pixels
+    pub fn from_rgba(img: &image::RgbaImage) -> Self {
+        let mut histogram = Histogram {
+            red: [0; 256],
+            green: [0; 256],
+            blue: [0; 256],
+            luminance: [0; 256],
+            pixel_count: 0,
+        };
+
+        for pixel in img.pixels() {
+            let [r, g, b, _] = pixel.0;
+            histogram.red[r as usize] += 1;
+            histogram.green[g as usize] += 1;
+            histogram.blue[b as usize] += 1;
+
+            let luma = 0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64;
+            histogram.luminance[luma.round().min(255.0) as usize] += 1;
+            histogram.pixel_count += 1;
+        }
+
+        histogram
+    }
+
+    /// Smallest value at or below which `fraction` (0.0 - 1.0) of the pixels fall
+    pub fn percentile(buckets: &[u32; 256], fraction: f64) -> u8 {
+        let total: u64 = buckets.iter().map(|&count| count as u64).sum();
+        if total == 0 {
+            return 0;
+        }
+
+        let threshold = (total as f64 * fraction.clamp(0.0, 1.0)).ceil().max(1.0) as u64;
+        let mut cumulative = 0u64;
+        for (value, &count) in buckets.iter().enumerate() {
+            cumulative += count as u64;
+            if cumulative >= threshold {
+                return value as u8;
+            }
+        }
+        255
+    }
+
+    /// Contrast as the 5th-95th percentile luminance spread (0.0 - 1.0)
+    pub fn contrast_spread(&self) -> f64 {
+        let low = Self::percentile(&self.luminance, 0.05);
+        let high = Self::percentile(&self.luminance, 0.95);
+        high.saturating_sub(low) as f64 / 255.0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub enum RiskLevel {
     Low,    // Safe to compress aggressively
     Medium, // Moderate compression recommended
     High,   // Conservative compression only
 }
 
-/// Analyze image quality metrics
-pub fn assess_image_quality(metadata: &ImageMetadata) -> ImageResult<QualityAssessment> {
+/// Compute per-channel and luminance histograms from the decoded pixels
+pub fn compute_histogram(data: &[u8], metadata: &ImageMetadata) -> ImageResult<Histogram> {
+    let img = decode_image(data, &metadata.format)?;
+    Ok(Histogram::from_rgba(&img.to_rgba8()))
+}
+
+/// Analyze image quality metrics, using the luminance histogram for contrast when available
+pub fn assess_image_quality(
+    metadata: &ImageMetadata,
+    histogram: Option<&Histogram>,
+) -> ImageResult<QualityAssessment> {
     // Simplified quality assessment based on metadata and basic analysis
     // Estimate sharpness based on compression ratio and format
-    let sharpness_score = match metadata.format.to_lowercase().as_str() {
-        "jpg" | "jpeg" => {
+    let sharpness_score = match (
+        metadata.format.to_lowercase().as_str(),
+        metadata.quality_estimate,
+    ) {
+        // Qualité réelle lue dans les tables de quantification
+        ("jpg" | "jpeg", Some(quality)) => quality as f64 / 100.0,
+        ("jpg" | "jpeg", None) => {
             let compression_ratio = metadata.compression_ratio();
             if compression_ratio > 0.1 {
                 0.3 // Heavily compressed JPEG
@@ -54,9 +135,9 @@ pub fn assess_image_quality(metadata: &ImageMetadata) -> ImageResult<QualityAsse
                 0.9 // High quality JPEG
             }
         }
-        "png" => 0.95,  // PNG is lossless
-        "webp" => 0.85, // WebP generally good quality
-        _ => 0.5,       // Unknown format
+        ("png", _) => 0.95,  // PNG is lossless
+        ("webp", _) => 0.85, // WebP generally good quality
+        _ => 0.5,            // Unknown format
     };
 
     // Estimate noise level (inverse of quality for lossy formats)
@@ -66,13 +147,16 @@ pub fn assess_image_quality(metadata: &ImageMetadata) -> ImageResult<QualityAsse
         0.1 // Low noise for lossless formats
     };
 
-    // Estimate contrast based on image type and size
-    let contrast_score = match metadata.image_type {
-        ImageType::Photo => 0.7,
-        ImageType::Logo => 0.9, // High contrast logos
-        ImageType::Graphic => 0.8,
-        ImageType::Screenshot => 0.6,
-        ImageType::Unknown => 0.5,
+    // Measure contrast from the histogram, or estimate it from the image type
+    let contrast_score = match histogram {
+        Some(histogram) => histogram.contrast_spread(),
+        None => match metadata.image_type {
+            ImageType::Photo => 0.7,
+            ImageType::Logo => 0.9, // High contrast logos
+            ImageType::Graphic => 0.8,
+            ImageType::Screenshot => 0.6,
+            ImageType::Unknown => 0.5,
+        },
     };
 
     // Color richness based on color space and estimated complexity
@@ -98,6 +182,145 @@ pub fn assess_image_quality(metadata: &ImageMetadata) -> ImageResult<QualityAsse
     })
 }
 
+// Standard JPEG quantization tables (ITU-T T.81 Annex K), luminance then chrominance
+const ANNEX_K_LUMINANCE: [u16; 64] = [
+    16, 11, 10, 16, 24, 40, 51, 61, 12, 12, 14, 19, 26, 58, 60, 55, 14, 13, 16, 24, 40, 57, 69, 56,
+    14, 17, 22, 29, 51, 87, 80, 62, 18, 22, 37, 56, 68, 109, 103, 77, 24, 35, 55, 64, 81, 104, 113,
+    92, 49, 64, 78, 87, 103, 121, 120, 101, 72, 92, 95, 98, 112, 100, 103, 99,
+];
+const ANNEX_K_CHROMINANCE: [u16; 64] = [
+    17, 18, 24, 47, 99, 99, 99, 99, 18, 21, 26, 66, 99, 99, 99, 99, 24, 26, 56, 99, 99, 99, 99, 99,
+    47, 66, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99,
+    99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99,
+];
+
+/// Estimate the JPEG quality factor (1-100) from the DQT quantization tables
+pub fn estimate_jpeg_quality(data: &[u8]) -> Option<u8> {
+    let tables = read_quantization_tables(data);
+    let luminance = tables.iter().find(|(id, _)| *id == 0)?;
+    let chrominance = tables.iter().find(|(id, _)| *id == 1);
+
+    // Les tables sont stockées en zigzag : on compare des sommes, indépendantes de l'ordre
+    let actual_sum = luminance.1.iter().map(|&v| v as u64).sum::<u64>()
+        + chrominance.map_or(0, |(_, table)| table.iter().map(|&v| v as u64).sum());
+
+    (1..=100u8).min_by_key(|&quality| {
+        let expected_sum = scaled_table_sum(&ANNEX_K_LUMINANCE, quality)
+            + chrominance.map_or(0, |_| scaled_table_sum(&ANNEX_K_CHROMINANCE, quality));
+        expected_sum.abs_diff(actual_sum)
+    })
+}
+
+/// Sum of a standard table scaled with the IJG quality formula
+fn scaled_table_sum(table: &[u16; 64], quality: u8) -> u64 {
+    let quality = quality as u64;
+    let scale = if quality < 50 {
+        5000 / quality
+    } else {
+        200 - quality * 2
+    };
+
+    table
+        .iter()
+        .map(|&v| ((v as u64 * scale + 50) / 100).clamp(1, 255))
+        .sum()
+}
+
+/// Read the quantization tables (id, values) declared before the first scan
+fn read_quantization_tables(data: &[u8]) -> Vec<(u8, Vec<u16>)> {
+    let mut tables = Vec::new();
+
+    if data.len() < 4 || data[0..2] != [0xFF, 0xD8] {
+        return tables;
+    }
+
+    let mut pos = 2;
+    while pos + 4 <= data.len() {
+        if data[pos] != 0xFF {
+            break;
+        }
+        let marker = data[pos + 1];
+        if marker == 0xDA || marker == 0xD9 {
+            break; // Start of scan / end of image
+        }
+
+        let length = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        let end = pos + 2 + length;
+        if length < 2 || end > data.len() {
+            break;
+        }
+
+        if marker == 0xDB {
+            let mut segment = &data[pos + 4..end];
+            while let Some((&info, rest)) = segment.split_first() {
+                let precision_16bit = info >> 4 != 0;
+                let table_id = info & 0x0F;
+                let table_len = if precision_16bit { 128 } else { 64 };
+                if rest.len() < table_len {
+                    break;
+                }
+
+                let values = if precision_16bit {
+                    rest[..128]
+                        .chunks_exact(2)
+                        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                        .collect()
+                } else {
+                    rest[..64].iter().map(|&v| v as u16).collect()
+                };
+                tables.push((table_id, values));
+                segment = &rest[table_len..];
+            }
+        }
+
+        pos = end;
+    }
+
+    tables
+}
+
+/// Compute a BlurHash placeholder string from a downscaled copy of the image
+pub fn compute_blurhash(
+    data: &[u8],
+    metadata: &ImageMetadata,
+    x_components: u32,
+    y_components: u32,
+) -> ImageResult<String> {
+    if !(1..=9).contains(&x_components) || !(1..=9).contains(&y_components) {
+        return Err(ImageError::ProcessingError(format!(
+            "BlurHash components must be between 1 and 9 (got {}x{})",
+            x_components, y_components
+        )));
+    }
+
+    // Le hash ne garde que les basses fréquences : une petite vignette suffit
+    let thumbnail = decode_image(data, &metadata.format)?
+        .thumbnail(64, 64)
+        .to_rgba8();
+
+    blurhash::encode(
+        x_components,
+        y_components,
+        thumbnail.width(),
+        thumbnail.height(),
+        thumbnail.as_raw(),
+    )
+    .map_err(|e| ImageError::ProcessingError(format!("BlurHash encoding failed: {}", e)))
+}
+
+/// Lossy files stored below this fraction of their raw size have little left to gain
+const OPTIMIZED_COMPRESSION_RATIO: f64 = 0.05;
+
+/// Whether a lossy file is already compressed so tightly that re-encoding it would not help
+pub fn is_likely_optimized(metadata: &ImageMetadata) -> bool {
+    let compression_ratio = metadata.compression_ratio();
+    matches!(
+        metadata.format.to_lowercase().as_str(),
+        "webp" | "jpg" | "jpeg"
+    ) && compression_ratio > 0.0
+        && compression_ratio <= OPTIMIZED_COMPRESSION_RATIO
+}
+
 /// Analyze color distribution in image
 pub fn analyze_colors(metadata: &ImageMetadata) -> ImageResult<ColorAnalysis> {
     // Simplified color analysis - in real implementation would sample pixels
@@ -164,6 +387,29 @@ pub fn analyze_colors(metadata: &ImageMetadata) -> ImageResult<ColorAnalysis> {
     })
 }
 
+/// Choose between lossless (true) and lossy (false) WebP from the analysis results
+pub fn recommend_webp_mode(
+    metadata: &ImageMetadata,
+    colors: &ColorAnalysis,
+    quality: &QualityAssessment,
+) -> bool {
+    // Source déjà dégradée (JPEG, bruit) : le lossless ne ferait que grossir le fichier
+    if matches!(metadata.format.to_lowercase().as_str(), "jpg" | "jpeg")
+        || quality.noise_level > 0.5
+    {
+        return false;
+    }
+
+    match metadata.image_type {
+        ImageType::Logo | ImageType::Screenshot => true,
+        ImageType::Photo => false,
+        // Aplats de couleurs : le lossless compresse mieux sans artefacts
+        ImageType::Graphic | ImageType::Unknown => {
+            colors.unique_color_estimate <= 256 || colors.color_variance < 0.5
+        }
+    }
+}
+
 /// Analyze compression potential
 pub fn analyze_compression_potential(
     metadata: &ImageMetadata,
@@ -297,7 +543,7 @@ fn assess_compression_risk(
 pub fn comprehensive_analysis(
     metadata: &ImageMetadata,
 ) -> ImageResult<(QualityAssessment, ColorAnalysis, CompressionPotential)> {
-    let quality = assess_image_quality(metadata)?;
+    let quality = assess_image_quality(metadata, None)?;
     let colors = analyze_colors(metadata)?;
     let compression = analyze_compression_potential(metadata, &quality, &colors);
 
@@ -320,7 +566,7 @@ mod tests {
         );
         metadata.image_type = ImageType::Photo;
 
-        let quality = assess_image_quality(&metadata).unwrap();
+        let quality = assess_image_quality(&metadata, None).unwrap();
         assert!(quality.overall_quality > 0.0);
 
         let colors = analyze_colors(&metadata).unwrap();
@@ -331,6 +577,114 @@ mod tests {
         assert!(compression.estimated_savings_percent > 10.0);
     }
 
+    #[test]
+    fn test_is_likely_optimized() {
+        let dimensions = Dimensions::new(1000, 1000).unwrap(); // 3 MB en RGB brut
+        let tight = ImageMetadata::new(
+            "webp".to_string(),
+            dimensions.clone(),
+            ColorSpace::RGB,
+            60_000,
+        );
+        let loose = ImageMetadata::new(
+            "jpg".to_string(),
+            dimensions.clone(),
+            ColorSpace::RGB,
+            900_000,
+        );
+        let lossless = ImageMetadata::new("png".to_string(), dimensions, ColorSpace::RGB, 60_000);
+
+        assert!(is_likely_optimized(&tight));
+        assert!(!is_likely_optimized(&loose));
+        assert!(!is_likely_optimized(&lossless));
+    }
+
+    #[test]
+    fn test_blurhash_solid_color() {
+        let mut data = Vec::new();
+        image::RgbImage::from_pixel(32, 32, image::Rgb([40, 120, 200]))
+            .write_to(
+                &mut std::io::Cursor::new(&mut data),
+                image::ImageFormat::Png,
+            )
+            .unwrap();
+        let metadata = ImageMetadata::new(
+            "png".to_string(),
+            Dimensions::new(32, 32).unwrap(),
+            ColorSpace::RGB,
+            data.len() as u64,
+        );
+
+        let hash = compute_blurhash(&data, &metadata, 4, 3).unwrap();
+        assert_eq!(hash.len(), 28);
+        assert_eq!(hash, compute_blurhash(&data, &metadata, 4, 3).unwrap());
+
+        // Couleur moyenne quantifiée : proche de l'originale, uniforme sur toute l'image
+        let pixels = blurhash::decode(&hash, 8, 8, 1.0).unwrap();
+        for pixel in pixels.chunks(4) {
+            assert!(pixel[0].abs_diff(40) <= 12, "{:?}", pixel);
+            assert!(pixel[1].abs_diff(120) <= 12, "{:?}", pixel);
+            assert!(pixel[2].abs_diff(200) <= 12, "{:?}", pixel);
+        }
+        for channel in 0..3 {
+            let values = pixels.iter().skip(channel).step_by(4);
+            let spread = values.clone().max().unwrap() - values.min().unwrap();
+            assert!(spread <= 20, "channel {} spread {}", channel, spread);
+        }
+
+        assert!(compute_blurhash(&data, &metadata, 0, 3).is_err());
+        assert!(compute_blurhash(&data, &metadata, 4, 10).is_err());
+    }
+
+    #[test]
+    fn test_compression_potential_json() {
+        let potential = CompressionPotential {
+            lossy_suitable: true,
+            estimated_savings_percent: 42.0,
+            recommended_quality: 80,
+            risk_level: RiskLevel::Medium,
+        };
+
+        let json = serde_json::to_value(&potential).unwrap();
+        for key in [
+            "lossySuitable",
+            "estimatedSavingsPercent",
+            "recommendedQuality",
+            "riskLevel",
+        ] {
+            assert!(json.get(key).is_some(), "missing key {}", key);
+        }
+        assert_eq!(json["riskLevel"], "medium");
+
+        let parsed: CompressionPotential = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.risk_level, RiskLevel::Medium);
+    }
+
+    #[test]
+    fn test_recommend_webp_mode() {
+        let mut logo = ImageMetadata::new(
+            "png".to_string(),
+            Dimensions::new(128, 128).unwrap(),
+            ColorSpace::RGBA,
+            5000,
+        );
+        logo.image_type = ImageType::Logo;
+        let quality = assess_image_quality(&logo, None).unwrap();
+        let colors = analyze_colors(&logo).unwrap();
+        assert!(recommend_webp_mode(&logo, &colors, &quality));
+
+        let mut photo = ImageMetadata::new(
+            "png".to_string(),
+            Dimensions::new(1920, 1080).unwrap(),
+            ColorSpace::RGB,
+            3_000_000,
+        );
+        photo.image_type = ImageType::Photo;
+        let quality = assess_image_quality(&photo, None).unwrap();
+        let colors = analyze_colors(&photo).unwrap();
+        assert!(!recommend_webp_mode(&photo, &colors, &quality));
+    }
+
     #[test]
     fn test_logo_analysis() {
         let dims = Dimensions::new(128, 128).unwrap();
@@ -343,7 +697,7 @@ mod tests {
         metadata.image_type = ImageType::Logo;
         metadata.has_transparency = true;
 
-        let quality = assess_image_quality(&metadata).unwrap();
+        let quality = assess_image_quality(&metadata, None).unwrap();
         let colors = analyze_colors(&metadata).unwrap();
         let compression = analyze_compression_potential(&metadata, &quality, &colors);
 
@@ -364,13 +718,62 @@ mod tests {
         metadata.image_type = ImageType::Screenshot;
 
         let colors = analyze_colors(&metadata).unwrap();
-        let quality = assess_image_quality(&metadata).unwrap();
+        let quality = assess_image_quality(&metadata, None).unwrap();
         let compression = analyze_compression_potential(&metadata, &quality, &colors);
 
         assert!(!compression.lossy_suitable); // Screenshots should preserve UI clarity
         assert_eq!(compression.risk_level, RiskLevel::High);
     }
 
+    #[test]
+    fn test_estimate_jpeg_quality() {
+        let img = ::image::RgbImage::from_fn(64, 64, |x, y| {
+            ::image::Rgb([(x * 4) as u8, (y * 4) as u8, ((x + y) * 2) as u8])
+        });
+        let mut jpeg_data = Vec::new();
+        ::image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg_data, 50)
+            .encode_image(&img)
+            .unwrap();
+
+        let estimate = estimate_jpeg_quality(&jpeg_data).unwrap();
+        assert!((47..=53).contains(&estimate), "estimate was {}", estimate);
+
+        let metadata = crate::domain::image::extract_metadata(&jpeg_data, "jpg").unwrap();
+        assert_eq!(metadata.quality_estimate, Some(estimate));
+
+        assert_eq!(estimate_jpeg_quality(b"not a jpeg"), None);
+    }
+
+    #[test]
+    fn test_low_contrast_histogram() {
+        // Gray values between 120 and 135 only
+        let img = ::image::RgbImage::from_fn(64, 64, |x, y| {
+            let gray = 120 + ((x + y) % 16) as u8;
+            ::image::Rgb([gray, gray, gray])
+        });
+        let mut data = Vec::new();
+        ::image::DynamicImage::ImageRgb8(img)
+            .write_to(
+                &mut std::io::Cursor::new(&mut data),
+                ::image::ImageFormat::Png,
+            )
+            .unwrap();
+        let metadata = ImageMetadata::new(
+            "png".to_string(),
+            Dimensions::new(64, 64).unwrap(),
+            ColorSpace::RGB,
+            data.len() as u64,
+        );
+
+        let histogram = compute_histogram(&data, &metadata).unwrap();
+        assert_eq!(histogram.pixel_count, 64 * 64);
+        assert_eq!(histogram.red[..120].iter().sum::<u32>(), 0);
+        assert_eq!(histogram.luminance[120..136].iter().sum::<u32>(), 64 * 64);
+
+        let quality = assess_image_quality(&metadata, Some(&histogram)).unwrap();
+        assert!(quality.contrast_score < 0.1);
+    }
+
     #[test]
     fn test_comprehensive_analysis() {
         let dims = Dimensions::new(800, 600).unwrap();
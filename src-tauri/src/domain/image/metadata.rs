@@ -1,3 +1,4 @@
+use crate::domain::image::analysis::estimate_jpeg_quality;
 use crate::domain::image::error::{ImageError, ImageResult};
 use serde::{Deserialize, Serialize};
 
@@ -86,6 +87,19 @@ pub enum ImageType {
     Unknown,
 }
 
+impl ImageType {
+    /// Lowercase name as stored in the statistics database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImageType::Photo => "photo",
+            ImageType::Logo => "logo",
+            ImageType::Graphic => "graphic",
+            ImageType::Screenshot => "screenshot",
+            ImageType::Unknown => "unknown",
+        }
+    }
+}
+
 /// Comprehensive image metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImageMetadata {
@@ -123,9 +137,14 @@ impl ImageMetadata {
         }
     }
 
+    /// Bits per pixel, all channels included, at the stored bit depth
+    pub fn bits_per_pixel(&self) -> u64 {
+        self.color_space.bytes_per_pixel() as u64 * self.bit_depth as u64
+    }
+
     /// Calculate theoretical uncompressed size
     pub fn uncompressed_size_bytes(&self) -> u64 {
-        self.dimensions.pixel_count() * self.color_space.bytes_per_pixel() as u64
+        (self.dimensions.pixel_count() * self.bits_per_pixel()).div_ceil(8)
     }
 
     /// Calculate current compression ratio
@@ -161,27 +180,62 @@ impl ImageMetadata {
     }
 }
 
-/// Extract basic metadata from image data (placeholder implementation)
-pub fn extract_metadata(data: &[u8], format: &str) -> ImageResult<ImageMetadata> {
-    // This is a simplified implementation
-    // In a real implementation, you'd use libraries like `image` crate
+/// Returns true if metadata can be extracted from this input format
+pub fn is_supported_input_format(format: &str) -> bool {
+    matches!(
+        format.to_lowercase().as_str(),
+        "png" | "jpg" | "jpeg" | "webp"
+    )
+}
 
-    let (width, height) = match format.to_lowercase().as_str() {
-        "png" => extract_png_dimensions(data)?,
-        "jpg" | "jpeg" => extract_jpeg_dimensions(data)?,
-        "webp" => extract_webp_dimensions(data)?,
+/// Extract basic metadata from image data, reading dimensions from the file header
+pub fn extract_metadata(data: &[u8], format: &str) -> ImageResult<ImageMetadata> {
+    let (width, height, bit_depth, color_space) = match format.to_lowercase().as_str() {
+        "png" => {
+            let (width, height) = extract_png_dimensions(data)?;
+            (
+                width,
+                height,
+                extract_png_bit_depth(data)?,
+                extract_png_color_space(data)?,
+            )
+        }
+        "jpg" | "jpeg" => {
+            let frame = jpeg_frame_header(data)?;
+            let (width, height) = jpeg_frame_dimensions(frame);
+            // Précision de l'échantillon : 8 bits, 12 en JPEG étendu
+            (width, height, frame[0], jpeg_color_space(frame[5]))
+        }
+        // VP8/VP8L ne codent que des canaux de 8 bits
+        "webp" => {
+            let (width, height) = extract_webp_dimensions(data)?;
+            let color_space = if webp_has_alpha(data) {
+                ColorSpace::RGBA
+            } else {
+                ColorSpace::RGB
+            };
+            (width, height, 8, color_space)
+        }
         _ => return Err(ImageError::UnsupportedFormat(format.to_string())),
     };
 
     let dimensions = Dimensions::new(width, height)?;
-    let color_space = ColorSpace::RGB; // Simplified assumption
 
-    Ok(ImageMetadata::new(
+    let mut metadata = ImageMetadata::new(
         format.to_string(),
         dimensions,
         color_space,
         data.len() as u64,
-    ))
+    );
+    metadata.bit_depth = bit_depth;
+    // Un chunk tRNS rend transparente une image PNG sans canal alpha
+    metadata.has_transparency |= format.eq_ignore_ascii_case("png") && png_has_trns_chunk(data);
+
+    if matches!(format.to_lowercase().as_str(), "jpg" | "jpeg") {
+        metadata.quality_estimate = estimate_jpeg_quality(data);
+    }
+
+    Ok(metadata)
 }
 
 /// Classify image type based on metadata and simple analysis
@@ -194,6 +248,15 @@ pub fn classify_image_type(metadata: &ImageMetadata) -> ImageType {
         return ImageType::Logo;
     }
 
+    // Résolution d'écran exacte en format sans perte : capture d'écran, même si
+    // elle a la taille et le ratio d'une photo (un JPEG 1920x1080 reste une photo)
+    let width = metadata.dimensions.width;
+    let height = metadata.dimensions.height;
+    let is_jpeg = matches!(metadata.format.to_lowercase().as_str(), "jpg" | "jpeg");
+    if !is_jpeg && is_typical_screen_resolution(width, height) {
+        return ImageType::Screenshot;
+    }
+
     // Large images with standard photo aspect ratios
     if pixel_count > 1000000 {
         let aspect_ratio = metadata.dimensions.aspect_ratio();
@@ -202,13 +265,6 @@ pub fn classify_image_type(metadata: &ImageMetadata) -> ImageType {
         }
     }
 
-    // Check for typical screenshot dimensions
-    let width = metadata.dimensions.width;
-    let height = metadata.dimensions.height;
-    if is_typical_screen_resolution(width, height) {
-        return ImageType::Screenshot;
-    }
-
     // Default classification based on estimated complexity
     if let Some(colors) = metadata.estimated_colors {
         if colors < 64 {
@@ -241,8 +297,17 @@ fn is_typical_screen_resolution(width: u32, height: u32) -> bool {
         .any(|(w, h)| *w == width && *h == height)
 }
 
-// Placeholder dimension extraction functions
-// In a real implementation, these would parse the actual image headers
+// Parseurs d'en-têtes : toute entrée tronquée ou hostile doit donner une erreur, jamais un panic
+
+/// Bytes `range` of the header, or an error naming what was being read
+fn header_bytes<'a>(
+    data: &'a [u8],
+    range: std::ops::Range<usize>,
+    what: &str,
+) -> ImageResult<&'a [u8]> {
+    data.get(range)
+        .ok_or_else(|| ImageError::InvalidFormat(format!("{} truncated", what)))
+}
 
 fn extract_png_dimensions(data: &[u8]) -> ImageResult<(u32, u32)> {
     if data.len() < 24 {
@@ -250,27 +315,177 @@ fn extract_png_dimensions(data: &[u8]) -> ImageResult<(u32, u32)> {
     }
 
     // PNG signature check
-    if &data[0..8] != b"\x89PNG\r\n\x1a\n" {
+    if header_bytes(data, 0..8, "PNG signature")? != b"\x89PNG\r\n\x1a\n" {
         return Err(ImageError::InvalidFormat(
             "Invalid PNG signature".to_string(),
         ));
     }
 
-    // Extract dimensions from IHDR chunk (simplified)
-    let width = u32::from_be_bytes([data[16], data[17], data[18], data[19]]);
-    let height = u32::from_be_bytes([data[20], data[21], data[22], data[23]]);
+    let ihdr = header_bytes(data, 16..24, "PNG IHDR chunk")?;
+    let width = u32::from_be_bytes([ihdr[0], ihdr[1], ihdr[2], ihdr[3]]);
+    let height = u32::from_be_bytes([ihdr[4], ihdr[5], ihdr[6], ihdr[7]]);
 
     Ok((width, height))
 }
 
-fn extract_jpeg_dimensions(_data: &[u8]) -> ImageResult<(u32, u32)> {
-    // Simplified placeholder - would need proper JPEG parsing
-    Ok((1920, 1080)) // Default dimensions
+fn extract_png_bit_depth(data: &[u8]) -> ImageResult<u8> {
+    match header_bytes(data, 24..25, "PNG IHDR chunk")?[0] {
+        bit_depth @ (1 | 2 | 4 | 8 | 16) => Ok(bit_depth),
+        other => Err(ImageError::InvalidFormat(format!(
+            "Invalid PNG bit depth: {}",
+            other
+        ))),
+    }
+}
+
+fn extract_png_color_space(data: &[u8]) -> ImageResult<ColorSpace> {
+    match header_bytes(data, 25..26, "PNG IHDR chunk")?[0] {
+        0 => Ok(ColorSpace::Grayscale),
+        // Palette : décodée en RGB, la transparence éventuelle passe par tRNS
+        2 | 3 => Ok(ColorSpace::RGB),
+        4 => Ok(ColorSpace::GrayscaleAlpha),
+        6 => Ok(ColorSpace::RGBA),
+        other => Err(ImageError::InvalidFormat(format!(
+            "Invalid PNG color type: {}",
+            other
+        ))),
+    }
+}
+
+/// Whether a tRNS chunk appears before the image data
+fn png_has_trns_chunk(data: &[u8]) -> bool {
+    let mut pos = 8;
+    while let Some(header) = data.get(pos..pos + 8) {
+        let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        match &header[4..8] {
+            b"tRNS" => return true,
+            b"IDAT" | b"IEND" => return false,
+            _ => {}
+        }
+        // Longueur + type + données + CRC
+        pos = match pos.checked_add(12).and_then(|p| p.checked_add(length)) {
+            Some(next) => next,
+            None => return false,
+        };
+    }
+    false
+}
+
+/// Color space from the SOF component count (YCbCr is decoded to RGB)
+fn jpeg_color_space(components: u8) -> ColorSpace {
+    match components {
+        1 => ColorSpace::Grayscale,
+        4 => ColorSpace::CMYK,
+        _ => ColorSpace::RGB,
+    }
+}
+
+/// Alpha flag of a VP8X or VP8L header; simple lossy VP8 has no alpha
+fn webp_has_alpha(data: &[u8]) -> bool {
+    match data.get(12..16) {
+        Some(b"VP8X") => data.get(20).is_some_and(|flags| flags & 0x10 != 0),
+        Some(b"VP8L") => data.get(24).is_some_and(|bits| bits & 0x10 != 0),
+        _ => false,
+    }
 }
 
-fn extract_webp_dimensions(_data: &[u8]) -> ImageResult<(u32, u32)> {
-    // Simplified placeholder - would need proper WebP parsing
-    Ok((1920, 1080)) // Default dimensions
+/// Width and height from a SOF header as returned by `jpeg_frame_header`
+fn jpeg_frame_dimensions(frame: &[u8]) -> (u32, u32) {
+    let height = u16::from_be_bytes([frame[1], frame[2]]) as u32;
+    let width = u16::from_be_bytes([frame[3], frame[4]]) as u32;
+    (width, height)
+}
+
+/// Start of the SOF segment: precision, height, width, component count
+fn jpeg_frame_header(data: &[u8]) -> ImageResult<&[u8]> {
+    if header_bytes(data, 0..2, "JPEG signature")? != [0xFF, 0xD8] {
+        return Err(ImageError::InvalidFormat(
+            "Invalid JPEG signature".to_string(),
+        ));
+    }
+
+    let mut pos = 2;
+    loop {
+        let marker = header_bytes(data, pos..pos + 2, "JPEG marker")?;
+        if marker[0] != 0xFF {
+            return Err(ImageError::InvalidFormat("Invalid JPEG marker".to_string()));
+        }
+
+        match marker[1] {
+            // Octet de bourrage avant un marqueur
+            0xFF => {
+                pos += 1;
+                continue;
+            }
+            // Marqueurs autonomes, sans longueur
+            0x01 | 0xD0..=0xD7 => {
+                pos += 2;
+                continue;
+            }
+            0xD9 | 0xDA => {
+                return Err(ImageError::InvalidFormat(
+                    "JPEG frame header not found".to_string(),
+                ))
+            }
+            _ => {}
+        }
+
+        let length_bytes = header_bytes(data, pos + 2..pos + 4, "JPEG segment")?;
+        let length = u16::from_be_bytes([length_bytes[0], length_bytes[1]]) as usize;
+        if length < 2 {
+            return Err(ImageError::InvalidFormat(
+                "Invalid JPEG segment length".to_string(),
+            ));
+        }
+
+        // SOF0..SOF15, hors DHT (C4), JPG (C8) et DAC (CC)
+        if matches!(marker[1], 0xC0..=0xCF) && !matches!(marker[1], 0xC4 | 0xC8 | 0xCC) {
+            return header_bytes(data, pos + 4..pos + 10, "JPEG frame header");
+        }
+
+        pos += 2 + length;
+    }
+}
+
+fn extract_webp_dimensions(data: &[u8]) -> ImageResult<(u32, u32)> {
+    let riff = header_bytes(data, 0..12, "WebP RIFF header")?;
+    if &riff[0..4] != b"RIFF" || &riff[8..12] != b"WEBP" {
+        return Err(ImageError::InvalidFormat(
+            "Invalid WebP signature".to_string(),
+        ));
+    }
+
+    match header_bytes(data, 12..16, "WebP chunk")? {
+        b"VP8 " => {
+            // Trame clé : code de démarrage puis largeur/hauteur sur 14 bits
+            let frame = header_bytes(data, 23..30, "WebP VP8 frame header")?;
+            if frame[0..3] != [0x9D, 0x01, 0x2A] {
+                return Err(ImageError::InvalidFormat(
+                    "Invalid WebP VP8 start code".to_string(),
+                ));
+            }
+            let width = u16::from_le_bytes([frame[3], frame[4]]) & 0x3FFF;
+            let height = u16::from_le_bytes([frame[5], frame[6]]) & 0x3FFF;
+            Ok((width as u32, height as u32))
+        }
+        b"VP8L" => {
+            let header = header_bytes(data, 20..25, "WebP VP8L header")?;
+            if header[0] != 0x2F {
+                return Err(ImageError::InvalidFormat(
+                    "Invalid WebP VP8L signature".to_string(),
+                ));
+            }
+            let bits = u32::from_le_bytes([header[1], header[2], header[3], header[4]]);
+            Ok(((bits & 0x3FFF) + 1, ((bits >> 14) & 0x3FFF) + 1))
+        }
+        b"VP8X" => {
+            let canvas = header_bytes(data, 24..30, "WebP VP8X header")?;
+            let width = u32::from_le_bytes([canvas[0], canvas[1], canvas[2], 0]) + 1;
+            let height = u32::from_le_bytes([canvas[3], canvas[4], canvas[5], 0]) + 1;
+            Ok((width, height))
+        }
+        _ => Err(ImageError::InvalidFormat("Unknown WebP chunk".to_string())),
+    }
 }
 
 #[cfg(test)]
@@ -345,5 +560,183 @@ mod tests {
             classify_image_type(&screenshot_metadata),
             ImageType::Screenshot
         );
+
+        // Même résolution en JPEG : une photo
+        let photo_metadata = ImageMetadata::new(
+            "jpg".to_string(),
+            Dimensions::new(1920, 1080).unwrap(),
+            ColorSpace::RGB,
+            100000,
+        );
+        assert_eq!(classify_image_type(&photo_metadata), ImageType::Photo);
+    }
+
+    /// Encode a small gradient so the headers are real encoder output
+    fn encoded_fixture(format: ::image::ImageFormat) -> Vec<u8> {
+        let img = ::image::RgbImage::from_fn(37, 21, |x, y| {
+            ::image::Rgb([(x * 6) as u8, (y * 12) as u8, 128])
+        });
+        let mut data = Vec::new();
+        ::image::DynamicImage::ImageRgb8(img)
+            .write_to(&mut std::io::Cursor::new(&mut data), format)
+            .unwrap();
+        data
+    }
+
+    #[test]
+    fn test_header_parsers_reject_truncated_input() {
+        type Parser = fn(&[u8]) -> ImageResult<(u32, u32)>;
+        let lossy_webp = webp::Encoder::from_rgb(&[90u8; 37 * 21 * 3], 37, 21)
+            .encode(60.0)
+            .to_vec();
+        let cases: [(&str, Parser, Vec<u8>); 4] = [
+            (
+                "png",
+                extract_png_dimensions,
+                encoded_fixture(::image::ImageFormat::Png),
+            ),
+            (
+                "jpeg",
+                |data| jpeg_frame_header(data).map(jpeg_frame_dimensions),
+                encoded_fixture(::image::ImageFormat::Jpeg),
+            ),
+            (
+                "webp lossless",
+                extract_webp_dimensions,
+                encoded_fixture(::image::ImageFormat::WebP),
+            ),
+            ("webp lossy", extract_webp_dimensions, lossy_webp),
+        ];
+
+        for (name, parse, data) in cases {
+            assert_eq!(parse(&data).unwrap(), (37, 21), "{}", name);
+
+            // Entrées hostiles : vide, un octet, signature seule, données aléatoires
+            for hostile in [
+                &[][..],
+                &data[..1],
+                &data[..4],
+                &[0xFF; 64][..],
+                &[0x00; 64][..],
+            ] {
+                assert!(parse(hostile).is_err(), "{} accepted {:?}", name, hostile);
+            }
+
+            // Préfixe valide puis coupure : erreur ou dimensions exactes, jamais de panic
+            let mut first_success = None;
+            for len in 0..data.len() {
+                match parse(&data[..len]) {
+                    Ok(dimensions) => {
+                        assert_eq!(dimensions, (37, 21), "{} cut at {}", name, len);
+                        first_success.get_or_insert(len);
+                    }
+                    Err(_) => assert!(first_success.is_none(), "{} cut at {}", name, len),
+                }
+            }
+            assert!(first_success.is_some_and(|len| len > 4), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_png_bit_depth_from_ihdr() {
+        let encode = |img: ::image::DynamicImage| {
+            let mut data = Vec::new();
+            img.write_to(
+                &mut std::io::Cursor::new(&mut data),
+                ::image::ImageFormat::Png,
+            )
+            .unwrap();
+            data
+        };
+        let deep = encode(::image::DynamicImage::ImageRgb16(
+            ::image::ImageBuffer::from_fn(40, 30, |x, y| {
+                ::image::Rgb([(x * 1600) as u16, (y * 2100) as u16, 32768])
+            }),
+        ));
+        let shallow = encode(::image::DynamicImage::ImageRgb8(
+            ::image::RgbImage::from_fn(40, 30, |x, y| {
+                ::image::Rgb([(x * 6) as u8, (y * 8) as u8, 128])
+            }),
+        ));
+
+        let deep = extract_metadata(&deep, "png").unwrap();
+        let shallow = extract_metadata(&shallow, "png").unwrap();
+        assert_eq!(deep.bit_depth, 16);
+        assert_eq!(shallow.bit_depth, 8);
+        assert_eq!(
+            deep.uncompressed_size_bytes(),
+            2 * shallow.uncompressed_size_bytes()
+        );
+
+        let jpeg = encoded_fixture(::image::ImageFormat::Jpeg);
+        assert_eq!(extract_metadata(&jpeg, "jpg").unwrap().bit_depth, 8);
+    }
+
+    #[test]
+    fn test_color_space_from_headers() {
+        let encode = |img: ::image::DynamicImage, format| {
+            let mut data = Vec::new();
+            img.write_to(&mut std::io::Cursor::new(&mut data), format)
+                .unwrap();
+            data
+        };
+        let gray = ::image::GrayImage::from_fn(32, 32, |x, _| ::image::Luma([(x * 8) as u8]));
+        let rgba = ::image::RgbaImage::from_fn(32, 32, |x, y| {
+            ::image::Rgba([(x * 8) as u8, (y * 8) as u8, 90, (x * 4) as u8])
+        });
+
+        let png = extract_metadata(
+            &encode(
+                ::image::DynamicImage::ImageLuma8(gray.clone()),
+                ::image::ImageFormat::Png,
+            ),
+            "png",
+        )
+        .unwrap();
+        assert_eq!(png.color_space, ColorSpace::Grayscale);
+        assert!(!png.has_transparency);
+        assert_eq!(png.uncompressed_size_bytes(), 32 * 32);
+
+        let png = extract_metadata(
+            &encode(
+                ::image::DynamicImage::ImageRgba8(rgba.clone()),
+                ::image::ImageFormat::Png,
+            ),
+            "png",
+        )
+        .unwrap();
+        assert_eq!(png.color_space, ColorSpace::RGBA);
+        assert!(png.has_transparency);
+
+        let jpeg = encode(
+            ::image::DynamicImage::ImageLuma8(gray),
+            ::image::ImageFormat::Jpeg,
+        );
+        assert_eq!(
+            extract_metadata(&jpeg, "jpg").unwrap().color_space,
+            ColorSpace::Grayscale
+        );
+
+        let webp = encode(
+            ::image::DynamicImage::ImageRgba8(rgba),
+            ::image::ImageFormat::WebP,
+        );
+        let webp = extract_metadata(&webp, "webp").unwrap();
+        assert_eq!(webp.color_space, ColorSpace::RGBA);
+        assert!(webp.has_transparency);
+        let opaque = encoded_fixture(::image::ImageFormat::WebP);
+        assert!(!extract_metadata(&opaque, "webp").unwrap().has_transparency);
+    }
+
+    #[test]
+    fn test_corrupted_segment_length_is_rejected() {
+        // Longueur de segment APP0 impossible (< 2)
+        let data = [0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x01, 0x00, 0x00];
+        assert!(jpeg_frame_header(&data).is_err());
+
+        // Longueur qui pointe au-delà des données
+        let data = [0xFF, 0xD8, 0xFF, 0xE0, 0xFF, 0xFF, 0x00];
+        assert!(jpeg_frame_header(&data).is_err());
+        assert!(extract_metadata(&data, "jpg").is_err());
     }
 }
@@ -11,15 +11,19 @@ pub mod processing;
 // Re-export core types and functions for easy access
 pub use analysis::{
     analyze_colors, analyze_compression_potential, assess_image_quality, comprehensive_analysis,
-    ColorAnalysis, CompressionPotential, QualityAssessment, RiskLevel,
+    compute_blurhash, compute_histogram, estimate_jpeg_quality, is_likely_optimized,
+    recommend_webp_mode, ColorAnalysis, CompressionPotential, Histogram, QualityAssessment,
+    RiskLevel,
 };
 pub use error::{ImageError, ImageResult};
 pub use metadata::{
-    classify_image_type, extract_metadata, ColorSpace, Dimensions, ImageMetadata, ImageType,
+    classify_image_type, extract_metadata, is_supported_input_format, ColorSpace, Dimensions,
+    ImageMetadata, ImageType,
 };
 pub use processing::{
-    apply_sharpening, auto_crop, convert_color_space, create_progressive_jpeg, optimize_for_web,
-    resize_image, ProcessingParams, ProcessingResult,
+    apply_auto_levels, apply_sharpening, apply_watermark, auto_crop, convert_color_space,
+    create_progressive_jpeg, flatten_alpha, generate_contact_sheet, optimize_for_web, resize_image,
+    ProcessingParams, ProcessingResult, ResampleFilter, Transform, Watermark, WatermarkPosition,
 };
 
 // Convenience functions for common image operations
@@ -32,7 +36,9 @@ pub fn analyze_image(
     let mut metadata = extract_metadata(data, format)?;
     metadata.image_type = classify_image_type(&metadata);
 
-    let quality = assess_image_quality(&metadata)?;
+    // Histogramme indisponible (décodage impossible) : contraste estimé par type
+    let histogram = compute_histogram(data, &metadata).ok();
+    let quality = assess_image_quality(&metadata, histogram.as_ref())?;
     let colors = analyze_colors(&metadata)?;
 
     Ok((metadata, quality, colors))
@@ -53,6 +59,7 @@ pub fn prepare_for_web(
     format: &str,
     max_dimensions: Option<Dimensions>,
     target_quality: Option<u8>,
+    filter: Option<ResampleFilter>,
 ) -> ImageResult<ProcessingResult> {
     let (metadata, quality, colors) = analyze_image(data, format)?;
     let compression_potential = analyze_compression_potential(&metadata, &quality, &colors);
@@ -76,7 +83,10 @@ pub fn prepare_for_web(
             let new_height = (metadata.dimensions.height as f64 * scale) as u32;
 
             if let Ok(target_dims) = Dimensions::new(new_width.max(1), new_height.max(1)) {
-                params = params.with_resize(target_dims);
+                let filter = filter.unwrap_or_else(|| {
+                    ResampleFilter::default_for(&metadata.dimensions, &target_dims)
+                });
+                params = params.with_resize(target_dims).with_resample_filter(filter);
             }
         }
     }
@@ -90,11 +100,14 @@ pub fn prepare_for_web(
 }
 
 /// Smart resize that maintains aspect ratio
+///
+/// Without a filter, Lanczos3 is used to shrink and Triangle to enlarge.
 pub fn smart_resize(
     data: &[u8],
     format: &str,
     target_dimensions: Dimensions,
     maintain_aspect_ratio: bool,
+    filter: Option<ResampleFilter>,
 ) -> ImageResult<(Vec<u8>, Dimensions)> {
     let metadata = extract_metadata(data, format)?;
 
@@ -103,11 +116,120 @@ pub fn smart_resize(
     } else {
         target_dimensions
     };
+    if final_dimensions == metadata.dimensions {
+        return Ok((data.to_vec(), final_dimensions));
+    }
 
-    let resized_data = resize_image(data, &metadata, &final_dimensions)?;
+    let filter = filter
+        .unwrap_or_else(|| ResampleFilter::default_for(&metadata.dimensions, &final_dimensions));
+    let resized = processing::decode_image(data, format)?.resize_exact(
+        final_dimensions.width,
+        final_dimensions.height,
+        filter.filter_type(),
+    );
+    let resized_data = processing::encode_image(
+        &resized,
+        format,
+        &ProcessingParams::new(100).with_lossless(),
+    )?;
     Ok((resized_data, final_dimensions))
 }
 
+/// How `smart_crop_resize` picks the part of the image it keeps
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CropStrategy {
+    #[default]
+    Center,
+    /// Window with the most edge energy (local detail)
+    Entropy,
+}
+
+/// Crop to the target aspect ratio, then resize to exactly the target dimensions
+pub fn smart_crop_resize(
+    data: &[u8],
+    format: &str,
+    target_dimensions: Dimensions,
+    strategy: CropStrategy,
+) -> ImageResult<(Vec<u8>, Dimensions)> {
+    let img = processing::decode_image(data, format)?;
+    let (width, height) = (img.width(), img.height());
+
+    // Fenêtre la plus grande possible au ratio cible, le long d'un seul axe
+    let target_ratio = target_dimensions.aspect_ratio();
+    let (crop_width, crop_height) = if width as f64 / height as f64 > target_ratio {
+        (
+            ((height as f64 * target_ratio).round() as u32).clamp(1, width),
+            height,
+        )
+    } else {
+        (
+            width,
+            ((width as f64 / target_ratio).round() as u32).clamp(1, height),
+        )
+    };
+
+    let (x, y) = match strategy {
+        CropStrategy::Center => ((width - crop_width) / 2, (height - crop_height) / 2),
+        CropStrategy::Entropy => most_detailed_window(&img.to_luma8(), crop_width, crop_height),
+    };
+
+    let resized = img.crop_imm(x, y, crop_width, crop_height).resize_exact(
+        target_dimensions.width,
+        target_dimensions.height,
+        ::image::imageops::FilterType::Lanczos3,
+    );
+    let resized_data = processing::encode_image(
+        &resized,
+        format,
+        &ProcessingParams::new(100).with_lossless(),
+    )?;
+
+    Ok((resized_data, target_dimensions))
+}
+
+/// Top-left corner of the crop window holding the most edge energy, centered on ties
+fn most_detailed_window(
+    luma: &::image::GrayImage,
+    crop_width: u32,
+    crop_height: u32,
+) -> (u32, u32) {
+    let (width, height) = luma.dimensions();
+    let horizontal = crop_width < width;
+    let (length, window) = if horizontal {
+        (width, crop_width)
+    } else {
+        (height, crop_height)
+    };
+
+    // Énergie de contour (gradient horizontal + vertical) cumulée par colonne ou par ligne
+    let mut energy = vec![0u64; length as usize];
+    for (x, y, pixel) in luma.enumerate_pixels() {
+        let value = pixel[0];
+        let dx = luma.get_pixel((x + 1).min(width - 1), y)[0].abs_diff(value);
+        let dy = luma.get_pixel(x, (y + 1).min(height - 1))[0].abs_diff(value);
+        let index = if horizontal { x } else { y };
+        energy[index as usize] += (dx as u64) + (dy as u64);
+    }
+
+    let center = (length - window) / 2;
+    let mut window_energy: u64 = energy[..window as usize].iter().sum();
+    let (mut best_offset, mut best_energy) = (0u32, window_energy);
+    for offset in 1..=(length - window) {
+        window_energy += energy[(offset + window - 1) as usize];
+        window_energy -= energy[(offset - 1) as usize];
+        let closer_to_center = offset.abs_diff(center) < best_offset.abs_diff(center);
+        if window_energy > best_energy || (window_energy == best_energy && closer_to_center) {
+            (best_offset, best_energy) = (offset, window_energy);
+        }
+    }
+
+    if horizontal {
+        (best_offset, 0)
+    } else {
+        (0, best_offset)
+    }
+}
+
 /// Calculate dimensions that preserve aspect ratio
 fn calculate_aspect_preserving_dimensions(current: &Dimensions, target: &Dimensions) -> Dimensions {
     let current_ratio = current.aspect_ratio();
@@ -158,6 +280,7 @@ mod integration_tests {
                                       // Add width and height (1920x1080 in big-endian)
         png_data[16..20].copy_from_slice(&1920u32.to_be_bytes());
         png_data[20..24].copy_from_slice(&1080u32.to_be_bytes());
+        png_data.extend([8, 2]); // 8-bit RGB
 
         let result = analyze_image(&png_data, "png");
         assert!(result.is_ok());
@@ -172,8 +295,9 @@ mod integration_tests {
 
     #[test]
     fn test_compression_recommendations() {
-        let fake_data = vec![0u8; 1000];
-        let result = get_compression_recommendations(&fake_data, "jpg");
+        // Les dimensions sont lues dans l'en-tête : il faut un vrai JPEG
+        let jpeg_data = encode_test_image(64, 48, ::image::ImageFormat::Jpeg);
+        let result = get_compression_recommendations(&jpeg_data, "jpg");
         assert!(result.is_ok());
 
         let recommendations = result.unwrap();
@@ -183,33 +307,139 @@ mod integration_tests {
         );
     }
 
+    fn encode_test_image(width: u32, height: u32, format: ::image::ImageFormat) -> Vec<u8> {
+        let img = ::image::RgbImage::from_fn(width, height, |x, y| {
+            ::image::Rgb([(x % 256) as u8, (y % 256) as u8, 64])
+        });
+        let mut data = Vec::new();
+        ::image::DynamicImage::ImageRgb8(img)
+            .write_to(&mut std::io::Cursor::new(&mut data), format)
+            .unwrap();
+        data
+    }
+
     #[test]
     fn test_prepare_for_web() {
-        let fake_data = vec![0u8; 10000];
+        let png_data = encode_test_image(1600, 1200, ::image::ImageFormat::Png);
         let max_dims = Dimensions::new(800, 600).unwrap();
 
-        let result = prepare_for_web(&fake_data, "png", Some(max_dims), Some(80));
+        let result = prepare_for_web(&png_data, "png", Some(max_dims), Some(80), None);
         assert!(result.is_ok());
 
         let processed = result.unwrap();
         assert!(processed.processed_size > 0);
         assert!(!processed.operations_applied.is_empty());
+
+        // Output must be a real image, not a truncated buffer
+        let decoded = ::image::load_from_memory(&processed.data).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (800, 600));
     }
 
     #[test]
     fn test_smart_resize() {
-        // Create fake image data
-        let fake_data = vec![0u8; 30000]; // Simulates 100x100 RGB
+        let png_data = encode_test_image(400, 200, ::image::ImageFormat::Png);
         let target = Dimensions::new(200, 150).unwrap();
 
-        let result = smart_resize(&fake_data, "png", target.clone(), true);
-        assert!(result.is_ok());
+        let (resized_data, final_dims) =
+            smart_resize(&png_data, "png", target.clone(), true, None).unwrap();
+        // With aspect ratio preservation, the image fits inside the target
+        assert_eq!((final_dims.width, final_dims.height), (200, 100));
+
+        let decoded = ::image::load_from_memory(&resized_data).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (200, 100));
+
+        // Sans conserver le ratio, la cible est prise telle quelle
+        let (_, exact_dims) = smart_resize(&png_data, "png", target.clone(), false, None).unwrap();
+        assert_eq!(exact_dims, target);
+    }
+
+    #[test]
+    fn test_lanczos_downscale_aliases_less_than_nearest() {
+        // Rayures bien au-delà de la fréquence de Nyquist de l'image réduite
+        let stripes = ::image::GrayImage::from_fn(256, 64, |x, _| {
+            let phase = x as f64 * std::f64::consts::TAU / 2.3;
+            ::image::Luma([(128.0 + 127.0 * phase.sin()) as u8])
+        });
+        let mut png_data = Vec::new();
+        ::image::DynamicImage::ImageLuma8(stripes)
+            .write_to(
+                &mut std::io::Cursor::new(&mut png_data),
+                ::image::ImageFormat::Png,
+            )
+            .unwrap();
+
+        let high_frequency_energy = |filter| {
+            let target = Dimensions::new(64, 16).unwrap();
+            let (data, dims) = smart_resize(&png_data, "png", target, true, filter).unwrap();
+            assert_eq!((dims.width, dims.height), (64, 16));
+            let luma = ::image::load_from_memory(&data).unwrap().to_luma8();
+            luma.rows()
+                .flat_map(|row| {
+                    let row: Vec<f64> = row.map(|pixel| pixel[0] as f64).collect();
+                    (1..row.len())
+                        .map(|x| (row[x] - row[x - 1]).abs())
+                        .collect::<Vec<_>>()
+                })
+                .sum::<f64>()
+        };
 
-        let (resized_data, final_dims) = result.unwrap();
-        assert!(!resized_data.is_empty());
-        // With aspect ratio preservation, one dimension should match target
-        assert!(final_dims.width <= target.width);
-        assert!(final_dims.height <= target.height);
+        let nearest = high_frequency_energy(Some(ResampleFilter::Nearest));
+        let lanczos = high_frequency_energy(Some(ResampleFilter::Lanczos3));
+        assert!(
+            lanczos * 4.0 < nearest,
+            "lanczos {} nearest {}",
+            lanczos,
+            nearest
+        );
+        // Réduction sans filtre explicite : Lanczos3
+        assert_eq!(high_frequency_energy(None), lanczos);
+
+        let small = Dimensions::new(10, 10).unwrap();
+        let large = Dimensions::new(20, 20).unwrap();
+        assert_eq!(
+            ResampleFilter::default_for(&large, &small),
+            ResampleFilter::Lanczos3
+        );
+        assert_eq!(
+            ResampleFilter::default_for(&small, &large),
+            ResampleFilter::Triangle
+        );
+    }
+
+    #[test]
+    fn test_smart_crop_resize_to_square() {
+        let png_data = encode_test_image(1920, 1080, ::image::ImageFormat::Png);
+        let target = Dimensions::new(256, 256).unwrap();
+
+        for strategy in [CropStrategy::Center, CropStrategy::Entropy] {
+            let (data, dimensions) =
+                smart_crop_resize(&png_data, "png", target.clone(), strategy).unwrap();
+            assert_eq!(dimensions, target);
+
+            let decoded = ::image::load_from_memory(&data).unwrap();
+            assert_eq!(decoded.width(), decoded.height());
+            assert_eq!((decoded.width(), decoded.height()), (256, 256));
+        }
+    }
+
+    #[test]
+    fn test_entropy_crop_follows_detail() {
+        // Fond uni avec un damier à droite : la fenêtre doit s'y placer
+        let img = ::image::GrayImage::from_fn(300, 100, |x, y| {
+            if x >= 200 && (x / 4 + y / 4) % 2 == 0 {
+                ::image::Luma([255])
+            } else {
+                ::image::Luma([40])
+            }
+        });
+        // Le bord gauche du damier compte aussi comme contour, à un pixel près
+        let (x, y) = most_detailed_window(&img, 100, 100);
+        assert!((199..=200).contains(&x), "window starts at {}", x);
+        assert_eq!(y, 0);
+
+        // Image uniforme : on retombe sur le centre
+        let flat = ::image::GrayImage::from_pixel(300, 100, ::image::Luma([128]));
+        assert_eq!(most_detailed_window(&flat, 100, 100), (100, 0));
     }
 
     #[test]
@@ -227,19 +457,28 @@ mod integration_tests {
     #[test]
     fn test_batch_processing() {
         let images = vec![
-            (vec![0u8; 1000], "png".to_string()),
-            (vec![1u8; 2000], "jpg".to_string()),
-            (vec![2u8; 1500], "webp".to_string()),
+            (
+                encode_test_image(32, 32, ::image::ImageFormat::Png),
+                "png".to_string(),
+            ),
+            (
+                encode_test_image(32, 32, ::image::ImageFormat::Jpeg),
+                "jpg".to_string(),
+            ),
+            (
+                encode_test_image(32, 32, ::image::ImageFormat::WebP),
+                "webp".to_string(),
+            ),
         ];
 
         let processor = |data: &[u8], format: &str| -> ImageResult<ProcessingResult> {
-            prepare_for_web(data, format, None, Some(80))
+            prepare_for_web(data, format, None, Some(80), None)
         };
 
         let results = batch_process_images(&images, processor);
         assert_eq!(results.len(), 3);
 
-        // All should succeed (with our simplified implementation)
+        // All should succeed
         for result in results {
             assert!(result.is_ok());
         }
@@ -1,12 +1,43 @@
 use crate::domain::image::{
+    analysis::Histogram,
     error::{ImageError, ImageResult},
     metadata::{ColorSpace, Dimensions, ImageMetadata},
 };
 
+/// Resampling filter used when resizing, from fastest to smoothest
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResampleFilter {
+    Nearest,
+    Triangle,
+    CatmullRom,
+    Lanczos3,
+}
+
+impl ResampleFilter {
+    /// Lanczos3 when shrinking, Triangle when enlarging
+    pub fn default_for(from: &Dimensions, to: &Dimensions) -> Self {
+        if to.pixel_count() < from.pixel_count() {
+            ResampleFilter::Lanczos3
+        } else {
+            ResampleFilter::Triangle
+        }
+    }
+
+    pub(crate) fn filter_type(self) -> image::imageops::FilterType {
+        match self {
+            ResampleFilter::Nearest => image::imageops::FilterType::Nearest,
+            ResampleFilter::Triangle => image::imageops::FilterType::Triangle,
+            ResampleFilter::CatmullRom => image::imageops::FilterType::CatmullRom,
+            ResampleFilter::Lanczos3 => image::imageops::FilterType::Lanczos3,
+        }
+    }
+}
+
 /// Image processing operation result
 #[derive(Debug, Clone)]
 pub struct ProcessingResult {
     pub data: Vec<u8>,
+    pub dimensions: Dimensions, // Output dimensions
     pub original_size: usize,
     pub processed_size: usize,
     pub operations_applied: Vec<String>,
@@ -23,6 +54,57 @@ impl ProcessingResult {
     }
 }
 
+/// Rotation (clockwise) or flip applied before export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transform {
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    FlipHorizontal,
+    FlipVertical,
+}
+
+impl Transform {
+    /// Apply the transform to a decoded image
+    pub fn apply(self, img: &image::DynamicImage) -> image::DynamicImage {
+        use image::imageops;
+
+        let rgba_img = img.to_rgba8();
+        let transformed = match self {
+            Transform::Rotate90 => imageops::rotate90(&rgba_img),
+            Transform::Rotate180 => imageops::rotate180(&rgba_img),
+            Transform::Rotate270 => imageops::rotate270(&rgba_img),
+            Transform::FlipHorizontal => imageops::flip_horizontal(&rgba_img),
+            Transform::FlipVertical => imageops::flip_vertical(&rgba_img),
+        };
+
+        let transformed = image::DynamicImage::ImageRgba8(transformed);
+        if img.color().has_alpha() {
+            transformed
+        } else {
+            image::DynamicImage::ImageRgb8(transformed.to_rgb8())
+        }
+    }
+}
+
+/// Where a watermark is placed on the base image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatermarkPosition {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    Center,
+}
+
+/// PNG overlay stamped onto processed images
+#[derive(Debug, Clone)]
+pub struct Watermark {
+    pub overlay_png: Vec<u8>,
+    pub position: WatermarkPosition,
+    pub opacity: f32, // 0.0 - 1.0
+}
+
 /// Image processing parameters
 #[derive(Debug, Clone)]
 pub struct ProcessingParams {
@@ -32,6 +114,12 @@ pub struct ProcessingParams {
     pub optimize_alpha: bool,
     pub progressive: bool,
     pub lossless: bool,
+    pub output_format: Option<String>,
+    pub transform: Option<Transform>,
+    pub auto_levels: bool,
+    pub watermark: Option<Watermark>,
+    pub background: [u8; 3], // Fond des pixels transparents quand l'alpha est retiré
+    pub resample_filter: ResampleFilter,
 }
 
 impl ProcessingParams {
@@ -43,6 +131,12 @@ impl ProcessingParams {
             optimize_alpha: true,
             progressive: false,
             lossless: false,
+            output_format: None,
+            transform: None,
+            auto_levels: false,
+            watermark: None,
+            background: [255, 255, 255],
+            resample_filter: ResampleFilter::Lanczos3,
         }
     }
 
@@ -51,6 +145,11 @@ impl ProcessingParams {
         self
     }
 
+    pub fn with_resample_filter(mut self, filter: ResampleFilter) -> Self {
+        self.resample_filter = filter;
+        self
+    }
+
     pub fn with_lossless(mut self) -> Self {
         self.lossless = true;
         self
@@ -60,6 +159,40 @@ impl ProcessingParams {
         self.preserve_metadata = true;
         self
     }
+
+    pub fn with_output_format(mut self, format: &str) -> Self {
+        self.output_format = Some(format.to_lowercase());
+        self
+    }
+
+    pub fn with_transform(mut self, transform: Transform) -> Self {
+        self.transform = Some(transform);
+        self
+    }
+
+    pub fn with_background(mut self, background: [u8; 3]) -> Self {
+        self.background = background;
+        self
+    }
+
+    pub fn with_auto_levels(mut self) -> Self {
+        self.auto_levels = true;
+        self
+    }
+
+    pub fn with_watermark(
+        mut self,
+        overlay_png: Vec<u8>,
+        position: WatermarkPosition,
+        opacity: f32,
+    ) -> Self {
+        self.watermark = Some(Watermark {
+            overlay_png,
+            position,
+            opacity,
+        });
+        self
+    }
 }
 
 /// Resize image data
@@ -143,9 +276,10 @@ pub fn convert_color_space(
             }
         }
         (ColorSpace::RGBA, ColorSpace::RGB) => {
-            // Remove alpha channel
+            // Composite over white rather than dropping the alpha channel
             for chunk in data.chunks_exact(4) {
-                result.extend_from_slice(&chunk[..3]);
+                let pixel = [chunk[0], chunk[1], chunk[2], chunk[3]];
+                result.extend_from_slice(&composite_over(pixel, [255, 255, 255]));
             }
         }
         (ColorSpace::RGB, ColorSpace::Grayscale) => {
@@ -183,81 +317,413 @@ pub fn optimize_for_web(
 ) -> ImageResult<ProcessingResult> {
     let start_time = std::time::Instant::now();
     let mut operations = Vec::new();
-    let mut processed_data = data.to_vec();
     let original_size = data.len();
 
-    // Step 1: Resize if needed
+    let mut img = decode_image(data, &metadata.format)?;
+
+    // Step 1: Resize if needed (fit within target, never upscale)
     if let Some(ref target_dims) = params.target_dimensions {
-        processed_data = resize_image(&processed_data, metadata, target_dims)?;
-        operations.push(format!(
-            "Resize to {}x{}",
-            target_dims.width, target_dims.height
-        ));
+        let width = target_dims.width.min(img.width());
+        let height = target_dims.height.min(img.height());
+
+        if width != img.width() || height != img.height() {
+            img = img.resize(width, height, params.resample_filter.filter_type());
+            operations.push(format!("Resize to {}x{}", img.width(), img.height()));
+        }
     }
 
-    // Step 2: Color space optimization
-    if metadata.color_space == ColorSpace::RGBA && !params.optimize_alpha {
-        // Convert RGBA to RGB if no transparency is actually used
-        processed_data = convert_color_space(
-            &processed_data,
-            ColorSpace::RGBA,
-            ColorSpace::RGB,
-            params
-                .target_dimensions
-                .as_ref()
-                .unwrap_or(&metadata.dimensions),
+    // Step 2: Rotate or flip
+    if let Some(transform) = params.transform {
+        img = transform.apply(&img);
+        operations.push(format!("Transform {:?}", transform));
+    }
+
+    // Step 3: Stretch the levels, if requested
+    if params.auto_levels {
+        if let Some(leveled) = stretch_levels(&img) {
+            img = leveled;
+            operations.push("Auto levels".to_string());
+        }
+    }
+
+    // Step 4: Stamp the watermark, if any
+    if let Some(ref watermark) = params.watermark {
+        img = blend_watermark(
+            img,
+            &watermark.overlay_png,
+            watermark.position,
+            watermark.opacity,
         )?;
+        operations.push(format!("Apply watermark ({:?})", watermark.position));
+    }
+
+    // Step 5: Color space optimization
+    if params.optimize_alpha && img.color().has_alpha() && is_fully_opaque(&img) {
+        // Convert RGBA to RGB if no transparency is actually used
+        img = image::DynamicImage::ImageRgb8(flatten_alpha(&img, params.background));
         operations.push("Remove unused alpha channel".to_string());
     }
 
-    // Step 3: Quality optimization (simulated)
-    if !params.lossless && params.quality < 100 {
-        // Simulate quality reduction by slight data reduction
-        let quality_factor = params.quality as f64 / 100.0;
-        let target_size = (processed_data.len() as f64 * quality_factor * 0.8) as usize;
-        if target_size < processed_data.len() {
-            processed_data.truncate(target_size);
-            operations.push(format!("Apply quality {}", params.quality));
-        }
+    // Step 6: Encode with the requested quality
+    let output_format = params.output_format.as_deref().unwrap_or(&metadata.format);
+    let processed_data = encode_image(&img, output_format, params)?;
+    if params.lossless {
+        operations.push(format!("Encode {} lossless", output_format));
+    } else {
+        operations.push(format!(
+            "Encode {} at quality {}",
+            output_format, params.quality
+        ));
     }
 
-    // Step 4: Remove metadata if not preserving
+    // Step 7: Re-encoding drops the source metadata
     if !params.preserve_metadata {
         operations.push("Remove metadata".to_string());
-        // Metadata removal would be format-specific
     }
 
     let processing_time = start_time.elapsed().as_millis() as u64;
 
     Ok(ProcessingResult {
-        data: processed_data.clone(),
+        dimensions: Dimensions::new(img.width(), img.height())?,
         original_size,
         processed_size: processed_data.len(),
+        data: processed_data,
         operations_applied: operations,
         processing_time_ms: processing_time,
     })
 }
 
-/// Auto-crop image to remove unnecessary borders
-pub fn auto_crop(data: &[u8], metadata: &ImageMetadata) -> ImageResult<(Vec<u8>, Dimensions)> {
-    // Simplified auto-crop implementation
-    // In reality, this would analyze pixel data to find content boundaries
+/// Decode image data using the format as a hint
+pub(crate) fn decode_image(data: &[u8], format: &str) -> ImageResult<image::DynamicImage> {
+    let decoded = match image::ImageFormat::from_extension(format.to_lowercase()) {
+        Some(image_format) => image::load_from_memory_with_format(data, image_format),
+        None => image::load_from_memory(data),
+    };
+
+    decoded.map_err(|e| ImageError::InvalidFormat(format!("Failed to decode image: {}", e)))
+}
+
+/// Composite the image over an opaque background color, dropping its alpha channel
+pub fn flatten_alpha(img: &image::DynamicImage, background: [u8; 3]) -> image::RgbImage {
+    if !img.color().has_alpha() {
+        return img.to_rgb8();
+    }
+
+    let rgba_img = img.to_rgba8();
+    image::RgbImage::from_fn(rgba_img.width(), rgba_img.height(), |x, y| {
+        image::Rgb(composite_over(rgba_img.get_pixel(x, y).0, background))
+    })
+}
+
+/// "Over" operator for a straight-alpha pixel on an opaque background
+fn composite_over(pixel: [u8; 4], background: [u8; 3]) -> [u8; 3] {
+    let alpha = pixel[3] as u32;
+    // Couleur prémultipliée (c·α) plus la part du fond laissée visible (1 − α)
+    std::array::from_fn(|channel| {
+        let premultiplied = pixel[channel] as u32 * alpha;
+        ((premultiplied + background[channel] as u32 * (255 - alpha) + 127) / 255) as u8
+    })
+}
 
-    let current_dims = &metadata.dimensions;
+/// Check whether every pixel of the image is fully opaque
+fn is_fully_opaque(img: &image::DynamicImage) -> bool {
+    img.to_rgba8().pixels().all(|pixel| pixel[3] == 255)
+}
 
-    // Simulate finding a crop region (10% margin reduction)
-    let margin_percent = 0.1;
-    let new_width = ((current_dims.width as f64) * (1.0 - margin_percent)) as u32;
-    let new_height = ((current_dims.height as f64) * (1.0 - margin_percent)) as u32;
+/// Encode an image to the given format
+pub(crate) fn encode_image(
+    img: &image::DynamicImage,
+    format: &str,
+    params: &ProcessingParams,
+) -> ImageResult<Vec<u8>> {
+    let mut buffer = Vec::new();
+
+    match format.to_lowercase().as_str() {
+        "webp" => {
+            let encoded = if img.color().has_alpha() {
+                let rgba_img = img.to_rgba8();
+                let encoder =
+                    webp::Encoder::from_rgba(rgba_img.as_raw(), img.width(), img.height());
+                if params.lossless {
+                    encoder.encode_lossless()
+                } else {
+                    encoder.encode(params.quality as f32)
+                }
+            } else {
+                let rgb_img = img.to_rgb8();
+                let encoder = webp::Encoder::from_rgb(rgb_img.as_raw(), img.width(), img.height());
+                if params.lossless {
+                    encoder.encode_lossless()
+                } else {
+                    encoder.encode(params.quality as f32)
+                }
+            };
+            buffer.extend_from_slice(&encoded);
+        }
+        "jpg" | "jpeg" => {
+            let rgb_img = flatten_alpha(img, params.background);
+            let quality = if params.lossless { 100 } else { params.quality };
 
-    let new_dimensions = Dimensions::new(new_width.max(1), new_height.max(1))?;
+            // Sans mozjpeg, seul l'encodeur baseline est disponible
+            if params.progressive && cfg!(feature = "mozjpeg") {
+                return encode_progressive_jpeg(&rgb_img, quality);
+            }
 
-    // Simulate cropping by resizing (in real implementation, would extract region)
-    let cropped_data = resize_image(data, metadata, &new_dimensions)?;
+            image::codecs::jpeg::JpegEncoder::new_with_quality(&mut buffer, quality)
+                .encode_image(&rgb_img)
+                .map_err(|e| ImageError::ProcessingError(format!("JPEG encoding failed: {}", e)))?;
+        }
+        "png" => {
+            img.write_to(
+                &mut std::io::Cursor::new(&mut buffer),
+                image::ImageFormat::Png,
+            )
+            .map_err(|e| ImageError::ProcessingError(format!("PNG encoding failed: {}", e)))?;
+        }
+        other => return Err(ImageError::UnsupportedFormat(other.to_string())),
+    }
+
+    Ok(buffer)
+}
+
+/// Auto-crop image to remove uniform borders matching the corner background color
+pub fn auto_crop(
+    data: &[u8],
+    metadata: &ImageMetadata,
+    tolerance: u8,
+) -> ImageResult<(Vec<u8>, Dimensions)> {
+    let img = decode_image(data, &metadata.format)?;
+    let rgba_img = img.to_rgba8();
+    let (width, height) = rgba_img.dimensions();
+
+    let background = *rgba_img.get_pixel(0, 0);
+    let is_content = |x: u32, y: u32| {
+        let pixel = rgba_img.get_pixel(x, y);
+        pixel
+            .0
+            .iter()
+            .zip(background.0.iter())
+            .any(|(a, b)| a.abs_diff(*b) > tolerance)
+    };
+    let row_has_content = |y: u32| (0..width).any(|x| is_content(x, y));
+
+    // Uniform image: nothing to crop
+    let top = match (0..height).find(|&y| row_has_content(y)) {
+        Some(top) => top,
+        None => return Ok((data.to_vec(), Dimensions::new(width, height)?)),
+    };
+    let bottom = (top..height)
+        .rev()
+        .find(|&y| row_has_content(y))
+        .unwrap_or(top);
+
+    let column_has_content = |x: u32| (top..=bottom).any(|y| is_content(x, y));
+    let left = (0..width).find(|&x| column_has_content(x)).unwrap_or(0);
+    let right = (left..width)
+        .rev()
+        .find(|&x| column_has_content(x))
+        .unwrap_or(left);
+
+    let new_dimensions = Dimensions::new(right - left + 1, bottom - top + 1)?;
+    if new_dimensions.width == width && new_dimensions.height == height {
+        return Ok((data.to_vec(), new_dimensions));
+    }
+
+    let cropped = img.crop_imm(left, top, new_dimensions.width, new_dimensions.height);
+    let cropped_data = encode_image(
+        &cropped,
+        &metadata.format,
+        &ProcessingParams::new(100).with_lossless(),
+    )?;
 
     Ok((cropped_data, new_dimensions))
 }
 
+/// Tile scaled thumbnails of the given images into a single grid PNG, skipping unreadable files
+pub fn generate_contact_sheet<P: AsRef<std::path::Path>>(
+    paths: &[P],
+    columns: u32,
+    thumb_size: u32,
+) -> ImageResult<Vec<u8>> {
+    if columns == 0 || thumb_size == 0 {
+        return Err(ImageError::InvalidDimensions(
+            "Columns and thumbnail size must be greater than 0".to_string(),
+        ));
+    }
+
+    let thumbnails: Vec<image::RgbaImage> = paths
+        .iter()
+        .filter_map(|path| image::open(path).ok())
+        .map(|img| img.thumbnail(thumb_size, thumb_size).to_rgba8())
+        .collect();
+    if thumbnails.is_empty() {
+        return Err(ImageError::InvalidFormat(
+            "No decodable image for the contact sheet".to_string(),
+        ));
+    }
+
+    let count = thumbnails.len() as u32;
+    let grid_columns = columns.min(count);
+    let grid_rows = count.div_ceil(columns);
+    let mut sheet = image::RgbaImage::from_pixel(
+        grid_columns * thumb_size,
+        grid_rows * thumb_size,
+        image::Rgba([255, 255, 255, 255]),
+    );
+
+    for (index, thumbnail) in thumbnails.iter().enumerate() {
+        let index = index as u32;
+        // Vignette centrée dans sa cellule (les ratios non carrés laissent des marges)
+        let x = (index % columns) * thumb_size + (thumb_size - thumbnail.width()) / 2;
+        let y = (index / columns) * thumb_size + (thumb_size - thumbnail.height()) / 2;
+        image::imageops::overlay(&mut sheet, thumbnail, x as i64, y as i64);
+    }
+
+    encode_image(
+        &image::DynamicImage::ImageRgba8(sheet),
+        "png",
+        &ProcessingParams::new(100).with_lossless(),
+    )
+}
+
+/// Stretch each channel so its 1st/99th percentiles map to 0/255
+pub fn apply_auto_levels(data: &[u8], metadata: &ImageMetadata) -> ImageResult<Vec<u8>> {
+    let img = decode_image(data, &metadata.format)?;
+
+    match stretch_levels(&img) {
+        Some(leveled) => encode_image(
+            &leveled,
+            &metadata.format,
+            &ProcessingParams::new(100).with_lossless(),
+        ),
+        // L'image couvre déjà toute la plage : rien à faire
+        None => Ok(data.to_vec()),
+    }
+}
+
+/// Returns the stretched image, or None if every channel already spans the full range
+fn stretch_levels(img: &image::DynamicImage) -> Option<image::DynamicImage> {
+    let mut rgba_img = img.to_rgba8();
+    let histogram = Histogram::from_rgba(&rgba_img);
+
+    let levels: Vec<Option<(u8, u8)>> = [&histogram.red, &histogram.green, &histogram.blue]
+        .iter()
+        .map(|buckets| {
+            let low = Histogram::percentile(buckets, 0.01);
+            let high = Histogram::percentile(buckets, 0.99);
+            if high <= low || (low == 0 && high == 255) {
+                None
+            } else {
+                Some((low, high))
+            }
+        })
+        .collect();
+
+    if levels.iter().all(Option::is_none) {
+        return None;
+    }
+
+    for pixel in rgba_img.pixels_mut() {
+        for (channel, level) in levels.iter().enumerate() {
+            if let Some((low, high)) = level {
+                let value = pixel[channel].clamp(*low, *high) - low;
+                let stretched = value as f64 * 255.0 / (high - low) as f64;
+                pixel[channel] = stretched.round() as u8;
+            }
+        }
+    }
+
+    let leveled = image::DynamicImage::ImageRgba8(rgba_img);
+    Some(if img.color().has_alpha() {
+        leveled
+    } else {
+        image::DynamicImage::ImageRgb8(leveled.to_rgb8())
+    })
+}
+
+/// Stamp a PNG overlay onto the image with alpha blending at the given opacity
+pub fn apply_watermark(
+    base_data: &[u8],
+    base_metadata: &ImageMetadata,
+    overlay_png: &[u8],
+    position: WatermarkPosition,
+    opacity: f32,
+) -> ImageResult<Vec<u8>> {
+    let img = decode_image(base_data, &base_metadata.format)?;
+    let watermarked = blend_watermark(img, overlay_png, position, opacity)?;
+
+    encode_image(
+        &watermarked,
+        &base_metadata.format,
+        &ProcessingParams::new(100).with_lossless(),
+    )
+}
+
+/// Blend the overlay onto the image, shrinking it first if it does not fit
+fn blend_watermark(
+    img: image::DynamicImage,
+    overlay_png: &[u8],
+    position: WatermarkPosition,
+    opacity: f32,
+) -> ImageResult<image::DynamicImage> {
+    let mut overlay = image::load_from_memory_with_format(overlay_png, image::ImageFormat::Png)
+        .map_err(|e| ImageError::InvalidFormat(format!("Failed to decode watermark: {}", e)))?;
+
+    if overlay.width() > img.width() || overlay.height() > img.height() {
+        overlay = overlay.resize(
+            img.width(),
+            img.height(),
+            image::imageops::FilterType::Lanczos3,
+        );
+    }
+    let overlay = overlay.to_rgba8();
+
+    let (x_offset, y_offset) = match position {
+        WatermarkPosition::TopLeft => (0, 0),
+        WatermarkPosition::TopRight => (img.width() - overlay.width(), 0),
+        WatermarkPosition::BottomLeft => (0, img.height() - overlay.height()),
+        WatermarkPosition::BottomRight => (
+            img.width() - overlay.width(),
+            img.height() - overlay.height(),
+        ),
+        WatermarkPosition::Center => (
+            (img.width() - overlay.width()) / 2,
+            (img.height() - overlay.height()) / 2,
+        ),
+    };
+
+    let opacity = if opacity.is_finite() {
+        opacity.clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let had_alpha = img.color().has_alpha();
+    let mut base = img.to_rgba8();
+
+    for (x, y, overlay_pixel) in overlay.enumerate_pixels() {
+        let alpha = overlay_pixel[3] as f32 / 255.0 * opacity;
+        if alpha == 0.0 {
+            continue;
+        }
+
+        let base_pixel = base.get_pixel_mut(x + x_offset, y + y_offset);
+        for channel in 0..3 {
+            let blended =
+                base_pixel[channel] as f32 * (1.0 - alpha) + overlay_pixel[channel] as f32 * alpha;
+            base_pixel[channel] = blended.round() as u8;
+        }
+        let base_alpha = base_pixel[3] as f32;
+        base_pixel[3] = (base_alpha + (255.0 - base_alpha) * alpha).round() as u8;
+    }
+
+    let blended = image::DynamicImage::ImageRgba8(base);
+    Ok(if had_alpha {
+        blended
+    } else {
+        image::DynamicImage::ImageRgb8(blended.to_rgb8())
+    })
+}
+
 /// Apply sharpening to image
 pub fn apply_sharpening(
     data: &[u8],
@@ -286,7 +752,7 @@ pub fn apply_sharpening(
     Ok(result)
 }
 
-/// Progressive JPEG encoding simulation
+/// Re-encode a JPEG as a progressive JPEG
 pub fn create_progressive_jpeg(
     data: &[u8],
     metadata: &ImageMetadata,
@@ -298,29 +764,36 @@ pub fn create_progressive_jpeg(
         ));
     }
 
-    // Simulate progressive JPEG by creating multiple quality levels
-    let mut result = Vec::new();
-
-    // Add progressive scan headers (simplified simulation)
-    result.extend_from_slice(b"PROGRESSIVE_JPEG_HEADER");
-
-    // Add low quality scan
-    let low_quality_size = (data.len() as f64 * 0.3 * quality as f64 / 100.0) as usize;
-    result.extend_from_slice(&data[..low_quality_size.min(data.len())]);
-
-    // Add medium quality scan
-    let medium_quality_size = (data.len() as f64 * 0.6 * quality as f64 / 100.0) as usize;
-    if medium_quality_size > low_quality_size && medium_quality_size <= data.len() {
-        result.extend_from_slice(&data[low_quality_size..medium_quality_size]);
-    }
+    let img = image::load_from_memory_with_format(data, image::ImageFormat::Jpeg)
+        .map_err(|e| ImageError::InvalidFormat(format!("Failed to decode JPEG: {}", e)))?;
+    encode_progressive_jpeg(&img.to_rgb8(), quality)
+}
 
-    // Add final quality scan
-    let final_quality_size = (data.len() as f64 * quality as f64 / 100.0) as usize;
-    if final_quality_size > medium_quality_size && final_quality_size <= data.len() {
-        result.extend_from_slice(&data[medium_quality_size..final_quality_size]);
-    }
+/// Encode RGB pixels as a progressive JPEG with mozjpeg
+#[cfg(feature = "mozjpeg")]
+fn encode_progressive_jpeg(rgb_img: &image::RgbImage, quality: u8) -> ImageResult<Vec<u8>> {
+    let (width, height) = rgb_img.dimensions();
+
+    // mozjpeg signale ses erreurs par un panic, on les intercepte
+    std::panic::catch_unwind(|| -> std::io::Result<Vec<u8>> {
+        let mut compress = mozjpeg::Compress::new(mozjpeg::ColorSpace::JCS_RGB);
+        compress.set_size(width as usize, height as usize);
+        compress.set_quality(quality.clamp(1, 100) as f32);
+        compress.set_progressive_mode();
+
+        let mut started = compress.start_compress(Vec::new())?;
+        started.write_scanlines(rgb_img.as_raw())?;
+        started.finish()
+    })
+    .map_err(|_| ImageError::ProcessingError("Progressive JPEG encoder panicked".to_string()))?
+    .map_err(|e| ImageError::ProcessingError(format!("Progressive JPEG encoding failed: {}", e)))
+}
 
-    Ok(result)
+#[cfg(not(feature = "mozjpeg"))]
+fn encode_progressive_jpeg(_rgb_img: &image::RgbImage, _quality: u8) -> ImageResult<Vec<u8>> {
+    Err(ImageError::ProcessingError(
+        "Progressive JPEG encoding requires the mozjpeg feature".to_string(),
+    ))
 }
 
 #[cfg(test)]
@@ -381,31 +854,226 @@ mod tests {
         assert_eq!(gray_result.len(), 4); // 4 pixels * 1 byte
     }
 
+    #[test]
+    fn test_flatten_half_transparent_red() {
+        let img = image::DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
+            1,
+            1,
+            image::Rgba([255, 0, 0, 128]),
+        ));
+        assert_eq!(
+            flatten_alpha(&img, [255, 255, 255]).get_pixel(0, 0).0,
+            [255, 127, 127]
+        );
+        assert_eq!(
+            flatten_alpha(&img, [0, 0, 0]).get_pixel(0, 0).0,
+            [128, 0, 0]
+        );
+
+        let dims = Dimensions::new(1, 1).unwrap();
+        let rgb = convert_color_space(&[255, 0, 0, 128], ColorSpace::RGBA, ColorSpace::RGB, &dims)
+            .unwrap();
+        assert_eq!(rgb, vec![255, 127, 127]);
+    }
+
+    fn encode_test_image(img: image::DynamicImage, format: image::ImageFormat) -> Vec<u8> {
+        let mut data = Vec::new();
+        img.write_to(&mut std::io::Cursor::new(&mut data), format)
+            .unwrap();
+        data
+    }
+
     #[test]
     fn test_optimize_for_web() {
+        // Opaque RGBA image: the alpha channel should be dropped
+        let img = image::RgbaImage::from_pixel(100, 100, image::Rgba([128, 64, 32, 255]));
+        let data = encode_test_image(
+            image::DynamicImage::ImageRgba8(img),
+            image::ImageFormat::Png,
+        );
+
         let dims = Dimensions::new(100, 100).unwrap();
-        let metadata = ImageMetadata::new("png".to_string(), dims, ColorSpace::RGB, 30000);
+        let metadata =
+            ImageMetadata::new("png".to_string(), dims, ColorSpace::RGBA, data.len() as u64);
+        let params = ProcessingParams::new(80)
+            .with_resize(Dimensions::new(50, 50).unwrap())
+            .with_output_format("jpeg");
+
+        let result = optimize_for_web(&data, &metadata, &params).unwrap();
+        assert_eq!(result.processed_size, result.data.len());
+        assert!(result
+            .operations_applied
+            .contains(&"Remove unused alpha channel".to_string()));
 
-        let data = vec![128u8; 30000];
-        let params = ProcessingParams::new(80);
+        let decoded = image::load_from_memory(&result.data).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (50, 50));
+    }
 
+    #[test]
+    fn test_rotate_90() {
+        // 100x50 black image with a red top-left pixel
+        let mut img = image::RgbImage::from_pixel(100, 50, image::Rgb([0, 0, 0]));
+        img.put_pixel(0, 0, image::Rgb([255, 0, 0]));
+        let data = encode_test_image(image::DynamicImage::ImageRgb8(img), image::ImageFormat::Png);
+        let metadata = ImageMetadata::new(
+            "png".to_string(),
+            Dimensions::new(100, 50).unwrap(),
+            ColorSpace::RGB,
+            data.len() as u64,
+        );
+
+        let params = ProcessingParams::new(80).with_transform(Transform::Rotate90);
         let result = optimize_for_web(&data, &metadata, &params).unwrap();
-        assert!(result.processed_size <= result.original_size);
-        assert!(result.size_reduction_percent() >= 0.0);
-        assert!(!result.operations_applied.is_empty());
+        assert_eq!(result.dimensions, Dimensions::new(50, 100).unwrap());
+
+        // Clockwise rotation moves the top-left corner to the top-right
+        let rotated = image::load_from_memory(&result.data).unwrap().to_rgb8();
+        assert_eq!(rotated.dimensions(), (50, 100));
+        assert_eq!(rotated.get_pixel(49, 0), &image::Rgb([255, 0, 0]));
+        assert_eq!(rotated.get_pixel(0, 0), &image::Rgb([0, 0, 0]));
+    }
+
+    #[test]
+    fn test_flip_transforms() {
+        let mut img = image::RgbImage::from_pixel(4, 2, image::Rgb([0, 0, 0]));
+        img.put_pixel(0, 0, image::Rgb([255, 0, 0]));
+        let img = image::DynamicImage::ImageRgb8(img);
+
+        let flipped = Transform::FlipHorizontal.apply(&img).to_rgb8();
+        assert_eq!(flipped.get_pixel(3, 0), &image::Rgb([255, 0, 0]));
+
+        let flipped = Transform::FlipVertical.apply(&img).to_rgb8();
+        assert_eq!(flipped.get_pixel(0, 1), &image::Rgb([255, 0, 0]));
+
+        let rotated = Transform::Rotate180.apply(&img);
+        assert_eq!((rotated.width(), rotated.height()), (4, 2));
+        assert!(!rotated.color().has_alpha());
     }
 
     #[test]
     fn test_auto_crop() {
+        // 40x40 colored square centered on a white 100x100 background
+        let img = image::RgbImage::from_fn(100, 100, |x, y| {
+            if (30..70).contains(&x) && (30..70).contains(&y) {
+                image::Rgb([200, 30, 30])
+            } else {
+                image::Rgb([255, 255, 255])
+            }
+        });
+        let data = encode_test_image(image::DynamicImage::ImageRgb8(img), image::ImageFormat::Png);
         let dims = Dimensions::new(100, 100).unwrap();
-        let metadata = ImageMetadata::new("png".to_string(), dims, ColorSpace::RGB, 30000);
+        let metadata =
+            ImageMetadata::new("png".to_string(), dims, ColorSpace::RGB, data.len() as u64);
+
+        let (cropped_data, new_dims) = auto_crop(&data, &metadata, 10).unwrap();
+        assert_eq!((new_dims.width, new_dims.height), (40, 40));
+
+        let decoded = image::load_from_memory(&cropped_data).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (40, 40));
+    }
+
+    #[test]
+    fn test_auto_crop_uniform_image() {
+        let img = image::RgbImage::from_pixel(20, 10, image::Rgb([255, 255, 255]));
+        let data = encode_test_image(image::DynamicImage::ImageRgb8(img), image::ImageFormat::Png);
+        let dims = Dimensions::new(20, 10).unwrap();
+        let metadata =
+            ImageMetadata::new("png".to_string(), dims, ColorSpace::RGB, data.len() as u64);
+
+        let (cropped_data, new_dims) = auto_crop(&data, &metadata, 0).unwrap();
+        assert_eq!(cropped_data, data);
+        assert_eq!((new_dims.width, new_dims.height), (20, 10));
+    }
+
+    #[test]
+    fn test_apply_watermark() {
+        let base = image::RgbImage::from_pixel(100, 100, image::Rgb([255, 255, 255]));
+        let data = encode_test_image(
+            image::DynamicImage::ImageRgb8(base),
+            image::ImageFormat::Png,
+        );
+        let metadata = ImageMetadata::new(
+            "png".to_string(),
+            Dimensions::new(100, 100).unwrap(),
+            ColorSpace::RGB,
+            data.len() as u64,
+        );
+        let red_square = encode_test_image(
+            image::DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
+                10,
+                10,
+                image::Rgba([255, 0, 0, 255]),
+            )),
+            image::ImageFormat::Png,
+        );
+
+        let result = apply_watermark(
+            &data,
+            &metadata,
+            &red_square,
+            WatermarkPosition::BottomRight,
+            0.5,
+        )
+        .unwrap();
+        let stamped = image::load_from_memory(&result).unwrap().to_rgb8();
+
+        assert_eq!(stamped.get_pixel(95, 95), &image::Rgb([255, 128, 128]));
+        assert_eq!(stamped.get_pixel(89, 89), &image::Rgb([255, 255, 255]));
+        assert_eq!(stamped.get_pixel(5, 5), &image::Rgb([255, 255, 255]));
+
+        // An overlay larger than the base is shrunk to fit
+        let params = ProcessingParams::new(80).with_watermark(
+            encode_test_image(
+                image::DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
+                    300,
+                    300,
+                    image::Rgba([255, 0, 0, 255]),
+                )),
+                image::ImageFormat::Png,
+            ),
+            WatermarkPosition::Center,
+            1.0,
+        );
+        let result = optimize_for_web(&data, &metadata, &params).unwrap();
+        let stamped = image::load_from_memory(&result.data).unwrap().to_rgb8();
+        assert_eq!(stamped.get_pixel(0, 0), &image::Rgb([255, 0, 0]));
+    }
 
-        let data = vec![128u8; 30000];
-        let (cropped_data, new_dims) = auto_crop(&data, &metadata).unwrap();
+    #[test]
+    fn test_apply_auto_levels() {
+        // Washed-out gradient: every channel stays within 100..=155
+        let img = image::RgbImage::from_fn(56, 56, |x, y| {
+            image::Rgb([100 + x as u8, 100 + y as u8, 100 + ((x + y) / 2) as u8])
+        });
+        let data = encode_test_image(image::DynamicImage::ImageRgb8(img), image::ImageFormat::Png);
+        let metadata = ImageMetadata::new(
+            "png".to_string(),
+            Dimensions::new(56, 56).unwrap(),
+            ColorSpace::RGB,
+            data.len() as u64,
+        );
 
-        assert!(new_dims.width < metadata.dimensions.width);
-        assert!(new_dims.height < metadata.dimensions.height);
-        assert!(cropped_data.len() < data.len());
+        let channel_range = |data: &[u8]| {
+            let img = image::load_from_memory(data).unwrap().to_rgb8();
+            let values: Vec<u8> = img.pixels().flat_map(|p| p.0).collect();
+            (*values.iter().min().unwrap(), *values.iter().max().unwrap())
+        };
+
+        let leveled = apply_auto_levels(&data, &metadata).unwrap();
+        let (input_min, input_max) = channel_range(&data);
+        let (output_min, output_max) = channel_range(&leveled);
+        assert!(output_min < input_min);
+        assert!(output_max > input_max);
+        assert_eq!((output_min, output_max), (0, 255));
+
+        // Already full range: returned unchanged
+        let full =
+            image::RgbImage::from_fn(16, 16, |x, y| image::Rgb([((x + y) % 2) as u8 * 255; 3]));
+        let full_data = encode_test_image(
+            image::DynamicImage::ImageRgb8(full),
+            image::ImageFormat::Png,
+        );
+        assert_eq!(apply_auto_levels(&full_data, &metadata).unwrap(), full_data);
     }
 
     #[test]
@@ -423,4 +1091,65 @@ mod tests {
         // Invalid strength should error
         assert!(apply_sharpening(&data, 1.5).is_err());
     }
+
+    #[cfg(feature = "mozjpeg")]
+    #[test]
+    fn test_create_progressive_jpeg() {
+        let img =
+            image::RgbImage::from_fn(64, 48, |x, y| image::Rgb([x as u8 * 4, y as u8 * 5, 90]));
+        let mut jpeg_data = Vec::new();
+        image::DynamicImage::ImageRgb8(img)
+            .write_to(
+                &mut std::io::Cursor::new(&mut jpeg_data),
+                image::ImageFormat::Jpeg,
+            )
+            .unwrap();
+
+        let dims = Dimensions::new(64, 48).unwrap();
+        let metadata = ImageMetadata::new(
+            "jpeg".to_string(),
+            dims,
+            ColorSpace::RGB,
+            jpeg_data.len() as u64,
+        );
+
+        let result = create_progressive_jpeg(&jpeg_data, &metadata, 80).unwrap();
+        assert_eq!(&result[..2], &[0xFF, 0xD8]);
+        // SOF2 marker identifies a progressive JPEG
+        assert!(result.windows(2).any(|w| w == [0xFF, 0xC2]));
+
+        let decoded = image::load_from_memory(&result).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (64, 48));
+
+        // Non-JPEG inputs are still rejected
+        let png_metadata = ImageMetadata::new(
+            "png".to_string(),
+            Dimensions::new(64, 48).unwrap(),
+            ColorSpace::RGB,
+            0,
+        );
+        assert!(create_progressive_jpeg(&jpeg_data, &png_metadata, 80).is_err());
+    }
+
+    #[test]
+    fn test_contact_sheet_grid() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut paths: Vec<_> = (0..4)
+            .map(|index| {
+                let path = temp_dir.path().join(format!("image_{}.png", index));
+                image::RgbImage::from_pixel(120, 80, image::Rgb([index * 60, 100, 200]))
+                    .save(&path)
+                    .unwrap();
+                path
+            })
+            .collect();
+        let not_an_image = temp_dir.path().join("notes.png");
+        std::fs::write(&not_an_image, b"not an image").unwrap();
+        paths.insert(1, not_an_image);
+
+        let sheet = generate_contact_sheet(&paths, 2, 64).unwrap();
+
+        let img = image::load_from_memory(&sheet).unwrap();
+        assert_eq!((img.width(), img.height()), (128, 128));
+    }
 }
@@ -8,28 +8,57 @@ pub mod shared;
 
 // Compression domain exports
 pub use compression::{
+    abandoned_workers,
     calculate_confidence,
+    calibrate_predictions,
+    calibration_stats,
     compress_batch_files,
+    compress_batch_files_parallel,
+    compress_bytes,
+    compress_bytes_with_cache,
+    compress_bytes_with_timeout,
     // Core functions
     compress_file_to_file,
+    compress_file_to_file_with_timeout,
+    compress_to_formats,
+    configure_global_cache,
     create_compression_stat,
     create_prediction_query,
     create_stat,
     estimate_compression,
+    estimate_with_fallback,
     get_size_range,
     high_quality_settings,
+    is_lossy_encoding,
+    list_presets,
     max_compression_settings,
+    plan_batch_waves,
+    preset_by_name,
+    quality_size_curve,
+    record_calibration,
+    run_with_timeout,
+    wait_for_abandoned_workers,
     // Convenience functions
     web_optimized_settings,
+    with_decode_progress,
+    BatchEstimation,
+    CompressionCache,
     CompressionError,
     CompressionOutput,
     CompressionPredictionService,
+    CompressionPreset,
     CompressionResult,
     CompressionSettings,
     CompressionStat,
     EstimationQuery,
     EstimationResult,
+    FileEstimation,
+    ImageTypeCompression,
+    JpegEncoder,
     OutputFormat,
+    OversizePolicy,
+    PredictionAccuracy,
+    PredictionBias,
     SqliteStatsStore,
     StatsStore,
 };
@@ -40,24 +69,37 @@ pub use image::{
     analyze_compression_potential,
     // Convenience functions
     analyze_image,
+    apply_auto_levels,
     apply_sharpening,
+    apply_watermark,
     assess_image_quality,
     auto_crop,
     batch_process_images,
     classify_image_type,
     comprehensive_analysis,
+    compute_blurhash,
+    compute_histogram,
     convert_color_space,
+    estimate_jpeg_quality,
     // Core functions
     extract_metadata,
+    flatten_alpha,
+    generate_contact_sheet,
     get_compression_recommendations,
+    is_likely_optimized,
+    is_supported_input_format,
     optimize_for_web,
     prepare_for_web,
+    recommend_webp_mode,
     resize_image,
+    smart_crop_resize,
     smart_resize,
     ColorAnalysis,
     ColorSpace,
     CompressionPotential,
+    CropStrategy,
     Dimensions,
+    Histogram,
     ImageError,
     ImageMetadata,
     ImageResult,
@@ -65,24 +107,39 @@ pub use image::{
     ProcessingParams,
     ProcessingResult,
     QualityAssessment,
+    ResampleFilter,
     RiskLevel,
+    Transform,
+    Watermark,
+    WatermarkPosition,
 };
 
 // File domain exports
 pub use file::{
+    atomic_write,
     batch_copy_files,
+    batch_copy_files_unique,
+    batch_move_files,
+    check_free_space,
     cleanup_temp_files,
+    cleanup_temp_files_older_than,
     copy_file,
+    copy_file_unique,
+    copy_file_unique_into,
     create_backup,
     delete_file,
     file_exists,
+    find_duplicates,
     format_file_size,
     // Path utilities
     generate_output_path,
     get_file_extension,
     get_file_info,
     get_temp_file_path,
+    is_app_temp_file,
     is_supported_image_file,
+    link_or_copy_file,
+    mirror_output_dir,
     move_file,
     process_image_files,
     // Core functions
@@ -98,6 +155,8 @@ pub use file::{
     FileResult,
     OperationType,
     PathUtils,
+    TempCleanupScheduler,
+    TEMP_FILE_PREFIXES,
 };
 
 // Shared domain exports
@@ -107,10 +166,16 @@ pub use shared::{
     cleanup,
     compression_completed_event,
     compression_failed_event,
+    configuration_changed_event,
     content_equal,
     content_id,
+    content_sha256,
     current_timestamp,
+    default_config_path,
     error_event,
+    execute_with_recovery,
+    execute_with_recovery_and_sleep,
+    expand_home,
     // Event creators
     file_processed_event,
     format_bytes,
@@ -126,7 +191,9 @@ pub use shared::{
     normalize_extension,
     sanitize_filename,
     simple_hash,
+    temp_files_cleaned_up_event,
     truncate_with_ellipsis,
+    unique_suffix,
     validate_dimensions,
     validate_format,
     validate_path_depth,
@@ -134,7 +201,9 @@ pub use shared::{
     AppConfig,
     AppState,
     CompressionConfig,
+    ConfigFieldError,
     ConfigManager,
+    ConfigWatchGuard,
     ConsoleEventListener,
     DomainError,
     DomainEvent,
@@ -1,5 +1,9 @@
+use crate::domain::compression::{OutputFormat, OversizePolicy};
 use crate::domain::shared::error::{DomainError, DomainResult};
+use crate::domain::shared::events::{configuration_changed_event, DomainEvent};
+use crate::domain::shared::utils::path::expand_home;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 /// Application configuration
@@ -14,14 +18,36 @@ pub struct AppConfig {
     pub security: SecurityConfig,
 }
 
+/// A configuration setting rejected by validation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfigFieldError {
+    pub field: String, // Dotted path, e.g. "compression.default_quality"
+    pub message: String,
+}
+
+impl ConfigFieldError {
+    fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
 /// Compression-specific configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompressionConfig {
     pub default_quality: u8,
     pub enable_lossless_fallback: bool,
     pub max_dimensions: (u32, u32),
+    /// Behavior for images exceeding `max_dimensions`
+    #[serde(default)]
+    pub on_oversize: OversizePolicy,
     pub preserve_metadata: bool,
     pub auto_optimize: bool,
+    /// Per-format default quality, falls back to `default_quality` when absent
+    #[serde(default)]
+    pub format_qualities: BTreeMap<OutputFormat, u8>,
 }
 
 /// Performance configuration
@@ -30,7 +56,11 @@ pub struct PerformanceConfig {
     pub max_concurrent_operations: usize,
     pub memory_limit_mb: u64,
     pub disk_cache_size_mb: u64,
+    #[serde(default = "default_memory_cache_size_mb")]
+    pub memory_cache_size_mb: u64, // In-memory cache of compressed outputs, 0 = disabled
     pub enable_gpu_acceleration: bool,
+    #[serde(default = "default_per_image_timeout_ms")]
+    pub per_image_timeout_ms: u64, // 0 = no timeout
 }
 
 /// Security configuration
@@ -66,9 +96,13 @@ impl Default for CompressionConfig {
         Self {
             default_quality: 80,
             enable_lossless_fallback: true,
-            max_dimensions: (4096, 4096),
+            // Garde-fou mémoire, pas un redimensionnement : les photos 24/48 Mpx passent
+            max_dimensions: (16384, 16384),
+            on_oversize: OversizePolicy::Downscale,
             preserve_metadata: false,
             auto_optimize: true,
+            // PNG est sans perte, la qualité n'a pas d'effet
+            format_qualities: BTreeMap::from([(OutputFormat::Jpeg, 85), (OutputFormat::WebP, 80)]),
         }
     }
 }
@@ -77,9 +111,11 @@ impl Default for PerformanceConfig {
     fn default() -> Self {
         Self {
             max_concurrent_operations: num_cpus::get().max(4),
-            memory_limit_mb: 1024,          // 1GB
-            disk_cache_size_mb: 512,        // 512MB
+            memory_limit_mb: 1024,   // 1GB
+            disk_cache_size_mb: 512, // 512MB
+            memory_cache_size_mb: default_memory_cache_size_mb(),
             enable_gpu_acceleration: false, // Conservative default
+            per_image_timeout_ms: default_per_image_timeout_ms(),
         }
     }
 }
@@ -143,33 +179,41 @@ impl AppConfig {
 
     /// Validate configuration
     pub fn validate(&self) -> DomainResult<()> {
-        // Validate temp directory
+        match self.validation_errors().into_iter().next() {
+            Some(error) => Err(DomainError::Configuration(error.message)),
+            None => Ok(()),
+        }
+    }
+
+    /// List every invalid setting, keyed by its dotted field path
+    pub fn validation_errors(&self) -> Vec<ConfigFieldError> {
+        let mut errors = Vec::new();
+
         if self.temp_dir.is_empty() {
-            return Err(DomainError::Configuration(
-                "Temp directory cannot be empty".to_string(),
+            errors.push(ConfigFieldError::new(
+                "temp_dir",
+                "Temp directory cannot be empty",
             ));
         }
 
-        // Validate file size limit
         if self.max_file_size == 0 {
-            return Err(DomainError::Configuration(
-                "Max file size must be greater than 0".to_string(),
+            errors.push(ConfigFieldError::new(
+                "max_file_size",
+                "Max file size must be greater than 0",
             ));
         }
 
-        // Validate supported formats
         if self.supported_formats.is_empty() {
-            return Err(DomainError::Configuration(
-                "At least one supported format must be specified".to_string(),
+            errors.push(ConfigFieldError::new(
+                "supported_formats",
+                "At least one supported format must be specified",
             ));
         }
 
-        // Validate compression config
-        self.compression.validate()?;
-        self.performance.validate()?;
-        self.security.validate()?;
-
-        Ok(())
+        errors.extend(self.compression.validation_errors());
+        errors.extend(self.performance.validation_errors());
+        errors.extend(self.security.validation_errors());
+        errors
     }
 
     /// Get temporary directory path
@@ -229,53 +273,127 @@ impl AppConfig {
 }
 
 impl CompressionConfig {
-    fn validate(&self) -> DomainResult<()> {
+    /// Get the default quality for an output format
+    pub fn quality_for(&self, format: OutputFormat) -> u8 {
+        self.format_qualities
+            .get(&format)
+            .copied()
+            .unwrap_or(self.default_quality)
+    }
+
+    /// Use the requested quality, or the format default when none is given
+    pub fn resolve_quality(&self, requested: Option<u8>, format: OutputFormat) -> u8 {
+        requested.unwrap_or_else(|| self.quality_for(format))
+    }
+
+    fn validation_errors(&self) -> Vec<ConfigFieldError> {
+        let mut errors = Vec::new();
+
         if !(1..=100).contains(&self.default_quality) {
-            return Err(DomainError::Configuration(
-                "Default quality must be between 1 and 100".to_string(),
+            errors.push(ConfigFieldError::new(
+                "compression.default_quality",
+                "Default quality must be between 1 and 100",
             ));
         }
 
+        for (format, quality) in &self.format_qualities {
+            if !(1..=100).contains(quality) {
+                errors.push(ConfigFieldError::new(
+                    format!("compression.format_qualities.{}", format.extension()),
+                    format!("Default quality for {} must be between 1 and 100", format),
+                ));
+            }
+        }
+
         if self.max_dimensions.0 == 0 || self.max_dimensions.1 == 0 {
-            return Err(DomainError::Configuration(
-                "Max dimensions must be greater than 0".to_string(),
+            errors.push(ConfigFieldError::new(
+                "compression.max_dimensions",
+                "Max dimensions must be greater than 0",
             ));
         }
 
-        Ok(())
+        errors
     }
 }
 
 impl PerformanceConfig {
-    fn validate(&self) -> DomainResult<()> {
+    fn validation_errors(&self) -> Vec<ConfigFieldError> {
+        let mut errors = Vec::new();
+
         if self.max_concurrent_operations == 0 {
-            return Err(DomainError::Configuration(
-                "Max concurrent operations must be greater than 0".to_string(),
+            errors.push(ConfigFieldError::new(
+                "performance.max_concurrent_operations",
+                "Max concurrent operations must be greater than 0",
             ));
         }
 
         if self.memory_limit_mb == 0 {
-            return Err(DomainError::Configuration(
-                "Memory limit must be greater than 0".to_string(),
+            errors.push(ConfigFieldError::new(
+                "performance.memory_limit_mb",
+                "Memory limit must be greater than 0",
             ));
         }
 
-        Ok(())
+        errors
     }
 }
 
 impl SecurityConfig {
-    fn validate(&self) -> DomainResult<()> {
+    /// Allowed roots with `~` expanded to the home directory
+    pub fn allowed_roots(&self) -> Vec<PathBuf> {
+        self.allowed_paths.iter().map(expand_home).collect()
+    }
+
+    /// Blocked roots with `~` expanded to the home directory
+    pub fn blocked_roots(&self) -> Vec<PathBuf> {
+        self.blocked_paths.iter().map(expand_home).collect()
+    }
+
+    fn validation_errors(&self) -> Vec<ConfigFieldError> {
+        let mut errors = Vec::new();
+
+        // Une racine relative ne correspondrait jamais à un chemin absolu
+        for (field, roots) in [
+            ("security.allowed_paths", self.allowed_roots()),
+            ("security.blocked_paths", self.blocked_roots()),
+        ] {
+            if let Some(root) = roots.iter().find(|root| !root.is_absolute()) {
+                errors.push(ConfigFieldError::new(
+                    field,
+                    format!("Path must be absolute or start with ~: {}", root.display()),
+                ));
+            }
+        }
+
         if self.max_path_depth == 0 {
-            return Err(DomainError::Configuration(
-                "Max path depth must be greater than 0".to_string(),
+            errors.push(ConfigFieldError::new(
+                "security.max_path_depth",
+                "Max path depth must be greater than 0",
             ));
         }
 
-        Ok(())
+        errors
     }
 }
 
+/// Default location of the persisted configuration file
+pub fn default_config_path() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("plume")
+        .join("config.json")
+}
+
+/// Outputs kept in memory to skip re-encoding identical inputs
+fn default_memory_cache_size_mb() -> u64 {
+    64
+}
+
+/// Time allowed to compress a single image before it is reported as failed
+fn default_per_image_timeout_ms() -> u64 {
+    60_000
+}
+
 /// Get default temporary directory based on OS
 fn get_default_temp_dir() -> String {
     std::env::temp_dir()
@@ -321,6 +439,106 @@ impl ConfigManager {
         self.config = new_config;
         Ok(())
     }
+
+    /// Reload from file and return one event per changed top-level setting
+    ///
+    /// Invalid files are rejected and the last good configuration is kept.
+    pub fn reload_with_changes<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+    ) -> DomainResult<Vec<DomainEvent>> {
+        let old_config = self.config.clone();
+        self.reload_from_file(path)?;
+        Ok(config_changes(&old_config, &self.config))
+    }
+}
+
+/// Call `on_change` with the config path whenever the file is written
+///
+/// Watching stops when the returned guard is dropped.
+pub fn watch_config_file<P, F>(path: P, on_change: F) -> DomainResult<ConfigWatchGuard>
+where
+    P: AsRef<Path>,
+    F: Fn(&Path) + Send + 'static,
+{
+    use notify::{RecursiveMode, Watcher};
+
+    let config_path = path.as_ref().to_path_buf();
+    let file_name = config_path.file_name().map(|name| name.to_os_string());
+    // On surveille le dossier parent : les éditeurs remplacent souvent le fichier
+    let watch_dir = config_path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."))
+        .to_path_buf();
+
+    let handler_path = config_path.clone();
+    let mut watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+        let event = match result {
+            Ok(event) => event,
+            Err(e) => {
+                log::warn!("Config watcher error: {}", e);
+                return;
+            }
+        };
+
+        let touches_config = event
+            .paths
+            .iter()
+            .any(|changed| changed.file_name() == file_name.as_deref());
+        if touches_config && !event.kind.is_access() && !event.kind.is_remove() {
+            on_change(&handler_path);
+        }
+    })
+    .map_err(|e| DomainError::Configuration(format!("Failed to create config watcher: {}", e)))?;
+
+    watcher
+        .watch(&watch_dir, RecursiveMode::NonRecursive)
+        .map_err(|e| DomainError::Configuration(format!("Failed to watch config file: {}", e)))?;
+
+    Ok(ConfigWatchGuard {
+        _watcher: watcher,
+        path: config_path,
+    })
+}
+
+/// Keeps the config watcher alive; watching stops on drop
+pub struct ConfigWatchGuard {
+    _watcher: notify::RecommendedWatcher,
+    path: PathBuf,
+}
+
+impl ConfigWatchGuard {
+    /// Path of the watched config file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Build a ConfigurationChanged event for each top-level setting that differs
+pub(crate) fn config_changes(old_config: &AppConfig, new_config: &AppConfig) -> Vec<DomainEvent> {
+    let old_value = serde_json::to_value(old_config).unwrap_or_default();
+    let new_value = serde_json::to_value(new_config).unwrap_or_default();
+
+    let (old_fields, new_fields) = match (old_value.as_object(), new_value.as_object()) {
+        (Some(old_fields), Some(new_fields)) => (old_fields, new_fields),
+        _ => return Vec::new(),
+    };
+
+    new_fields
+        .iter()
+        .filter(|(name, value)| old_fields.get(*name) != Some(*value))
+        .map(|(name, value)| {
+            configuration_changed_event(
+                name.clone(),
+                old_fields
+                    .get(name)
+                    .map(|old| old.to_string())
+                    .unwrap_or_default(),
+                value.to_string(),
+            )
+        })
+        .collect()
 }
 
 impl Default for ConfigManager {
@@ -341,6 +559,10 @@ mod tests {
         assert!(!config.temp_dir.is_empty());
         assert!(config.max_file_size > 0);
         assert!(!config.supported_formats.is_empty());
+
+        // Une photo 48 Mpx (8000×6000) garde sa résolution
+        let (max_width, max_height) = config.compression.max_dimensions;
+        assert!(max_width >= 8000 && max_height >= 8000);
     }
 
     #[test]
@@ -365,6 +587,99 @@ mod tests {
         assert!(config.validate().is_err());
     }
 
+    #[test]
+    fn test_validation_errors_name_fields() {
+        let mut config = AppConfig::default();
+        assert!(config.validation_errors().is_empty());
+
+        config.max_file_size = 0;
+        config.performance.memory_limit_mb = 0;
+        let fields: Vec<_> = config
+            .validation_errors()
+            .into_iter()
+            .map(|error| error.field)
+            .collect();
+        assert_eq!(fields, vec!["max_file_size", "performance.memory_limit_mb"]);
+    }
+
+    #[test]
+    fn test_security_roots_expand_home() {
+        let Some(home) = dirs::home_dir() else {
+            return;
+        };
+        let config = SecurityConfig::default();
+        let documents = home.join("Documents");
+        assert!(config.allowed_roots().contains(&documents));
+        assert!(config.validation_errors().is_empty());
+
+        if documents.is_dir() {
+            let file = tempfile::NamedTempFile::new_in(&documents).unwrap();
+            assert!(config
+                .allowed_roots()
+                .iter()
+                .any(|root| file.path().starts_with(root)));
+        }
+
+        let relative = SecurityConfig {
+            allowed_paths: vec!["Pictures".to_string()],
+            ..SecurityConfig::default()
+        };
+        assert_eq!(
+            relative.validation_errors()[0].field,
+            "security.allowed_paths"
+        );
+    }
+
+    #[test]
+    fn test_format_quality_validation() {
+        let mut config = AppConfig::default();
+        assert!(config.validate().is_ok());
+
+        config
+            .compression
+            .format_qualities
+            .insert(OutputFormat::Jpeg, 0);
+        assert!(config.validate().is_err());
+
+        config
+            .compression
+            .format_qualities
+            .insert(OutputFormat::Jpeg, 101);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_format_quality_defaults() {
+        let config = CompressionConfig::default();
+
+        assert_eq!(config.resolve_quality(None, OutputFormat::Jpeg), 85);
+        assert_eq!(config.resolve_quality(None, OutputFormat::WebP), 80);
+        assert_eq!(
+            config.resolve_quality(None, OutputFormat::Png),
+            config.default_quality
+        );
+
+        // Explicit quality always wins
+        assert_eq!(config.resolve_quality(Some(42), OutputFormat::Jpeg), 42);
+    }
+
+    #[test]
+    fn test_format_qualities_missing_from_file() {
+        // Older config files without per-format qualities still load
+        let mut value = serde_json::to_value(AppConfig::default()).unwrap();
+        value["compression"]
+            .as_object_mut()
+            .unwrap()
+            .remove("format_qualities");
+
+        let config: AppConfig = serde_json::from_value(value).unwrap();
+        assert!(config.compression.format_qualities.is_empty());
+        assert_eq!(
+            config.compression.quality_for(OutputFormat::Jpeg),
+            config.compression.default_quality
+        );
+    }
+
     #[test]
     fn test_format_support() {
         let config = AppConfig::default();
@@ -430,4 +745,31 @@ mod tests {
         // Should still have the previous valid config
         assert_eq!(manager.get_config().max_file_size, 2048);
     }
+
+    #[test]
+    fn test_reload_with_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.json");
+        let mut manager = ConfigManager::new();
+
+        let updated = AppConfig {
+            max_file_size: 4096,
+            ..Default::default()
+        };
+        updated.save_to_file(&config_path).unwrap();
+
+        let events = manager.reload_with_changes(&config_path).unwrap();
+        assert_eq!(manager.get_config().max_file_size, 4096);
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            &events[0].payload,
+            crate::domain::shared::events::EventPayload::ConfigurationChanged { setting_name, .. }
+                if setting_name == "max_file_size"
+        ));
+
+        // Invalid edits keep the last good config
+        std::fs::write(&config_path, "{ not json").unwrap();
+        assert!(manager.reload_with_changes(&config_path).is_err());
+        assert_eq!(manager.get_config().max_file_size, 4096);
+    }
 }
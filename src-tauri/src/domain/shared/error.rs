@@ -1,4 +1,5 @@
 use std::fmt;
+use std::time::Duration;
 
 /// Common domain errors that can occur across all domains
 #[derive(Debug, Clone)]
@@ -56,6 +57,9 @@ impl From<crate::domain::file::FileError> for DomainError {
                 DomainError::InvalidInput(format!("Unsupported format: {}", format))
             }
             crate::domain::file::FileError::IoError(msg) => DomainError::Internal(msg),
+            err @ crate::domain::file::FileError::InsufficientSpace(_, _) => {
+                DomainError::ResourceLimit(err.to_string())
+            }
         }
     }
 }
@@ -101,6 +105,18 @@ impl From<crate::domain::compression::CompressionError> for DomainError {
             crate::domain::compression::CompressionError::ProcessingError(msg) => {
                 DomainError::Internal(msg)
             }
+            err @ crate::domain::compression::CompressionError::MemoryLimitExceeded(_, _) => {
+                DomainError::ResourceLimit(err.to_string())
+            }
+            err @ crate::domain::compression::CompressionError::DecodeFailed(_) => {
+                DomainError::InvalidInput(err.to_string())
+            }
+            crate::domain::compression::CompressionError::EncodeFailed(msg) => {
+                DomainError::Internal(msg)
+            }
+            err @ crate::domain::compression::CompressionError::InvalidDimensions(_) => {
+                DomainError::InvalidInput(err.to_string())
+            }
         }
     }
 }
@@ -137,6 +153,45 @@ pub fn get_recovery_strategy(error: &DomainError) -> ErrorRecovery {
     }
 }
 
+/// Run an operation, retrying it according to its error recovery strategy
+///
+/// Only `Retry` strategies are handled here; for `Fallback`, `Skip` and `Abort`
+/// the error is returned so the caller can apply the appropriate behavior.
+pub fn execute_with_recovery<F, T>(op: F) -> DomainResult<T>
+where
+    F: FnMut() -> DomainResult<T>,
+{
+    execute_with_recovery_and_sleep(op, std::thread::sleep)
+}
+
+/// Same as `execute_with_recovery` with an injectable sleep function
+pub fn execute_with_recovery_and_sleep<F, T, S>(mut op: F, mut sleep: S) -> DomainResult<T>
+where
+    F: FnMut() -> DomainResult<T>,
+    S: FnMut(Duration),
+{
+    let mut attempt: u32 = 1;
+
+    loop {
+        let error = match op() {
+            Ok(value) => return Ok(value),
+            Err(error) => error,
+        };
+
+        match get_recovery_strategy(&error) {
+            ErrorRecovery::Retry {
+                max_attempts,
+                backoff_ms,
+            } if attempt < max_attempts => {
+                // Backoff linéaire : attente croissante à chaque tentative
+                sleep(Duration::from_millis(backoff_ms * attempt as u64));
+                attempt += 1;
+            }
+            _ => return Err(error),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -171,6 +226,54 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_execute_with_recovery_retries() {
+        let mut calls = 0;
+        let mut sleeps = Vec::new();
+
+        let result = execute_with_recovery_and_sleep(
+            || {
+                calls += 1;
+                if calls <= 2 {
+                    Err(DomainError::External("API down".to_string()))
+                } else {
+                    Ok(calls)
+                }
+            },
+            |duration| sleeps.push(duration.as_millis()),
+        );
+
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(sleeps, vec![1000, 2000]);
+    }
+
+    #[test]
+    fn test_execute_with_recovery_gives_up() {
+        // Retries stop after max_attempts
+        let mut calls = 0;
+        let result: DomainResult<()> = execute_with_recovery_and_sleep(
+            || {
+                calls += 1;
+                Err(DomainError::External("API down".to_string()))
+            },
+            |_| {},
+        );
+        assert!(matches!(result, Err(DomainError::External(_))));
+        assert_eq!(calls, 3);
+
+        // Non-retryable errors are returned immediately
+        let mut calls = 0;
+        let result: DomainResult<()> = execute_with_recovery_and_sleep(
+            || {
+                calls += 1;
+                Err(DomainError::InvalidInput("bad data".to_string()))
+            },
+            |_| panic!("should not sleep"),
+        );
+        assert!(matches!(result, Err(DomainError::InvalidInput(_))));
+        assert_eq!(calls, 1);
+    }
+
     #[test]
     fn test_error_conversion() {
         let file_error = crate::domain::file::FileError::NotFound("test.jpg".to_string());
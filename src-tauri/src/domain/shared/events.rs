@@ -190,7 +190,7 @@ impl EventBus {
             if listener.can_handle(&event.event_type) {
                 if let Err(e) = listener.handle_event(&event) {
                     // Log error but don't fail the publish
-                    eprintln!("Error in event listener: {}", e);
+                    log::error!("Error in event listener: {}", e);
                 }
             }
         }
@@ -226,6 +226,11 @@ impl EventBus {
             .collect()
     }
 
+    /// Number of events kept in history
+    pub fn event_count(&self) -> usize {
+        self.event_history.len()
+    }
+
     /// Clear event history
     pub fn clear_history(&mut self) {
         self.event_history.clear();
@@ -314,6 +319,21 @@ pub fn compression_failed_event(
     )
 }
 
+pub fn configuration_changed_event(
+    setting_name: String,
+    old_value: String,
+    new_value: String,
+) -> DomainEvent {
+    DomainEvent::new(
+        EventType::ConfigurationChanged,
+        EventPayload::ConfigurationChanged {
+            setting_name,
+            old_value,
+            new_value,
+        },
+    )
+}
+
 pub fn error_event(operation: String, error_type: String, error_message: String) -> DomainEvent {
     DomainEvent::new(
         EventType::Error,
@@ -335,6 +355,16 @@ pub fn info_event(operation: String, info_message: String) -> DomainEvent {
     )
 }
 
+pub fn temp_files_cleaned_up_event(count: u32, total_size_freed: u64) -> DomainEvent {
+    DomainEvent::new(
+        EventType::TempFilesCleanedUp,
+        EventPayload::TempFilesCleanedUp {
+            count,
+            total_size_freed,
+        },
+    )
+}
+
 /// Simple console event listener for debugging
 pub struct ConsoleEventListener {
     min_severity: EventSeverity,
@@ -356,9 +386,18 @@ impl EventListener for ConsoleEventListener {
                 .and_then(|t| t.split('.').next())
                 .unwrap_or("--:--:--");
 
-            println!(
+            let level = match event.severity() {
+                EventSeverity::Debug => log::Level::Debug,
+                EventSeverity::Info => log::Level::Info,
+                EventSeverity::Warning => log::Level::Warn,
+                EventSeverity::Error => log::Level::Error,
+            };
+            log::log!(
+                level,
                 "[{}] {:?} - {:?}",
-                timestamp, event.event_type, event.payload
+                timestamp,
+                event.event_type,
+                event.payload
             );
         }
         Ok(())
@@ -9,15 +9,23 @@ pub mod events;
 pub mod utils;
 
 // Re-export core types and functions for easy access
-pub use config::{AppConfig, CompressionConfig, ConfigManager, PerformanceConfig, SecurityConfig};
-pub use error::{get_recovery_strategy, DomainError, DomainResult, ErrorRecovery};
+pub use config::{
+    default_config_path, AppConfig, CompressionConfig, ConfigFieldError, ConfigManager,
+    ConfigWatchGuard, PerformanceConfig, SecurityConfig,
+};
+pub use error::{
+    execute_with_recovery, execute_with_recovery_and_sleep, get_recovery_strategy, DomainError,
+    DomainResult, ErrorRecovery,
+};
 pub use events::{
     compression_completed_event,
     compression_failed_event,
+    configuration_changed_event,
     error_event,
     // Convenience event creators
     file_processed_event,
     info_event,
+    temp_files_cleaned_up_event,
     ConsoleEventListener,
     DomainEvent,
     EventBus,
@@ -28,10 +36,14 @@ pub use events::{
 };
 
 // Re-export commonly used utilities with shorter paths
-pub use utils::hash::{content_equal, content_id, simple_hash};
-pub use utils::path::{get_extension, is_safe_path, normalize_extension, validate_path_depth};
+pub use utils::hash::{content_equal, content_id, content_sha256, simple_hash};
+pub use utils::path::{
+    expand_home, get_extension, is_safe_path, normalize_extension, validate_path_depth,
+};
 pub use utils::size::{calculate_compression_ratio, calculate_savings_percent, format_bytes};
-pub use utils::string::{generate_temp_filename, sanitize_filename, truncate_with_ellipsis};
+pub use utils::string::{
+    generate_temp_filename, sanitize_filename, truncate_with_ellipsis, unique_suffix,
+};
 pub use utils::time::{current_timestamp, format_duration_ms};
 pub use utils::validation::{validate_dimensions, validate_format, validate_quality};
 
@@ -47,7 +59,16 @@ pub fn format_file_size(bytes: u64) -> String {
 pub fn initialize() -> DomainResult<AppState> {
     // Perform any necessary initialization
     log::info!("Initializing shared domain");
-    Ok(AppState::new())
+    // Réglages enregistrés, sinon les valeurs par défaut
+    let config_path = default_config_path();
+    let config = AppConfig::load_with_overrides(Some(&config_path)).unwrap_or_else(|e| {
+        log::warn!("Ignoring saved configuration: {}", e);
+        AppConfig::default()
+    });
+    let state = AppState::with_config(config).with_config_path(config_path);
+    PathUtils::apply_security_config(&state.get_config().security);
+    configure_global_cache(&state.get_config().performance);
+    Ok(state)
 }
 
 /// Cleanup shared domain resources
@@ -67,7 +88,7 @@ pub fn get_version_info() -> VersionInfo {
 }
 
 /// Application version information
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct VersionInfo {
     pub version: String,
     pub build_date: String,
@@ -87,12 +108,16 @@ impl std::fmt::Display for VersionInfo {
 }
 
 // Global application state (if needed)
+use crate::domain::compression::configure_global_cache;
+use crate::domain::file::PathUtils;
+use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
 
 /// Shared application state
 pub struct AppState {
     pub config: Arc<RwLock<AppConfig>>,
     pub event_bus: Arc<RwLock<EventBus>>,
+    pub config_path: PathBuf,
 }
 
 impl AppState {
@@ -101,6 +126,7 @@ impl AppState {
         Self {
             config: Arc::new(RwLock::new(AppConfig::default())),
             event_bus: Arc::new(RwLock::new(EventBus::new())),
+            config_path: default_config_path(),
         }
     }
 
@@ -109,9 +135,16 @@ impl AppState {
         Self {
             config: Arc::new(RwLock::new(config)),
             event_bus: Arc::new(RwLock::new(EventBus::new())),
+            config_path: default_config_path(),
         }
     }
 
+    /// Persist configuration changes to a custom file
+    pub fn with_config_path<P: Into<PathBuf>>(mut self, config_path: P) -> Self {
+        self.config_path = config_path.into();
+        self
+    }
+
     /// Get configuration (read-only)
     pub fn get_config(&self) -> std::sync::RwLockReadGuard<'_, AppConfig> {
         self.config.read().unwrap()
@@ -128,12 +161,73 @@ impl AppState {
         Ok(())
     }
 
+    /// Validate, save and apply a full configuration, publishing one event per changed setting
+    pub fn replace_config(&self, new_config: AppConfig) -> DomainResult<()> {
+        new_config.validate()?;
+        new_config.save_to_file(&self.config_path)?;
+        self.apply_config(new_config)
+    }
+
+    /// Validate and apply a configuration without saving it, publishing one event per
+    /// changed setting
+    pub fn apply_config(&self, new_config: AppConfig) -> DomainResult<()> {
+        new_config.validate()?;
+
+        // Verrou tenu le temps de l'échange seulement, les événements sont publiés après
+        let events = {
+            let mut config = self.config.write().unwrap();
+            let events = config::config_changes(&config, &new_config);
+            PathUtils::apply_security_config(&new_config.security);
+            configure_global_cache(&new_config.performance);
+            *config = new_config;
+            events
+        };
+
+        for event in events {
+            self.publish_event(event)?;
+        }
+        Ok(())
+    }
+
+    /// Apply valid edits of the config file while the app runs
+    ///
+    /// Invalid files are rejected and the current configuration is kept. Watching stops
+    /// when the returned guard is dropped.
+    pub fn watch_config_file(&self) -> DomainResult<ConfigWatchGuard> {
+        let state = AppState {
+            config: Arc::clone(&self.config),
+            event_bus: Arc::clone(&self.event_bus),
+            config_path: self.config_path.clone(),
+        };
+
+        // Pas de replace_config : réenregistrer le fichier relancerait la surveillance
+        config::watch_config_file(&self.config_path, move |path| {
+            let result = AppConfig::load_with_overrides(Some(path))
+                .and_then(|new_config| state.apply_config(new_config));
+            if let Err(e) = result {
+                log::warn!("Rejected config change: {}", e);
+            }
+        })
+    }
+
+    /// Restore the default configuration, save it and publish one event per changed setting
+    pub fn reset_config_to_defaults(&self) -> DomainResult<AppConfig> {
+        let defaults = AppConfig::default();
+        self.replace_config(defaults.clone())?;
+        Ok(defaults)
+    }
+
     /// Publish event to event bus
     pub fn publish_event(&self, event: DomainEvent) -> DomainResult<()> {
         let mut event_bus = self.event_bus.write().unwrap();
         event_bus.publish(event)
     }
 
+    /// Number of events kept in the event bus history
+    pub fn event_count(&self) -> usize {
+        self.event_bus.read().unwrap().event_count()
+    }
+
     /// Get recent events
     pub fn get_recent_events(&self, limit: usize) -> Vec<DomainEvent> {
         let event_bus = self.event_bus.read().unwrap();
@@ -197,6 +291,70 @@ mod integration_tests {
         assert_eq!(recent_events.len(), 1);
     }
 
+    #[test]
+    fn test_reset_config_to_defaults() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.json");
+        let state = AppState::new().with_config_path(&config_path);
+
+        state
+            .update_config(|config| {
+                config.max_file_size = 2048;
+                config.compression.default_quality = 42;
+                Ok(())
+            })
+            .unwrap();
+
+        let restored = state.reset_config_to_defaults().unwrap();
+        let defaults = serde_json::to_value(AppConfig::default()).unwrap();
+        assert_eq!(serde_json::to_value(&restored).unwrap(), defaults);
+        assert_eq!(
+            serde_json::to_value(&*state.get_config()).unwrap(),
+            defaults
+        );
+
+        // Fichier réécrit, un événement par réglage de premier niveau modifié
+        let saved = AppConfig::load_from_file(&config_path).unwrap();
+        assert_eq!(serde_json::to_value(saved).unwrap(), defaults);
+        let events = state.get_recent_events(10);
+        assert_eq!(events.len(), 2);
+        assert!(events
+            .iter()
+            .all(|event| matches!(event.event_type, EventType::ConfigurationChanged)));
+    }
+
+    #[test]
+    fn test_watch_config_file_updates_app_state() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.json");
+        AppConfig::default().save_to_file(&config_path).unwrap();
+
+        let state = AppState::new().with_config_path(&config_path);
+        let _guard = state.watch_config_file().unwrap();
+
+        let updated = AppConfig {
+            max_file_size: 8192,
+            ..Default::default()
+        };
+        updated.save_to_file(&config_path).unwrap();
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while state.get_config().max_file_size != 8192 && std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+
+        assert_eq!(state.get_config().max_file_size, 8192);
+        assert!(state
+            .get_recent_events(10)
+            .iter()
+            .any(|event| matches!(event.event_type, EventType::ConfigurationChanged)));
+
+        // Fichier invalide : la dernière configuration valide reste en place
+        std::fs::write(&config_path, "{ not json").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        assert_eq!(state.get_config().max_file_size, 8192);
+    }
+
     #[test]
     fn test_error_conversion_chain() {
         // Test that domain errors can be converted from all sub-domains
@@ -1,5 +1,5 @@
 use crate::domain::shared::error::{DomainError, DomainResult};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Path utilities and validation
 pub mod path {
@@ -37,6 +37,15 @@ pub mod path {
         true
     }
 
+    /// Replace a leading `~` with the user's home directory
+    pub fn expand_home<P: AsRef<Path>>(path: P) -> PathBuf {
+        let path_ref = path.as_ref();
+        match (path_ref.strip_prefix("~"), dirs::home_dir()) {
+            (Ok(rest), Some(home)) => home.join(rest),
+            _ => path_ref.to_path_buf(),
+        }
+    }
+
     /// Validate path depth to prevent deeply nested path attacks
     pub fn validate_path_depth<P: AsRef<Path>>(path: P, max_depth: u32) -> DomainResult<()> {
         let depth = path.as_ref().components().count() as u32;
@@ -115,8 +124,11 @@ pub mod size {
     }
 
     /// Parse human-readable size string to bytes
+    ///
+    /// `KiB`/`MiB`/`GiB` are binary (1024-based) and `kB` is decimal (1000-based).
+    /// The uppercase `KB`/`MB`/`GB` forms stay 1024-based for backward compatibility.
     pub fn parse_size_string(size_str: &str) -> crate::domain::DomainResult<u64> {
-        let size_str = size_str.trim().to_uppercase();
+        let size_str = size_str.trim();
 
         if size_str.is_empty() {
             return Err(crate::domain::DomainError::InvalidInput(
@@ -129,7 +141,7 @@ pub mod size {
             (&size_str[..pos], &size_str[pos..])
         } else {
             // No unit, assume bytes
-            (size_str.as_str(), "B")
+            (size_str, "B")
         };
 
         let number: f64 = number_part.trim().parse().map_err(|_| {
@@ -145,19 +157,24 @@ pub mod size {
             ));
         }
 
-        let multiplier = match unit_part.trim() {
-            "B" => 1,
-            "KB" => 1024,
-            "MB" => 1024 * 1024,
-            "GB" => 1024 * 1024 * 1024,
-            "TB" => 1024_u64.pow(4),
-            "PB" => 1024_u64.pow(5),
-            _ => {
-                return Err(crate::domain::DomainError::InvalidInput(format!(
-                    "Unknown unit: {}",
-                    unit_part
-                )))
-            }
+        let unit = unit_part.trim();
+        let multiplier = match unit {
+            // Préfixe SI en minuscule : décimal sans ambiguïté
+            "kB" => 1000,
+            _ => match unit.to_uppercase().as_str() {
+                "B" => 1,
+                "KIB" | "KB" => 1024,
+                "MIB" | "MB" => 1024 * 1024,
+                "GIB" | "GB" => 1024 * 1024 * 1024,
+                "TIB" | "TB" => 1024_u64.pow(4),
+                "PIB" | "PB" => 1024_u64.pow(5),
+                _ => {
+                    return Err(crate::domain::DomainError::InvalidInput(format!(
+                        "Unknown unit: {}",
+                        unit_part
+                    )))
+                }
+            },
         };
 
         Ok((number * multiplier as f64) as u64)
@@ -168,9 +185,15 @@ pub mod size {
 pub mod string {
     use super::*;
 
+    /// Longest filename (in bytes) accepted by common filesystems
+    const MAX_FILENAME_BYTES: usize = 255;
+
     /// Sanitize filename by removing dangerous characters
+    ///
+    /// Never returns an empty name: a stem left with only `_`/`.` becomes `file`, and the
+    /// result is capped to `MAX_FILENAME_BYTES` while keeping the extension.
     pub fn sanitize_filename(filename: &str) -> String {
-        filename
+        let sanitized = filename
             .chars()
             .map(|c| match c {
                 // Replace dangerous characters with underscore
@@ -182,7 +205,43 @@ pub mod string {
             })
             .collect::<String>()
             .trim()
-            .to_string()
+            .to_string();
+
+        // Une extension courte et alphanumérique est conservée telle quelle
+        let (stem, extension) = match sanitized.rsplit_once('.') {
+            Some((stem, extension))
+                if !extension.is_empty()
+                    && extension.len() <= 16
+                    && extension.chars().all(|c| c.is_ascii_alphanumeric()) =>
+            {
+                (stem, Some(extension))
+            }
+            _ => (sanitized.as_str(), None),
+        };
+
+        let stem = if stem
+            .chars()
+            .all(|c| c == '_' || c == '.' || c.is_whitespace())
+        {
+            "file"
+        } else {
+            stem
+        };
+
+        let max_stem_bytes = match extension {
+            Some(extension) => MAX_FILENAME_BYTES - extension.len() - 1,
+            None => MAX_FILENAME_BYTES,
+        };
+        let mut stem_end = stem.len().min(max_stem_bytes);
+        while !stem.is_char_boundary(stem_end) {
+            stem_end -= 1;
+        }
+        let stem = &stem[..stem_end];
+
+        match extension {
+            Some(extension) => format!("{}.{}", stem, extension),
+            None => stem.to_string(),
+        }
     }
 
     /// Truncate string to maximum length with ellipsis
@@ -202,7 +261,22 @@ pub mod string {
         let sanitized_prefix = sanitize_filename(prefix);
         let sanitized_ext = path::normalize_extension(extension);
 
-        format!("{}_{}.{}", sanitized_prefix, timestamp, sanitized_ext)
+        format!(
+            "{}_{}_{}.{}",
+            sanitized_prefix,
+            timestamp,
+            unique_suffix(),
+            sanitized_ext
+        )
+    }
+
+    /// Suffix unique across threads and processes, to tell apart names from the same millisecond
+    pub fn unique_suffix() -> String {
+        use std::sync::atomic::{AtomicU64, Ordering};
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+        let counter = COUNTER.fetch_add(1, Ordering::SeqCst);
+        format!("{}_{}", std::process::id(), counter)
     }
 
     /// Extract base filename without extension
@@ -248,19 +322,31 @@ pub mod time {
 
 /// Hash utilities
 pub mod hash {
+    use sha2::{Digest, Sha256};
     use std::collections::hash_map::DefaultHasher;
     use std::hash::{Hash, Hasher};
 
     /// Calculate simple hash of data
+    ///
+    /// Fast but not stable across Rust versions and prone to collisions:
+    /// only use it for in-process, non-security purposes.
     pub fn simple_hash(data: &[u8]) -> u64 {
         let mut hasher = DefaultHasher::new();
         data.hash(&mut hasher);
         hasher.finish()
     }
 
+    /// Calculate SHA-256 of data as a lowercase hex string
+    pub fn content_sha256(data: &[u8]) -> String {
+        Sha256::digest(data)
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
     /// Generate content-based ID from data
     pub fn content_id(data: &[u8]) -> String {
-        format!("content_{:x}", simple_hash(data))
+        format!("content_{}", content_sha256(data))
     }
 
     /// Check if two byte arrays have the same content
@@ -347,6 +433,17 @@ mod tests {
         assert!(!path::is_safe_path("file\0.jpg")); // null byte
     }
 
+    #[test]
+    fn test_expand_home() {
+        let Some(home) = dirs::home_dir() else {
+            return;
+        };
+        assert_eq!(path::expand_home("~/Documents"), home.join("Documents"));
+        assert_eq!(path::expand_home("~"), home);
+        assert_eq!(path::expand_home("/etc"), PathBuf::from("/etc"));
+        assert_eq!(path::expand_home("~user/x"), PathBuf::from("~user/x"));
+    }
+
     #[test]
     fn test_format_bytes() {
         assert_eq!(size::format_bytes(0), "0 B");
@@ -376,6 +473,13 @@ mod tests {
         assert!(size::parse_size_string("invalid").is_err());
         assert!(size::parse_size_string("-100").is_err());
         assert!(size::parse_size_string("1 XB").is_err());
+
+        // Binary and decimal conventions
+        assert_eq!(size::parse_size_string("1KiB").unwrap(), 1024);
+        assert_eq!(size::parse_size_string("2 MiB").unwrap(), 2 * 1024 * 1024);
+        assert_eq!(size::parse_size_string("1kB").unwrap(), 1000);
+        assert_eq!(size::parse_size_string("1.5kB").unwrap(), 1500);
+        assert_eq!(size::parse_size_string("1 MB").unwrap(), 1048576);
     }
 
     #[test]
@@ -391,6 +495,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_sanitize_filename_fallback_and_length() {
+        assert_eq!(string::sanitize_filename("<<<"), "file");
+        assert_eq!(string::sanitize_filename(""), "file");
+        assert_eq!(string::sanitize_filename("??.png"), "file.png");
+
+        let long_name = format!("{}.webp", "é".repeat(300));
+        let sanitized = string::sanitize_filename(&long_name);
+        assert!(sanitized.len() <= 255);
+        assert!(sanitized.ends_with(".webp"));
+        assert!(sanitized.starts_with("éé"));
+    }
+
     #[test]
     fn test_truncate_with_ellipsis() {
         assert_eq!(string::truncate_with_ellipsis("short", 10), "short");
@@ -411,6 +528,25 @@ mod tests {
         assert_ne!(filename, filename2); // Should be unique
     }
 
+    #[test]
+    fn test_generate_temp_filename_concurrent() {
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                std::thread::spawn(|| {
+                    (0..50)
+                        .map(|_| string::generate_temp_filename("test", "jpg"))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        let names: std::collections::HashSet<_> = handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect();
+        assert_eq!(names.len(), 8 * 50);
+    }
+
     #[test]
     fn test_format_duration_ms() {
         assert_eq!(time::format_duration_ms(500), "500ms");
@@ -437,6 +573,22 @@ mod tests {
         assert!(id1.starts_with("content_"));
     }
 
+    #[test]
+    fn test_content_sha256() {
+        assert_eq!(
+            hash::content_sha256(b"hello world"),
+            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
+        );
+        assert_eq!(
+            hash::content_sha256(b"hello world"),
+            hash::content_sha256(b"hello world")
+        );
+        assert_ne!(
+            hash::content_sha256(b"hello world"),
+            hash::content_sha256(b"different data")
+        );
+    }
+
     #[test]
     fn test_validation() {
         // Dimensions
@@ -5,13 +5,18 @@ mod commands;
 pub mod database;
 pub mod domain;
 
-use crate::domain::initialize;
+use crate::domain::{initialize, TempCleanupScheduler};
 use commands::{
-    clear_app_temporary_files, compress_batch, compress_image, generate_preview,
-    get_compression_estimation, get_compression_prediction, get_file_information, get_stats_count,
-    get_stats_summary, init_database, record_compression_result, record_compression_stat,
-    reset_compression_stats, save_all_to_downloads, save_to_downloads, seed_compression_database,
-    select_image_files, test_compression_prediction, test_database_connection,
+    analyze_file, calibrate_predictions, clear_app_temporary_files, compress_batch,
+    compress_data_uri, compress_image, delete_output_file, estimate_batch, generate_preview,
+    get_app_config, get_app_info, get_blurhash, get_compression_by_image_type,
+    get_compression_estimation, get_compression_prediction, get_compression_prediction_accuracy,
+    get_compression_presets, get_file_information, get_image_dimensions, get_stats_count,
+    get_stats_summary, get_supported_formats, init_database, prune_compression_stats,
+    quality_size_curve, read_exif, record_compression_result, record_compression_stat,
+    reset_compression_stats, reset_config_to_defaults, retry_failed, save_all_to_downloads,
+    save_to_downloads, scan_directory_for_images, seed_compression_database, select_image_files,
+    strip_location, test_compression_prediction, test_database_connection, update_app_config,
 };
 
 // Garde la fonction greet pour l'instant
@@ -21,35 +26,82 @@ fn greet(name: &str) -> String {
 }
 
 fn main() {
+    // RUST_LOG permet d'ajuster le niveau, "info" par défaut
+    let _ = env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
+        .try_init();
+
     // Initialize application state
     let app_state = initialize().expect("Failed to initialize application");
 
+    // Nettoyage périodique des fichiers temporaires, arrêté à la fermeture
+    let temp_dir = app_state.get_config().get_temp_path();
+    let mut temp_cleanup = Some(TempCleanupScheduler::start(
+        temp_dir,
+        app_state.config.clone(),
+        app_state.event_bus.clone(),
+    ));
+
+    // Les modifications du fichier de config s'appliquent sans redémarrer
+    let config_watch = app_state
+        .watch_config_file()
+        .map_err(|e| log::warn!("Config file will not be watched: {}", e))
+        .ok();
+
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
+        .manage(config_watch)
         .manage(app_state)
         .invoke_handler(tauri::generate_handler![
             greet,
             compress_image,
             compress_batch,
+            retry_failed,
+            compress_data_uri,
+            estimate_batch,
+            quality_size_curve,
             select_image_files,
             save_to_downloads,
+            scan_directory_for_images,
+            analyze_file,
+            get_image_dimensions,
+            get_blurhash,
+            read_exif,
+            strip_location,
             save_all_to_downloads,
             generate_preview,
             clear_app_temporary_files,
+            delete_output_file,
             get_file_information,
             get_stats_count,
+            get_supported_formats,
             get_stats_summary,
             get_compression_estimation,
+            calibrate_predictions,
+            get_compression_presets,
+            get_compression_prediction_accuracy,
+            get_compression_by_image_type,
             get_compression_prediction,
             record_compression_stat,
             reset_compression_stats,
+            prune_compression_stats,
+            get_app_config,
+            get_app_info,
+            update_app_config,
+            reset_config_to_defaults,
             init_database,
             seed_compression_database,
             test_compression_prediction,
             test_database_connection,
             record_compression_result
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(move |_app, event| {
+            if let tauri::RunEvent::Exit = event {
+                if let Some(mut scheduler) = temp_cleanup.take() {
+                    scheduler.stop();
+                }
+            }
+        });
 }